@@ -0,0 +1,78 @@
+use ccr::types::{identify_session_blocks, identify_session_blocks_parallel};
+use ccr::{UsageEntry, UsageEntryData};
+use chrono::{Duration, TimeZone, Utc};
+use colored::Colorize;
+use std::sync::Arc;
+use std::time::Instant;
+
+const ENTRIES: usize = 300_000;
+const RUNS: usize = 5;
+
+// Synthetic lifetime history: bursts of activity separated by idle nights
+fn generate_entries(count: usize) -> Vec<Arc<UsageEntry>> {
+    let start = Utc.with_ymd_and_hms(2023, 1, 1, 9, 0, 0).unwrap();
+    let mut time = start;
+
+    (0..count)
+        .map(|i| {
+            // A burst of 200 entries a minute apart, then a 10 hour break
+            time += if i % 200 == 0 {
+                Duration::hours(10)
+            } else {
+                Duration::minutes(1)
+            };
+            let line = format!(
+                r#"{{"timestamp":"{}","costUSD":0.01,"requestId":"req-{}","message":{{"id":"msg-{}"}}}}"#,
+                time.to_rfc3339(),
+                i,
+                i
+            );
+            let data: UsageEntryData = serde_json::from_str(&line).unwrap();
+            Arc::new(UsageEntry::from_data(data, "bench-session".into()))
+        })
+        .collect()
+}
+
+fn bench<F>(name: &str, entries: &[Arc<UsageEntry>], build: F) -> u128
+where
+    F: Fn(&[Arc<UsageEntry>]) -> usize,
+{
+    println!("\n{}", name.green());
+    let mut times = Vec::new();
+    for run in 1..=RUNS {
+        let start = Instant::now();
+        let blocks = std::hint::black_box(build(entries));
+        let elapsed = start.elapsed().as_micros();
+        times.push(elapsed);
+        println!("  Run {}: {} μs ({} blocks)", run, elapsed, blocks);
+    }
+    times.iter().sum::<u128>() / times.len() as u128
+}
+
+fn main() {
+    println!(
+        "{}",
+        "=== Session Block Identification Benchmark ==="
+            .green()
+            .bold()
+    );
+    println!("Entries: {}", ENTRIES);
+
+    let entries = generate_entries(ENTRIES);
+
+    let sequential = bench("1. Sequential (statusline path):", &entries, |e| {
+        identify_session_blocks(e).len()
+    });
+    let parallel = bench("2. Parallel (split at idle gaps):", &entries, |e| {
+        identify_session_blocks_parallel(e).len()
+    });
+
+    println!("\n{}", "=== Results ===".green().bold());
+    println!("Sequential: {} μs", sequential);
+    println!("Parallel:   {} μs", parallel);
+    println!(
+        "Speedup:    {:.1}x on {} threads",
+        sequential as f64 / parallel.max(1) as f64,
+        rayon::current_num_threads()
+    );
+}
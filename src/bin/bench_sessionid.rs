@@ -51,6 +51,22 @@ fn bench_equality_different_content(iterations: usize) -> u128 {
     duration
 }
 
+fn bench_equality_different_length(iterations: usize) -> u128 {
+    let id1 = SessionId::from("test-session-12345");
+    let id2 = SessionId::from("agent-test-session-12345");
+
+    let start = Instant::now();
+    let mut count = 0;
+    for _ in 0..iterations {
+        if std::hint::black_box(&id1) != std::hint::black_box(&id2) {
+            count += 1;
+        }
+    }
+    let duration = start.elapsed().as_nanos() / iterations as u128;
+    std::hint::black_box(count);
+    duration
+}
+
 fn bench_string_equality(iterations: usize) -> u128 {
     let s1 = "test-session-12345".to_string();
     let s2 = "test-session-12345".to_string();
@@ -117,8 +133,18 @@ fn main() {
     }
     let avg_diff_content = times.iter().sum::<u128>() / times.len() as u128;
 
+    // Benchmark different length (rejected by the length pre-check)
+    println!("\n{}", "4. Different length:".green());
+    let mut times = Vec::new();
+    for run in 1..=5 {
+        let time = bench_equality_different_length(ITERATIONS);
+        times.push(time);
+        println!("  Run {}: {} ns/op", run, time);
+    }
+    let avg_diff_length = times.iter().sum::<u128>() / times.len() as u128;
+
     // Benchmark plain String equality for comparison
-    println!("\n{}", "4. Plain String equality (baseline):".green());
+    println!("\n{}", "5. Plain String equality (baseline):".green());
     let mut times = Vec::new();
     for run in 1..=5 {
         let time = bench_string_equality(ITERATIONS);
@@ -138,6 +164,10 @@ fn main() {
         "Different content:                {} ns/op",
         avg_diff_content
     );
+    println!(
+        "Different length:                 {} ns/op",
+        avg_diff_length
+    );
     println!("Plain String comparison:          {} ns/op", avg_string);
 
     // Analysis
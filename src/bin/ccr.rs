@@ -1,27 +1,201 @@
-use colored::{ColoredString, Colorize};
-use std::io;
-use std::path::Path;
+use chrono::{Local, Utc};
+use colored::Colorize;
+use std::io::{self, IsTerminal};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::Arc;
+#[cfg(unix)]
+use std::time::Duration;
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task;
 
 // Import from organized modules
 use ccr::Result;
+use ccr::config;
 use ccr::error::CcrError;
-use ccr::types::{BurnRate, Cost, RemainingTime, StatuslineHookJson};
-use ccr::utils::{get_claude_paths, get_git_branch, load_all_data, load_transcript_usage};
+use ccr::statusline::{OutputFormat, StatusLine};
+use ccr::template::StatusTemplate;
+use ccr::types::{
+    Budget, BurnBasis, BurnCost, BurnRate, BurnThresholds, ContextThresholds, ContextTokens,
+    ContextWindow, Cost, DAILY_CSV_HEADER, DirSource, ENTRY_CSV_HEADER, MergedUsageSnapshot,
+    RemainingTime, ReportPeriod, SessionId, SessionScope, StatuslineHookJson, TimeStyle,
+    TokenBurnRate, TokenTotals, budget_alert_marker_path, claim_daily_alert, format_report_table,
+    install_pricing_table, project_key_from_path, short_model_name,
+};
+use ccr::utils::{
+    LoadOptions, get_claude_paths, get_git_status, hook_transcript_usage, init_global_thread_pool,
+    load_all_data_with, load_jsonl_file, projects_dir, remote_pricing_from_env,
+};
+use ccr::{ModelId, ModelPricing};
+
+/// Number of entries sampled when checking timestamp offsets for --stats
+const TIMESTAMP_SAMPLE_SIZE: usize = 1000;
+
+/// Minutes before the block ends at which the "block ending" hint appears
+const DEFAULT_ENDING_SOON_MINUTES: i64 = 10;
+
+/// Longest hook payload line the server accepts, in bytes
+#[cfg(unix)]
+const MAX_REQUEST_LINE_BYTES: u64 = 64 * 1024;
+
+/// Server connections that send no complete line for this long are closed
+#[cfg(unix)]
+const CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output mode selected on the command line
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    /// Print the statusline (default, used by the Claude Code hook)
+    Statusline,
+    /// Print diagnostic statistics about the loaded data
+    Stats,
+    /// Report cost and tokens for a single JSONL file
+    File(PathBuf),
+    /// Print the entries of the active block with their costs
+    DebugBlock,
+    /// Answer hook JSON lines on a Unix domain socket
+    #[cfg(unix)]
+    Serve(PathBuf),
+    /// Check the setup and report pass/fail per check
+    SelfTest,
+    /// Print the whole history as CSV with one row per local day
+    DailyCsv,
+    /// Print the whole history's cost per calendar period and model
+    Report,
+    /// Write every raw entry as CSV
+    Export,
+}
+
+/// Parsed command-line arguments
+#[derive(Debug)]
+struct Args {
+    mode: Mode,
+    /// Report non-fatal loading problems on stderr (--verbose, else CCR_DEBUG)
+    verbose: bool,
+    /// How the statusline is printed (--format, else CCR_FORMAT)
+    format: OutputFormat,
+    /// Disable colors regardless of the environment
+    no_color: bool,
+    /// Grouping of the cost report (--period)
+    period: ReportPeriod,
+    /// File the export is written to instead of stdout (--output)
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Self> {
+        let mut args = Args {
+            mode: Mode::Statusline,
+            verbose: config::env_flag("CCR_DEBUG"),
+            format: OutputFormat::from_env(),
+            no_color: false,
+            period: ReportPeriod::default(),
+            output: None,
+        };
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--stats" => args.mode = Mode::Stats,
+                "--debug-block" => args.mode = Mode::DebugBlock,
+                "--selftest" => args.mode = Mode::SelfTest,
+                "--daily-csv" => args.mode = Mode::DailyCsv,
+                "report" => args.mode = Mode::Report,
+                "export" => args.mode = Mode::Export,
+                // CSV is the only export format
+                "--csv" => {}
+                "--verbose" | "-v" => args.verbose = true,
+                "--no-color" => args.no_color = true,
+                "--format" => {
+                    let format = iter.next().ok_or_else(|| CcrError::InvalidArgument {
+                        message: "--format requires text or json".to_string(),
+                    })?;
+                    args.format = format
+                        .parse()
+                        .map_err(|message| CcrError::InvalidArgument { message })?;
+                }
+                "--period" => {
+                    let period = iter.next().ok_or_else(|| CcrError::InvalidArgument {
+                        message: "--period requires day, week or month".to_string(),
+                    })?;
+                    args.period = period
+                        .parse()
+                        .map_err(|message| CcrError::InvalidArgument { message })?;
+                }
+                "--output" => {
+                    let path = iter.next().ok_or_else(|| CcrError::InvalidArgument {
+                        message: "--output requires a path".to_string(),
+                    })?;
+                    args.output = Some(PathBuf::from(path));
+                }
+                "--file" => {
+                    let path = iter.next().ok_or_else(|| CcrError::InvalidArgument {
+                        message: "--file requires a path".to_string(),
+                    })?;
+                    args.mode = Mode::File(PathBuf::from(path));
+                }
+                #[cfg(unix)]
+                "--serve" => {
+                    let path = iter.next().ok_or_else(|| CcrError::InvalidArgument {
+                        message: "--serve requires a socket path".to_string(),
+                    })?;
+                    args.mode = Mode::Serve(PathBuf::from(path));
+                }
+                #[cfg(not(unix))]
+                "--serve" => {
+                    return Err(CcrError::InvalidArgument {
+                        message: "--serve needs Unix domain sockets, which this platform lacks"
+                            .to_string(),
+                    });
+                }
+                other => {
+                    return Err(CcrError::InvalidArgument {
+                        message: format!("unknown argument '{}'", other),
+                    });
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse()?;
+
     // Configure rayon thread pool for optimal performance
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_cpus::get())
-        .thread_name(|i| format!("ccr-worker-{}", i))
-        .build_global()
-        .map_err(CcrError::ThreadPoolInit)?;
+    init_global_thread_pool(num_cpus::get());
 
-    // Force colored output even when not in a TTY
-    colored::control::set_override(true);
+    // Opt-in remote prices, installed before anything is costed
+    if let Some(table) = task::spawn_blocking(remote_pricing_from_env).await? {
+        install_pricing_table(table);
+    }
 
-    // Read input JSON directly from stdin using stream processing
-    let hook_data: StatuslineHookJson = serde_json::from_reader(io::stdin())?;
+    // Color on a terminal, or when forced for hooks that pipe the output
+    colored::control::set_override(config::color_enabled(
+        args.no_color,
+        io::stdout().is_terminal(),
+    ));
+
+    // Single-file report does not need the hook payload
+    if let Mode::File(ref path) = args.mode {
+        print_file_report(path)?;
+        return Ok(());
+    }
+
+    // Self-test reports missing paths as a failed check instead of an error
+    if args.mode == Mode::SelfTest {
+        if !run_self_test() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // Check Claude paths exist
     let claude_paths = get_claude_paths();
@@ -29,154 +203,818 @@ async fn main() -> Result<()> {
         return Err(CcrError::ClaudePathNotFound);
     }
 
-    // Load usage snapshot and context info
-    let (usage_snapshot, git_branch, transcript_usage) = tokio::join!(
-        load_all_data(&claude_paths, &hook_data.session_id),
-        get_git_branch(Path::new(&hook_data.cwd)),
-        load_transcript_usage(Path::new(&hook_data.transcript_path))
+    // History export does not need the hook payload either
+    if matches!(args.mode, Mode::DailyCsv | Mode::Report | Mode::Export) {
+        let options = LoadOptions {
+            unfiltered: true,
+            ..LoadOptions::from_env()
+        };
+        let usage_snapshot =
+            load_all_data_with(&claude_paths, &SessionId::default(), &options).await?;
+        if args.verbose {
+            report_diagnostics(&usage_snapshot);
+        }
+        if args.mode == Mode::Export {
+            let mut csv = String::from(ENTRY_CSV_HEADER);
+            csv.push('\n');
+            for row in usage_snapshot.entry_csv_rows() {
+                csv.push_str(&row);
+                csv.push('\n');
+            }
+            match args.output {
+                Some(ref path) => {
+                    std::fs::write(path, csv).map_err(|source| CcrError::FileWrite {
+                        path: path.clone(),
+                        source,
+                    })?
+                }
+                None => print!("{}", csv),
+            }
+        } else if args.mode == Mode::Report {
+            let rows = usage_snapshot.period_costs(args.period);
+            match args.format {
+                OutputFormat::Text => print!("{}", format_report_table(&rows)),
+                OutputFormat::Json => println!("{}", serde_json::to_string(&rows)?),
+            }
+        } else {
+            println!("{}", DAILY_CSV_HEADER);
+            for day in usage_snapshot.daily_summaries() {
+                println!("{}", day.csv_row());
+            }
+        }
+        return Ok(());
+    }
+
+    // A broken template is reported before any work is done
+    let template = StatusTemplate::from_env()?;
+
+    // Long-running mode answering hook payloads over a Unix socket
+    #[cfg(unix)]
+    if let Mode::Serve(ref path) = args.mode {
+        return serve(path, claude_paths, template, args.format, args.verbose).await;
+    }
+
+    // Read input JSON directly from stdin using stream processing
+    let mut hook_data: StatuslineHookJson = serde_json::from_reader(io::stdin())?;
+    let session_overridden =
+        hook_data.apply_session_override(config::env_var("CLAUDE_CCR_SESSION_ID"));
+
+    if matches!(args.mode, Mode::Stats | Mode::DebugBlock) {
+        let load_options = LoadOptions::from_env();
+        let usage_snapshot =
+            load_all_data_with(&claude_paths, &hook_data.session_id, &load_options).await?;
+        if args.verbose {
+            report_diagnostics(&usage_snapshot);
+        }
+        if args.mode == Mode::Stats {
+            print_stats(&hook_data, &usage_snapshot);
+            if load_options.no_dedup {
+                // Load again with dedup to show how much the duplicates add
+                let dedup_options = LoadOptions {
+                    no_dedup: false,
+                    ..load_options
+                };
+                let deduplicated =
+                    load_all_data_with(&claude_paths, &hook_data.session_id, &dedup_options)
+                        .await?;
+                print_dedup_delta(&hook_data, &usage_snapshot, &deduplicated);
+            }
+        } else {
+            print_active_block(&usage_snapshot);
+        }
+        return Ok(());
+    }
+
+    let status_line =
+        build_status_line(&hook_data, session_overridden, &claude_paths, args.verbose).await?;
+    println!(
+        "{}",
+        render_status_line(&status_line, args.format, template.as_ref())
     );
 
-    let lines_info_str = lines_info(&hook_data);
+    Ok(())
+}
+
+// Render for output: JSON, the user's template, or the colored line fitted
+// to CLAUDE_CCR_MAX_WIDTH
+fn render_status_line(
+    status_line: &StatusLine,
+    format: OutputFormat,
+    template: Option<&StatusTemplate>,
+) -> String {
+    let colored = colored::control::SHOULD_COLORIZE.should_colorize();
+    if let Some(template) = template.filter(|_| format == OutputFormat::Text) {
+        return template.render(status_line, colored);
+    }
+    if format == OutputFormat::Json {
+        return status_line.to_json();
+    }
+
+    // Drop low-priority segments on narrow terminals
+    let fitted = config::env_parse::<usize>("CLAUDE_CCR_MAX_WIDTH")
+        .map(|max_width| status_line.fit_to_width(max_width));
+    let line = fitted.as_ref().unwrap_or(status_line);
+    if colored {
+        line.render_colored()
+    } else {
+        line.render_plain()
+    }
+}
+
+// Compute the statusline values for one hook payload
+async fn build_status_line(
+    hook_data: &StatuslineHookJson,
+    session_overridden: bool,
+    claude_paths: &[PathBuf],
+    verbose: bool,
+) -> Result<StatusLine> {
+    // Load usage snapshot and context info
+    let load_options = LoadOptions::from_env();
+    let (usage_snapshot, git_status, transcript_usage) = tokio::join!(
+        load_all_data_with(claude_paths, &hook_data.session_id, &load_options),
+        get_git_status(Path::new(&hook_data.cwd)),
+        hook_transcript_usage(hook_data)
+    );
 
     // Prefer API context_window if available, fallback to transcript-based calculation
-    let context_display = if let Some(ref ctx) = hook_data.context_window {
-        transcript_usage
+    let context = transcript_usage.as_ref().map(|u| {
+        let tokens = ContextTokens::from_usage(u);
+        match hook_data.context_window {
+            Some(ContextWindow {
+                used_percentage: Some(percentage),
+                context_window_size,
+                ..
+            }) => tokens.usage_with_api(percentage, context_window_size),
+            _ => tokens.usage_for_model(hook_data.model.id.as_ref()),
+        }
+        .with_thresholds(ContextThresholds::from_env())
+    });
+
+    let usage_snapshot = usage_snapshot?;
+    if verbose {
+        report_diagnostics(&usage_snapshot);
+    }
+
+    // Use API cost if available, otherwise calculate from usage data
+    // The hook's cost belongs to the hook's session, so an override skips it;
+    // it also covers the whole session, so the today scope skips it as well
+    let session_cost = match SessionScope::from_env() {
+        SessionScope::Today => usage_snapshot.session_cost_today(&hook_data.session_id),
+        SessionScope::All => hook_data
+            .cost
             .as_ref()
-            .map(|u| {
-                let tokens: ccr::ContextTokens = ccr::ContextTokens::from_usage(u);
-
-                if let Some(percentage) = ctx.used_percentage {
-                    format!(
-                        " ⚖️ {}",
-                        tokens.to_formatted_string_with_api(percentage, ctx.context_window_size)
-                    )
-                } else {
-                    format!(" ⚖️ {}", tokens.to_formatted_string())
-                }
-            })
-            .unwrap_or_default()
+            .filter(|_| !session_overridden)
+            .map(Cost::from)
+            .unwrap_or_else(|| {
+                usage_snapshot
+                    .session_cost_with_model(&hook_data.session_id, hook_data.model.id.as_ref())
+            }),
+    };
+
+    // Optional average cost per message today
+    let cost_per_message = if config::env_flag("CLAUDE_CCR_COST_PER_MESSAGE") {
+        usage_snapshot.today_cost_per_message()
+    } else {
+        None
+    };
+
+    // Optional spending limit per block in dollars
+    let block_budget = config::env_parse::<f64>("CLAUDE_CCR_BLOCK_BUDGET").map(Cost::new);
+
+    // Minimal mode skips other sessions' files, so today's total would understate
+    let today_cost = (!load_options.minimal).then(|| usage_snapshot.today_cost());
+    let cache_hit_ratio = if !load_options.minimal && config::env_flag("CCR_SHOW_CACHE_HIT") {
+        usage_snapshot.today_cache_hit_ratio()
     } else {
-        // No context_window field, fallback to transcript
-        transcript_usage
+        None
+    };
+    let daily_budget = today_cost
+        .zip(Budget::daily_limit_from_env())
+        .map(|(cost, limit)| Budget::from_cost_and_limit(cost, limit));
+    if let Some(budget) = daily_budget
+        && budget.is_exceeded()
+        && config::env_flag("CCR_BUDGET_ALERT")
+        && budget_alert_marker_path()
+            .is_none_or(|marker| claim_daily_alert(&marker, Local::now().date_naive()))
+    {
+        eprintln!(
+            "ccr: daily budget exceeded: {} of {}",
+            budget.spent, budget.limit
+        );
+    }
+
+    let mut status_line = StatusLine {
+        current_dir: hook_data.dir_name(DirSource::from_env()).to_string(),
+        git_branch: git_status.branch,
+        git_dirty: git_status.dirty,
+        model: model_name(&hook_data.model.display_name),
+        output_style: hook_data
+            .output_style
             .as_ref()
-            .map(|u| {
-                format!(
-                    " ⚖️ {}",
-                    ccr::ContextTokens::from_usage(u).to_formatted_string()
+            .filter(|style| style.name != "default")
+            .map(|style| style.name.clone()),
+        today_cost,
+        cost_per_message,
+        daily_budget,
+        cache_hit_ratio,
+        session_cost,
+        context,
+        lines_added: hook_data.cost.as_ref().map_or(0, |c| c.total_lines_added),
+        lines_removed: hook_data.cost.as_ref().map_or(0, |c| c.total_lines_removed),
+        version: hook_data.version.clone(),
+        ..Default::default()
+    };
+
+    // Calculate active block
+    if let Some(block) = usage_snapshot.active_block() {
+        status_line.block_cost = block.cost();
+        status_line.burn_rate = BurnRate::from_block_cost(
+            &block,
+            BurnCost::from_env(),
+            BurnBasis::from_env(),
+            Utc::now(),
+        );
+        status_line.burn_thresholds = BurnThresholds::from_env(block.dominant_model().as_ref());
+        if config::env_flag("CCR_SHOW_TOKEN_RATE") {
+            status_line.token_rate = TokenBurnRate::from_session_block(&block);
+        }
+        if config::env_flag("CCR_SHOW_PROJECTED_COST") {
+            status_line.projected_block_cost = status_line.burn_rate.map(|rate| {
+                rate.projected_block_cost(
+                    RemainingTime::from_session_block(&block),
+                    status_line.block_cost,
                 )
-            })
-            .unwrap_or_default()
+            });
+        }
+        match TimeStyle::from_env() {
+            TimeStyle::Remaining => {
+                status_line.remaining_time = Some(RemainingTime::from_session_block(&block));
+                if config::env_flag("CCR_SHOW_RESET_CLOCK") {
+                    status_line.reset_clock = Some(block.reset_local_string());
+                }
+            }
+            TimeStyle::Window => status_line.block_window = Some(block.window_local_string()),
+        }
+        status_line.budget_left = block_budget.map(|limit| block.remaining_budget(limit));
+        status_line.block_ending_soon = block.is_ending_soon(
+            config::env_parse("CLAUDE_CCR_ENDING_SOON_MINUTES")
+                .unwrap_or(DEFAULT_ENDING_SOON_MINUTES),
+        );
+    }
+
+    // Clamped costs almost always mean a corrupt costUSD in the logs; only
+    // reported when asked, since the statusline renders on every message
+    let costs = [
+        status_line.today_cost,
+        Some(status_line.session_cost),
+        Some(status_line.block_cost),
+    ];
+    if verbose
+        && costs
+            .into_iter()
+            .flatten()
+            .any(|cost| cost.exceeds_ceiling())
+    {
+        eprintln!("ccr: cost above the display ceiling, likely a corrupt costUSD entry");
+    }
+
+    Ok(status_line)
+}
+
+// Serve statuslines over a Unix domain socket for --serve
+// Each line a client sends is a hook JSON payload, answered with one statusline
+// line; problems with one connection never stop the server
+#[cfg(unix)]
+async fn serve(
+    path: &Path,
+    claude_paths: Vec<PathBuf>,
+    template: Option<StatusTemplate>,
+    format: OutputFormat,
+    verbose: bool,
+) -> Result<()> {
+    let listener = bind_socket(path)?;
+    let claude_paths = Arc::new(claude_paths);
+    let template = Arc::new(template);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("ccr: failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let claude_paths = Arc::clone(&claude_paths);
+        let template = Arc::clone(&template);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(
+                stream,
+                &claude_paths,
+                template.as_ref().as_ref(),
+                format,
+                verbose,
+            )
+            .await
+                && verbose
+            {
+                eprintln!("ccr: connection closed: {}", e);
+            }
+        });
+    }
+}
+
+// Bind the server socket
+// A socket file left behind by a previous run would make bind fail, so it is
+// replaced; any other file at the path is left alone and reported
+#[cfg(unix)]
+fn bind_socket(path: &Path) -> Result<UnixListener> {
+    let bind_error = |source: io::Error| CcrError::SocketBind {
+        path: path.to_path_buf(),
+        source,
     };
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            std::fs::remove_file(path).map_err(bind_error)?;
+        }
+        Ok(_) => {
+            return Err(bind_error(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            )));
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(bind_error(e)),
+    }
+    UnixListener::bind(path).map_err(bind_error)
+}
 
-    let usage_snapshot = usage_snapshot?;
+// Answer each hook JSON line on one connection until the client disconnects
+// Lines longer than MAX_REQUEST_LINE_BYTES and idle connections are closed,
+// so a misbehaving client cannot hold memory or a task forever
+#[cfg(unix)]
+async fn handle_connection(
+    stream: UnixStream,
+    claude_paths: &[PathBuf],
+    template: Option<&StatusTemplate>,
+    format: OutputFormat,
+    verbose: bool,
+) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
 
-    // Calculate metrics from the snapshot
-    let today_cost = usage_snapshot.today_cost();
+    loop {
+        buf.clear();
+        let mut limited = (&mut reader).take(MAX_REQUEST_LINE_BYTES + 1);
+        let read =
+            tokio::time::timeout(CONNECTION_IDLE_TIMEOUT, limited.read_until(b'\n', &mut buf))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connection idle"))??;
+        if read == 0 {
+            break;
+        }
+        if buf.len() as u64 > MAX_REQUEST_LINE_BYTES {
+            writer.write_all(b"ccr: request line too long\n").await?;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request line too long",
+            ));
+        }
 
-    // Use API cost if available, otherwise calculate from usage data
-    let session_cost = hook_data
-        .cost
-        .as_ref()
-        .map(Cost::from)
-        .unwrap_or_else(|| usage_snapshot.session_cost(&hook_data.session_id));
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
 
-    // Calculate active block
-    let (block_cost, burn_rate, remaining_time) = if let Some(block) = usage_snapshot.active_block()
+        let response = match serde_json::from_str::<StatuslineHookJson>(line) {
+            Ok(mut hook_data) => {
+                let session_overridden =
+                    hook_data.apply_session_override(config::env_var("CLAUDE_CCR_SESSION_ID"));
+                match build_status_line(&hook_data, session_overridden, claude_paths, verbose).await
+                {
+                    Ok(status_line) => render_status_line(&status_line, format, template),
+                    Err(e) => format!("ccr: {}", e),
+                }
+            }
+            Err(e) => format!("ccr: invalid hook JSON: {}", e),
+        };
+
+        writer.write_all(response.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+    }
+
+    Ok(())
+}
+
+// Print non-fatal loading problems to stderr for --verbose
+fn report_diagnostics(usage_snapshot: &MergedUsageSnapshot) {
+    for error in &usage_snapshot.diagnostics.access_errors {
+        match std::error::Error::source(error) {
+            Some(source) => eprintln!("ccr: {} ({})", error, source),
+            None => eprintln!("ccr: {}", error),
+        }
+    }
+    for (path, count) in &usage_snapshot.diagnostics.skipped_lines {
+        eprintln!(
+            "ccr: skipped {} malformed lines in {}",
+            count,
+            path.display()
+        );
+    }
+}
+
+// Print diagnostic statistics for --stats
+fn print_stats(hook_data: &StatuslineHookJson, usage_snapshot: &MergedUsageSnapshot) {
+    println!("Entries loaded:    {}", usage_snapshot.all_entries.len());
+    match usage_snapshot.loaded_range() {
+        Some((first, last)) => println!(
+            "Loaded range:      {} .. {}",
+            first.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            last.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        ),
+        None => println!("Loaded range:      -"),
+    }
+    let timestampless = usage_snapshot.timestampless_entries();
+    if timestampless > 0 {
+        println!(
+            "{} entries have no usable timestamp; they count toward the session cost but not the blocks",
+            timestampless
+        );
+    }
+    let diagnostics = &usage_snapshot.diagnostics;
+    if diagnostics.filtered_entries > 0
+        && let Some(ref cutoff) = diagnostics.cutoff_timestamp
     {
+        println!(
+            "Dropped {} entries older than {} (by design, to save memory); set CLAUDE_CCR_LOOKBACK_HOURS to include them",
+            diagnostics.filtered_entries, cutoff
+        );
+    }
+    println!("Today cost:        {}", usage_snapshot.today_cost());
+    println!("Retry cost today:  {}", usage_snapshot.retry_cost_today());
+    println!(
+        "Session cost:      {}",
+        usage_snapshot.session_cost(&hook_data.session_id)
+    );
+    println!(
+        "Session source:    {}",
+        usage_snapshot
+            .source_of_session(&hook_data.session_id)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    let offsets = usage_snapshot.timestamp_offsets(TIMESTAMP_SAMPLE_SIZE);
+    let offsets: Vec<_> = offsets.into_iter().collect();
+    println!(
+        "Timestamp offsets: {}",
+        if offsets.is_empty() {
+            "-".to_string()
+        } else {
+            offsets.join(", ")
+        }
+    );
+    if offsets.iter().any(|offset| offset != "Z") {
+        println!("  (non-UTC offsets found; the \"today\" boundary may be skewed)");
+    }
+    if let Some(ref workspace) = hook_data.workspace {
+        let project_key = project_key_from_path(&workspace.project_dir);
+        println!(
+            "Project today:     {}",
+            usage_snapshot.project_cost_today(&project_key)
+        );
+    }
+    println!("Today by model:");
+    for (model, cost) in usage_snapshot.today_cost_by_model() {
+        println!("  {:<32} {}", model, cost);
+    }
+    println!("Projects by cost (projects/ directory names):");
+    for (project, cost) in usage_snapshot.projects_by_cost().into_iter().take(5) {
+        println!("  {:<32} {}", project, cost);
+    }
+    println!("Top entries:");
+    for (entry, cost) in usage_snapshot.top_entries(5, false) {
+        println!(
+            "  {:<32} {:<32} {}",
+            entry.data.timestamp.as_deref().unwrap_or("-"),
+            entry.model().map_or("-", |model| model.as_str()),
+            cost
+        );
+    }
+
+    if let Some(ref cost_info) = hook_data.cost {
+        println!("API session cost:  {}", Cost::from(cost_info));
+        println!(
+            "Session duration:  {:.1}s",
+            cost_info.total_duration_ms as f64 / 1000.0
+        );
+        println!(
+            "API duration:      {:.1}s",
+            cost_info.total_api_duration_ms as f64 / 1000.0
+        );
+        if let Some(efficiency) = cost_info.api_efficiency() {
+            println!("API efficiency:    {:.1}%", efficiency * 100.0);
+        }
+    }
+}
+
+// Compare raw totals (CLAUDE_CCR_NO_DEDUP) against deduplicated ones for --stats
+fn print_dedup_delta(
+    hook_data: &StatuslineHookJson,
+    raw: &MergedUsageSnapshot,
+    deduplicated: &MergedUsageSnapshot,
+) {
+    println!("Duplicates (raw totals overstate cost, CLAUDE_CCR_NO_DEDUP is set):");
+    println!(
+        "  Entries:         {} raw, {} deduplicated",
+        raw.all_entries.len(),
+        deduplicated.all_entries.len()
+    );
+    let rows = [
+        ("Today", raw.today_cost(), deduplicated.today_cost()),
         (
-            block.cost(),
-            BurnRate::from_session_block(&block),
-            RemainingTime::from_session_block(&block),
-        )
-    } else {
-        (Cost::new(0.0), None, RemainingTime::new(0))
+            "Session",
+            raw.session_cost(&hook_data.session_id),
+            deduplicated.session_cost(&hook_data.session_id),
+        ),
+    ];
+    for (label, raw_cost, dedup_cost) in rows {
+        println!(
+            "  {:<16} {} raw, {} deduplicated ({})",
+            format!("{}:", label),
+            raw_cost,
+            dedup_cost,
+            Cost::new(raw_cost.value() - dedup_cost.value()).to_signed_colored_string()
+        );
+    }
+}
+
+// Print the active block's entries and burn rate inputs (--debug-block)
+fn print_active_block(usage_snapshot: &MergedUsageSnapshot) {
+    let Some(block) = usage_snapshot.active_block() else {
+        println!("No active block");
+        return;
     };
 
-    // Build and print status line
+    println!("Block start:       {}", block.start_time().to_rfc3339());
+    println!("Block end:         {}", block.end_time().to_rfc3339());
     println!(
-        "{reset_color}{current_dir}{branch} 👤 {model}{output_style}{reset_color}{remaining} 💰 {today} today, {session} session{block}{burn_rate}{context}{lines}",
-        reset_color = "\x1b[0m",
-        current_dir = get_current_dir(&hook_data.cwd),
-        branch = if let Some(branch) = git_branch {
-            format!(" {}", branch.cyan())
+        "{:<26} {:<32} {:>9} {:>9} {:>11} {:>11} {:>9}",
+        "timestamp", "model", "input", "output", "cache_write", "cache_read", "cost"
+    );
+    for entry in block.entries() {
+        let tokens = TokenTotals::from_entries(std::iter::once(entry));
+        println!(
+            "{:<26} {:<32} {:>9} {:>9} {:>11} {:>11} {:>9}",
+            entry.data.timestamp.as_deref().unwrap_or("-"),
+            entry.model().map(|m| m.as_str()).unwrap_or("-"),
+            tokens.input_tokens,
+            tokens.output_tokens,
+            tokens.cache_creation_tokens(),
+            tokens.cache_read_tokens,
+            // Individual entries are often below a cent
+            format!("${:.4}", Cost::from_entry(entry).value()),
+        );
+    }
+
+    println!("Entries:           {}", block.entries().len());
+    println!("Block cost:        {}", block.cost());
+    if let Some(minutes) = block.actual_duration_minutes() {
+        println!("Entry span:        {:.0}m", minutes);
+    }
+    for basis in [BurnBasis::Entries, BurnBasis::Elapsed] {
+        let rate = BurnRate::from_session_block_with(&block, basis, Utc::now());
+        println!(
+            "Burn rate ({:?}): {}",
+            basis,
+            rate.map(|r| r.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+}
+
+// Print one --selftest check and return whether it passed
+fn report_check(name: &str, result: std::result::Result<String, String>) -> bool {
+    match result {
+        Ok(detail) => {
+            println!("[{}] {}: {}", "PASS".green(), name, detail);
+            true
+        }
+        Err(detail) => {
+            println!("[{}] {}: {}", "FAIL".red(), name, detail);
+            false
+        }
+    }
+}
+
+// First JSONL file found under the projects directories
+fn find_sample_jsonl(projects_dirs: &[PathBuf]) -> Option<PathBuf> {
+    projects_dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|project| std::fs::read_dir(project.ok()?.path()).ok())
+        .flatten()
+        .filter_map(|file| file.ok().map(|file| file.path()))
+        .find(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+}
+
+// Check the setup step by step for --selftest; returns true if every check passed
+fn run_self_test() -> bool {
+    let claude_paths = get_claude_paths();
+    let mut passed = report_check(
+        "Claude paths",
+        if claude_paths.is_empty() {
+            Err("none found (expected e.g. ~/.claude)".to_string())
         } else {
-            String::new()
+            Ok(claude_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "))
         },
-        model = model_name(&hook_data.model.display_name),
-        output_style = if let Some(style) = hook_data.output_style
-            && style.name != "default"
-        {
-            format!(" [{}]", style.name.yellow())
-        } else {
-            String::new()
+    );
+
+    let projects_dirs: Vec<PathBuf> = claude_paths
+        .iter()
+        .map(|path| projects_dir(path))
+        .filter(|dir| dir.is_dir())
+        .collect();
+    passed &= report_check(
+        "Projects directory",
+        match projects_dirs.first() {
+            Some(dir) => Ok(format!(
+                "{} found, e.g. {}",
+                projects_dirs.len(),
+                dir.display()
+            )),
+            None => Err("no projects directory under the Claude paths".to_string()),
         },
-        remaining = if remaining_time.has_remaining() {
-            format!(" ⏰ {}", remaining_time.to_colored_string())
-        } else {
-            String::new()
+    );
+
+    passed &= report_check(
+        "Sample JSONL",
+        match find_sample_jsonl(&projects_dirs) {
+            Some(path) => match load_jsonl_file(&path) {
+                Ok(snapshot) if !snapshot.all_entries.is_empty() => Ok(format!(
+                    "{} entries in {}",
+                    snapshot.all_entries.len(),
+                    path.display()
+                )),
+                Ok(_) => Err(format!("no usage entries in {}", path.display())),
+                Err(e) => Err(e.to_string()),
+            },
+            None => Err("no .jsonl file found".to_string()),
         },
-        today = today_cost,
-        session = session_cost,
-        block = if block_cost.is_positive() {
-            format!(", {} block", block_cost)
+    );
+
+    let pricing = ModelPricing::from(&ModelId::ClaudeSonnet4_20250514);
+    passed &= report_check(
+        "Pricing table",
+        if pricing.input_cost_per_token > 0.0 && pricing.output_cost_per_token > 0.0 {
+            Ok(format!(
+                "Sonnet 4 at ${:.2}/M input",
+                pricing.input_cost_per_token * 1_000_000.0
+            ))
         } else {
-            String::new()
+            Err("no pricing for a known model".to_string())
         },
-        burn_rate = if let Some(rate) = burn_rate {
-            format!(" 🔥 {}", rate.to_colored_string())
+    );
+
+    let status_line = StatusLine {
+        current_dir: "selftest".to_string(),
+        model: "Sonnet 4".to_string(),
+        today_cost: Some(Cost::new(1.23)),
+        session_cost: Cost::new(0.45),
+        ..Default::default()
+    };
+    let rendered = status_line.render_plain();
+    passed &= report_check(
+        "Statusline",
+        if rendered.contains("$1.23") {
+            Ok(rendered)
         } else {
-            String::new()
+            Err(format!("unexpected output {:?}", rendered))
         },
-        context = context_display,
-        lines = lines_info_str,
     );
 
+    passed
+}
+
+// Print cost and token totals for a single JSONL file (--file)
+fn print_file_report(path: &Path) -> Result<()> {
+    let snapshot = load_jsonl_file(path)?;
+    let entries = || snapshot.all_entries.iter().map(|e| e.as_ref());
+    let totals = TokenTotals::from_entries(entries());
+
+    println!("File:              {}", path.display());
+    if let Some(entry) = snapshot.all_entries.first() {
+        println!("Session:           {}", entry.session_id);
+    }
+    println!("Entries:           {}", snapshot.all_entries.len());
+    println!("Cost:              {}", Cost::from_entries(entries()));
+    println!("Input tokens:      {}", totals.input_tokens);
+    println!("Output tokens:     {}", totals.output_tokens);
+    if totals.reasoning_output_tokens > 0 {
+        println!("Reasoning tokens:  {}", totals.reasoning_output_tokens);
+    }
+    println!(
+        "Cache creation:    {} (5m: {}, 1h: {})",
+        totals.cache_creation_tokens(),
+        totals.cache_creation_5m_tokens,
+        totals.cache_creation_1h_tokens
+    );
+    println!("Cache read:        {}", totals.cache_read_tokens);
+
     Ok(())
 }
 
-#[inline]
-fn model_name(model: &str) -> ColoredString {
-    let is_opus = model.to_lowercase().contains("opus");
-    if is_opus {
-        model.white()
+// Model name as displayed, abbreviated with CLAUDE_CCR_MODEL_SHORT=1
+fn model_name(model: &str) -> String {
+    if config::env_flag("CLAUDE_CCR_MODEL_SHORT") {
+        short_model_name(model)
     } else {
-        model.yellow().bold()
+        model.to_string()
     }
 }
 
-#[inline]
-fn get_current_dir(cwd: &str) -> ColoredString {
-    Path::new(cwd)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or(cwd)
-        .green()
-}
+// The tests all exercise the Unix socket server
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
 
-// Format lines added/removed
-fn lines_info(hook_data: &StatuslineHookJson) -> String {
-    if let Some(ref cost_info) = hook_data.cost {
-        let mut parts = Vec::new();
-        if cost_info.total_lines_added > 0 {
-            parts.push(
-                format!("+{}", cost_info.total_lines_added)
-                    .green()
-                    .to_string(),
-            );
-        }
-        if cost_info.total_lines_removed > 0 {
-            parts.push(
-                format!("-{}", cost_info.total_lines_removed)
-                    .red()
-                    .to_string(),
-            );
-        }
-        if !parts.is_empty() {
-            format!(" ✏️ {}", parts.join(" "))
-        } else {
-            String::new()
-        }
-    } else {
-        String::new()
+    const PAYLOAD: &str =
+        r#"{"session_id":"s","cwd":"/tmp/served","model":{"display_name":"Opus"}}"#;
+
+    // Run one connection of the server against an empty Claude root and
+    // return everything it answered to `request`
+    async fn exchange(request: Vec<u8>, format: OutputFormat) -> (String, io::Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ccr.sock");
+        let listener = bind_socket(&path).unwrap();
+        let claude_paths = vec![dir.path().to_path_buf()];
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(stream, &claude_paths, None, format, false).await
+        });
+
+        let mut client = UnixStream::connect(&path).await.unwrap();
+        // The server may hang up before reading everything
+        let _ = client.write_all(&request).await;
+        let _ = client.shutdown().await;
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        (response, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_serve_round_trips_a_payload() {
+        let (response, result) = exchange(
+            format!("{}\n\nnot json\n", PAYLOAD).into_bytes(),
+            OutputFormat::Text,
+        )
+        .await;
+        assert!(result.is_ok());
+        let lines: Vec<&str> = response.lines().collect();
+        assert_eq!(lines.len(), 2, "{:?}", response);
+        assert!(lines[0].contains("served"), "{:?}", lines[0]);
+        assert!(lines[1].starts_with("ccr: invalid hook JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_honors_output_format() {
+        let (response, _) =
+            exchange(format!("{}\n", PAYLOAD).into_bytes(), OutputFormat::Json).await;
+        assert!(
+            response.starts_with(r#"{"current_dir":"served""#),
+            "{:?}",
+            response
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve_rejects_overlong_lines() {
+        let request = vec![b'x'; MAX_REQUEST_LINE_BYTES as usize + 1];
+        let (response, result) = exchange(request, OutputFormat::Text).await;
+        assert_eq!(response, "ccr: request line too long\n");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_bind_socket_only_replaces_sockets() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        assert!(matches!(
+            bind_socket(&file),
+            Err(CcrError::SocketBind { .. })
+        ));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+
+        // A stale socket from an earlier run is replaced
+        let socket = dir.path().join("ccr.sock");
+        drop(bind_socket(&socket).unwrap());
+        assert!(bind_socket(&socket).is_ok());
     }
 }
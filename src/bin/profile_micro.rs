@@ -1,5 +1,5 @@
 use ccr::constants::SESSION_BLOCK_DURATION;
-use ccr::types::{SessionId, UniqueHash, UsageEntry, UsageEntryData};
+use ccr::types::{DedupBuildHasher, SessionId, UniqueHash, UsageEntry, UsageEntryData};
 use chrono::{Local, Utc};
 use colored::Colorize;
 use rayon::prelude::*;
@@ -11,6 +11,12 @@ use std::time::Instant;
 
 const INITIAL_HASH_CAPACITY: usize = 1024;
 
+// Compare with `cargo run --release --bin profile_micro --features fast-hash`
+#[cfg(feature = "fast-hash")]
+const DEDUP_HASHER_NAME: &str = "Sequential FxHash";
+#[cfg(not(feature = "fast-hash"))]
+const DEDUP_HASHER_NAME: &str = "Sequential SipHash";
+
 fn collect_jsonl_files(projects_path: &Path) -> Vec<(PathBuf, String)> {
     if !projects_path.exists() {
         return Vec::new();
@@ -129,6 +135,27 @@ fn deduplicate_sequential_no_mutex(results: Vec<Vec<UsageEntry>>) -> Vec<Arc<Usa
     all_entries
 }
 
+// Optimized: No mutex, using the library's dedup hasher (FxHash with `fast-hash`)
+fn deduplicate_sequential_dedup_hasher(results: Vec<Vec<UsageEntry>>) -> Vec<Arc<UsageEntry>> {
+    let mut all_entries = Vec::new();
+    let mut hashes: HashSet<UniqueHash, DedupBuildHasher> =
+        HashSet::with_capacity_and_hasher(INITIAL_HASH_CAPACITY, DedupBuildHasher::default());
+
+    for entries in results {
+        for entry in entries {
+            if let Some(hash) = UniqueHash::from_usage_entry_data(&entry.data) {
+                if hashes.contains(&hash) {
+                    continue;
+                }
+                hashes.insert(hash);
+            }
+            all_entries.push(Arc::new(entry));
+        }
+    }
+
+    all_entries
+}
+
 // Parallel deduplication with local merge
 fn deduplicate_parallel_local_merge(results: Vec<Vec<UsageEntry>>) -> Vec<Arc<UsageEntry>> {
     // Process each batch in parallel, maintaining local hash sets
@@ -268,6 +295,10 @@ async fn main() -> ccr::Result<()> {
             "Sequential no Mutex",
             deduplicate_sequential_no_mutex as fn(_) -> _,
         ),
+        (
+            DEDUP_HASHER_NAME,
+            deduplicate_sequential_dedup_hasher as fn(_) -> _,
+        ),
         (
             "Parallel local merge",
             deduplicate_parallel_local_merge as fn(_) -> _,
@@ -315,6 +346,10 @@ async fn main() -> ccr::Result<()> {
             "Sequential no Mutex",
             deduplicate_sequential_no_mutex as fn(_) -> _,
         ),
+        (
+            DEDUP_HASHER_NAME,
+            deduplicate_sequential_dedup_hasher as fn(_) -> _,
+        ),
         (
             "Parallel local merge",
             deduplicate_parallel_local_merge as fn(_) -> _,
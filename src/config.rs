@@ -0,0 +1,80 @@
+use std::env;
+use std::str::FromStr;
+
+/// Read an environment variable, treating empty values as unset
+pub fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Read and parse an environment variable
+/// Returns None if the variable is unset or fails to parse
+pub fn env_parse<T: FromStr>(name: &str) -> Option<T> {
+    env_var(name).and_then(|value| value.trim().parse().ok())
+}
+
+/// Check whether a boolean flag variable is enabled
+pub fn env_flag(name: &str) -> bool {
+    env_var(name).is_some_and(|value| is_truthy(&value))
+}
+
+/// Decide whether to emit colors
+/// `--no-color` wins, then CCR_FORCE_COLOR (for hooks piping the statusline),
+/// then NO_COLOR (<https://no-color.org>), otherwise color only on a terminal
+pub fn color_enabled(no_color_flag: bool, is_terminal: bool) -> bool {
+    color_choice(
+        no_color_flag,
+        env_flag("CCR_FORCE_COLOR"),
+        env_var("NO_COLOR").is_some(),
+        is_terminal,
+    )
+}
+
+fn color_choice(no_color_flag: bool, force: bool, no_color_env: bool, is_terminal: bool) -> bool {
+    if no_color_flag {
+        false
+    } else if force {
+        true
+    } else if no_color_env {
+        false
+    } else {
+        is_terminal
+    }
+}
+
+/// Accepts "1", "true", "yes" and "on" (case-insensitive)
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_truthy() {
+        assert!(is_truthy("1"));
+        assert!(is_truthy("true"));
+        assert!(is_truthy(" YES "));
+        assert!(is_truthy("on"));
+        assert!(!is_truthy("0"));
+        assert!(!is_truthy("false"));
+        assert!(!is_truthy("off"));
+        assert!(!is_truthy(""));
+    }
+
+    #[test]
+    fn test_color_choice() {
+        // A terminal gets color unless NO_COLOR is set
+        assert!(color_choice(false, false, false, true));
+        assert!(!color_choice(false, false, true, true));
+        // A pipe gets color only when forced
+        assert!(!color_choice(false, false, false, false));
+        assert!(color_choice(false, true, false, false));
+        assert!(color_choice(false, true, true, false));
+        // --no-color always wins
+        assert!(!color_choice(true, true, false, true));
+    }
+}
@@ -1,6 +1,87 @@
 use chrono::Duration;
+use std::sync::OnceLock;
 
 /// The duration of a session block in hours
 /// This is used to group activity into blocks with gaps
 /// Also used for filtering recent activity to reduce memory usage
 pub const SESSION_BLOCK_DURATION: Duration = Duration::hours(5);
+
+/// Longest block accepted from CCR_BLOCK_HOURS, one week
+/// Far larger values overflow the date arithmetic of the loader's cutoff
+pub const MAX_BLOCK_HOURS: u32 = 168;
+
+/// Session block duration from CCR_BLOCK_HOURS, read once per process
+/// Zero and non-numeric values fall back to SESSION_BLOCK_DURATION and values
+/// above MAX_BLOCK_HOURS are clamped, each reported on stderr once
+pub fn session_block_duration() -> Duration {
+    static DURATION: OnceLock<Duration> = OnceLock::new();
+    *DURATION.get_or_init(|| {
+        let raw = crate::config::env_var("CCR_BLOCK_HOURS");
+        let (duration, warning) = block_duration_from(raw.as_deref());
+        if let Some(warning) = warning {
+            eprintln!("ccr: {}", warning);
+        }
+        duration
+    })
+}
+
+/// Block duration for a raw CCR_BLOCK_HOURS value, with a warning when the
+/// value was not used as given
+/// Unset means SESSION_BLOCK_DURATION; zero and non-numeric values fall back to
+/// it, and values above MAX_BLOCK_HOURS are clamped
+fn block_duration_from(raw: Option<&str>) -> (Duration, Option<String>) {
+    let Some(raw) = raw else {
+        return (SESSION_BLOCK_DURATION, None);
+    };
+    // Digits too many for u64 are just another huge value
+    let trimmed = raw.trim();
+    let hours = (!trimmed.is_empty() && trimmed.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| trimmed.parse::<u64>().unwrap_or(u64::MAX));
+    match hours {
+        Some(hours) if hours > u64::from(MAX_BLOCK_HOURS) => (
+            Duration::hours(MAX_BLOCK_HOURS.into()),
+            Some(format!(
+                "CCR_BLOCK_HOURS={} is above the maximum, using {} hours",
+                raw, MAX_BLOCK_HOURS
+            )),
+        ),
+        Some(hours) if hours > 0 => (Duration::hours(hours as i64), None),
+        _ => (
+            SESSION_BLOCK_DURATION,
+            Some(format!(
+                "CCR_BLOCK_HOURS='{}' is not a number of hours from 1 to {}, using {} hours",
+                raw,
+                MAX_BLOCK_HOURS,
+                SESSION_BLOCK_DURATION.num_hours()
+            )),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_duration_from() {
+        assert_eq!(block_duration_from(None), (SESSION_BLOCK_DURATION, None));
+        assert_eq!(block_duration_from(Some("3")), (Duration::hours(3), None));
+        assert_eq!(block_duration_from(Some(" 168 ")).0, Duration::hours(168));
+    }
+
+    #[test]
+    fn test_block_duration_from_invalid_values() {
+        for raw in ["0", "five", "-3", "2.5", ""] {
+            let (duration, warning) = block_duration_from(Some(raw));
+            assert_eq!(duration, SESSION_BLOCK_DURATION, "{:?}", raw);
+            assert!(warning.is_some(), "{:?}", raw);
+        }
+
+        // Huge values used to overflow the cutoff calculation
+        for raw in ["169", "3000000000", "99999999999999999999999"] {
+            let (duration, warning) = block_duration_from(Some(raw));
+            assert_eq!(duration, Duration::hours(168), "{:?}", raw);
+            assert!(warning.is_some(), "{:?}", raw);
+        }
+    }
+}
@@ -11,6 +11,13 @@ pub enum CcrError {
         source: std::io::Error,
     },
 
+    #[error("Failed to write file: {path}")]
+    FileWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("Failed to access directory: {path}")]
     DirectoryAccess {
         path: PathBuf,
@@ -18,9 +25,23 @@ pub enum CcrError {
         source: std::io::Error,
     },
 
+    #[error("Failed to bind socket: {path}")]
+    SocketBind {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("Failed to read from stdin")]
     StdinRead(#[from] std::io::Error),
 
+    #[error("Failed to fetch pricing: {url}")]
+    PricingFetch {
+        url: String,
+        #[source]
+        source: Box<ureq::Error>,
+    },
+
     // Data processing errors
     #[error("Failed to parse JSON: {context}")]
     JsonParse {
@@ -29,12 +50,25 @@ pub enum CcrError {
         source: serde_json::Error,
     },
 
+    #[error("Failed to serialize JSON: {context}")]
+    JsonSerialize {
+        context: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
     #[error("Failed to parse JSON from stdin")]
     StdinJsonParse(#[from] serde_json::Error),
 
     #[error("Invalid data format: {message}")]
     DataValidation { message: String },
 
+    #[error("Invalid argument: {message}")]
+    InvalidArgument { message: String },
+
+    #[error("Invalid template: {message}")]
+    InvalidTemplate { message: String },
+
     // Environment-related errors
     #[error("Claude data directory not found")]
     ClaudePathNotFound,
@@ -52,10 +86,145 @@ pub enum CcrError {
     // Thread pool errors
     #[error("Failed to initialize thread pool")]
     ThreadPoolInit(#[source] rayon::ThreadPoolBuildError),
-
-    // Concurrency errors
-    #[error("Lock poisoned")]
-    LockPoisoned,
 }
 
 pub type Result<T> = std::result::Result<T, CcrError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::io;
+
+    fn io_error() -> io::Error {
+        io::Error::new(io::ErrorKind::PermissionDenied, "denied")
+    }
+
+    fn json_error() -> serde_json::Error {
+        serde_json::from_str::<serde_json::Value>("{").unwrap_err()
+    }
+
+    #[test]
+    fn test_io_error_messages() {
+        let path = PathBuf::from("/tmp/ccr");
+        let cases = [
+            (
+                CcrError::FileRead {
+                    path: path.clone(),
+                    source: io_error(),
+                },
+                "Failed to read file: /tmp/ccr",
+            ),
+            (
+                CcrError::FileWrite {
+                    path: path.clone(),
+                    source: io_error(),
+                },
+                "Failed to write file: /tmp/ccr",
+            ),
+            (
+                CcrError::DirectoryAccess {
+                    path: path.clone(),
+                    source: io_error(),
+                },
+                "Failed to access directory: /tmp/ccr",
+            ),
+            (
+                CcrError::SocketBind {
+                    path,
+                    source: io_error(),
+                },
+                "Failed to bind socket: /tmp/ccr",
+            ),
+            (CcrError::from(io_error()), "Failed to read from stdin"),
+        ];
+
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+            assert_eq!(error.source().unwrap().to_string(), "denied");
+        }
+    }
+
+    #[test]
+    fn test_data_error_messages() {
+        let parse = CcrError::JsonParse {
+            context: "session.jsonl".to_string(),
+            source: json_error(),
+        };
+        assert_eq!(parse.to_string(), "Failed to parse JSON: session.jsonl");
+        assert!(parse.source().is_some());
+
+        let serialize = CcrError::JsonSerialize {
+            context: "pricing.json".to_string(),
+            source: json_error(),
+        };
+        assert_eq!(
+            serialize.to_string(),
+            "Failed to serialize JSON: pricing.json"
+        );
+
+        let stdin = CcrError::from(json_error());
+        assert_eq!(stdin.to_string(), "Failed to parse JSON from stdin");
+        assert!(stdin.source().is_some());
+
+        let validation = CcrError::DataValidation {
+            message: "negative cost".to_string(),
+        };
+        assert_eq!(validation.to_string(), "Invalid data format: negative cost");
+
+        let argument = CcrError::InvalidArgument {
+            message: "unknown argument '--x'".to_string(),
+        };
+        assert_eq!(
+            argument.to_string(),
+            "Invalid argument: unknown argument '--x'"
+        );
+
+        let template = CcrError::InvalidTemplate {
+            message: "unknown placeholder '{x}'".to_string(),
+        };
+        assert_eq!(
+            template.to_string(),
+            "Invalid template: unknown placeholder '{x}'"
+        );
+    }
+
+    #[test]
+    fn test_environment_error_messages() {
+        assert_eq!(
+            CcrError::ClaudePathNotFound.to_string(),
+            "Claude data directory not found"
+        );
+        assert_eq!(
+            CcrError::EnvVarMissing {
+                var: "HOME".to_string()
+            }
+            .to_string(),
+            "Environment variable 'HOME' not set"
+        );
+        assert_eq!(
+            CcrError::from(std::env::VarError::NotPresent).to_string(),
+            "Environment variable error"
+        );
+    }
+
+    #[test]
+    fn test_thread_pool_error_message() {
+        let source = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .spawn_handler(|_| Err(io_error()))
+            .build()
+            .unwrap_err();
+        let error = CcrError::ThreadPoolInit(source);
+        assert_eq!(error.to_string(), "Failed to initialize thread pool");
+        assert!(error.source().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_task_join_error_message() {
+        let handle = tokio::spawn(std::future::pending::<()>());
+        handle.abort();
+        let error = CcrError::from(handle.await.unwrap_err());
+        assert_eq!(error.to_string(), "Task failed");
+    }
+}
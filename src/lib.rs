@@ -1,6 +1,9 @@
 // Module declarations
+pub mod config;
 pub mod constants;
 pub mod error;
+pub mod statusline;
+pub mod template;
 pub mod types;
 pub mod utils;
 
@@ -9,7 +12,7 @@ pub use error::{CcrError, Result};
 pub use types::ids::ModelId;
 pub use types::{
     BurnRate, ContextTokens, Cost, MergedUsageSnapshot, Message, ModelPricing, RemainingTime,
-    SessionBlock, StatuslineHookJson, UniqueHash, Usage, UsageEntry, UsageEntryData,
+    SessionBlock, StatuslineHookJson, TokenTotals, UniqueHash, Usage, UsageEntry, UsageEntryData,
 };
 
 #[cfg(test)]
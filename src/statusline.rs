@@ -0,0 +1,667 @@
+use crate::config;
+use crate::types::{
+    Budget, BurnRate, BurnThresholds, ContextUsage, Cost, ModelFamily, Palette, RemainingTime,
+    Severity, TokenBurnRate,
+};
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
+use std::str::FromStr;
+use unicode_width::UnicodeWidthStr;
+
+/// ANSI reset emitted around the leading segments of the colored statusline
+const RESET: &str = "\x1b[0m";
+
+/// Cache-hit percentage at or above which the segment is green
+const CACHE_HIT_GOOD_PERCENTAGE: f64 = 60.0;
+
+/// Cache-hit percentage below which the segment is red
+const CACHE_HIT_POOR_PERCENTAGE: f64 = 30.0;
+
+/// Optional segments in the order they are dropped to fit a width limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    LinesChanged,
+    Block,
+    DailyBudget,
+    Context,
+    Time,
+    OutputStyle,
+    GitBranch,
+}
+
+const DROP_ORDER: [Segment; 7] = [
+    Segment::LinesChanged,
+    Segment::Block,
+    Segment::DailyBudget,
+    Segment::Context,
+    Segment::Time,
+    Segment::OutputStyle,
+    Segment::GitBranch,
+];
+
+/// How the statusline is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// The colored emoji line
+    #[default]
+    Text,
+    /// One JSON object with raw values, for other prompt tools
+    Json,
+}
+
+impl OutputFormat {
+    /// Read the format from CCR_FORMAT (text|json)
+    pub fn from_env() -> Self {
+        config::env_parse("CCR_FORMAT").unwrap_or_default()
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("unknown output format '{}'", other)),
+        }
+    }
+}
+
+/// Statusline values as serialized for `--format json`
+/// Costs are plain dollars so consumers can format them themselves
+#[derive(Debug, Serialize)]
+struct StatusLineJson<'a> {
+    current_dir: &'a str,
+    git_branch: Option<&'a str>,
+    git_dirty: bool,
+    model: &'a str,
+    today_cost: Option<f64>,
+    session_cost: f64,
+    block_cost: f64,
+    burn_rate_per_hour: Option<f64>,
+    projected_block_cost: Option<f64>,
+    tokens_per_minute: Option<f64>,
+    cache_hit_ratio: Option<f64>,
+    remaining_minutes: Option<i64>,
+    context_tokens: Option<u64>,
+    context_percentage: Option<usize>,
+    daily_budget_percentage: Option<usize>,
+}
+
+/// Computed statusline values, independent of how they are rendered
+///
+/// The binary fills this once and picks a renderer, so the same values can be
+/// printed with colors for the hook or plain for logs and tests.
+#[derive(Debug, Clone, Default)]
+pub struct StatusLine {
+    pub current_dir: String,
+    pub git_branch: Option<String>,
+    /// Uncommitted changes, shown as a `*` after the branch
+    pub git_dirty: bool,
+    pub model: String,
+    /// Output style name, only set when it is not the default style
+    pub output_style: Option<String>,
+    pub remaining_time: Option<RemainingTime>,
+    /// Active block's clock window, shown instead of the remaining time when set
+    pub block_window: Option<String>,
+    /// Local clock time the active block resets, shown instead of the time left
+    pub reset_clock: Option<String>,
+    /// Active block is about to end, shown as a short hint after the time
+    pub block_ending_soon: bool,
+    /// Today's cost across sessions, absent when only the current session was loaded
+    pub today_cost: Option<Cost>,
+    pub cost_per_message: Option<Cost>,
+    pub session_cost: Cost,
+    pub block_cost: Cost,
+    pub budget_left: Option<Cost>,
+    /// Today's cost against CCR_DAILY_BUDGET
+    pub daily_budget: Option<Budget>,
+    pub burn_rate: Option<BurnRate>,
+    /// Block cost at its end at the current burn rate, shown after the rate
+    pub projected_block_cost: Option<Cost>,
+    /// Input and output tokens per minute in the active block (CCR_SHOW_TOKEN_RATE)
+    pub token_rate: Option<TokenBurnRate>,
+    /// Share of today's input tokens read from the cache, 0.0 to 1.0 (CCR_SHOW_CACHE_HIT)
+    pub cache_hit_ratio: Option<f64>,
+    pub burn_thresholds: BurnThresholds,
+    pub context: Option<ContextUsage>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    /// Claude Code version from the hook, only shown through a template
+    pub version: Option<String>,
+}
+
+impl StatusLine {
+    /// Render without any ANSI escape sequences
+    pub fn render_plain(&self) -> String {
+        self.render(false)
+    }
+
+    /// Render with colors for terminal output
+    pub fn render_colored(&self) -> String {
+        self.render(true)
+    }
+
+    /// Serialize the values as one JSON object
+    pub fn to_json(&self) -> String {
+        let json = StatusLineJson {
+            current_dir: &self.current_dir,
+            git_branch: self.git_branch.as_deref(),
+            git_dirty: self.git_dirty,
+            model: &self.model,
+            today_cost: self.today_cost.map(|cost| cost.value()),
+            session_cost: self.session_cost.value(),
+            block_cost: self.block_cost.value(),
+            burn_rate_per_hour: self.burn_rate.map(|rate| rate.value()),
+            projected_block_cost: self.projected_block_cost.map(|cost| cost.value()),
+            tokens_per_minute: self.token_rate.map(|rate| rate.tokens_per_minute()),
+            cache_hit_ratio: self.cache_hit_ratio,
+            remaining_minutes: self.remaining_time.map(|time| time.minutes()),
+            context_tokens: self.context.as_ref().map(|context| context.used_tokens),
+            context_percentage: self.context.as_ref().map(|context| context.percentage),
+            daily_budget_percentage: self.daily_budget.map(|budget| budget.percentage),
+        };
+        // Plain strings and numbers always serialize
+        serde_json::to_string(&json).unwrap_or_default()
+    }
+
+    /// Terminal columns taken by the rendered line, counting emoji as wide
+    pub fn display_width(&self) -> usize {
+        self.render_plain().width()
+    }
+
+    /// Drop lower-priority segments until the line fits in `max_width` columns
+    /// Segments go in a fixed order: lines changed and cache hits, block cost
+    /// and burn rate, daily budget, context, block time, output style, then git branch. Directory, model
+    /// and costs are always kept, so the result may still be wider.
+    pub fn fit_to_width(&self, max_width: usize) -> StatusLine {
+        let mut line = self.clone();
+        for segment in DROP_ORDER {
+            if line.display_width() <= max_width {
+                break;
+            }
+            line.drop_segment(segment);
+        }
+        line
+    }
+
+    fn drop_segment(&mut self, segment: Segment) {
+        match segment {
+            Segment::LinesChanged => {
+                self.lines_added = 0;
+                self.lines_removed = 0;
+                self.cache_hit_ratio = None;
+            }
+            Segment::Block => {
+                self.block_cost = Cost::default();
+                self.budget_left = None;
+                self.burn_rate = None;
+                self.projected_block_cost = None;
+                self.token_rate = None;
+            }
+            Segment::DailyBudget => self.daily_budget = None,
+            Segment::Context => self.context = None,
+            Segment::Time => {
+                self.remaining_time = None;
+                self.block_window = None;
+                self.reset_clock = None;
+                self.block_ending_soon = false;
+            }
+            Segment::OutputStyle => self.output_style = None,
+            Segment::GitBranch => self.git_branch = None,
+        }
+    }
+
+    fn render(&self, colored: bool) -> String {
+        let paint = |s: ColoredString| if colored { s.to_string() } else { s.input };
+        let reset = if colored { RESET } else { "" };
+
+        let mut out = String::with_capacity(256);
+        out.push_str(reset);
+        out.push_str(&paint(self.current_dir.green()));
+
+        if let Some(branch) = self.branch_text() {
+            out.push(' ');
+            out.push_str(&paint(branch.cyan()));
+        }
+
+        out.push_str(" 👤 ");
+        let model = if ModelFamily::from_name(&self.model) == ModelFamily::Opus {
+            self.model.white()
+        } else {
+            self.model.yellow().bold()
+        };
+        out.push_str(&paint(model));
+
+        if let Some(ref style) = self.output_style {
+            out.push_str(&format!(" [{}]", paint(style.yellow())));
+        }
+        out.push_str(reset);
+
+        if let Some(time) = self.time_text(colored) {
+            out.push_str(&format!(" ⏰ {}", time));
+        }
+        if self.block_ending_soon {
+            out.push_str(&format!(" ⏳ {}", paint("block ending".magenta())));
+        }
+
+        out.push_str(" 💰 ");
+        if let Some(today_cost) = self.today_cost {
+            out.push_str(&format!("{} today", today_cost));
+            if let Some(per_message) = self.cost_per_message {
+                out.push_str(&format!(" ({}/msg)", per_message));
+            }
+            out.push_str(", ");
+        }
+        out.push_str(&format!("{} session", self.session_cost));
+
+        if self.block_cost.is_positive() {
+            out.push_str(&format!(", {} block", self.block_cost));
+            if let Some(left) = self.budget_left {
+                out.push_str(&format!(" ({} left)", left));
+            }
+        }
+
+        if let Some(budget) = self.budget_text(colored) {
+            out.push_str(&format!(" 💸 {}", budget));
+        }
+
+        if let Some(rate) = self.burn_rate_text(colored) {
+            out.push_str(&format!(" 🔥 {}", rate));
+            if let Some(projected) = self.projected_block_cost {
+                out.push_str(&format!(" → {} proj", projected));
+            }
+        }
+
+        if let Some(rate) = self.token_rate {
+            out.push_str(&format!(" ⚡ {}", rate));
+        }
+
+        if let Some(cache_hit) = self.cache_hit_text(colored) {
+            out.push_str(&format!(" ♻️ {}", cache_hit));
+        }
+
+        if let Some(context) = self.context_text(colored) {
+            out.push_str(&format!(" ⚖️ {}", context));
+        }
+
+        if let Some(lines) = self.lines_text(colored) {
+            out.push_str(&format!(" ✏️ {}", lines));
+        }
+
+        out
+    }
+
+    /// Branch name with a `*` when the working tree is dirty
+    pub(crate) fn branch_text(&self) -> Option<String> {
+        let branch = self.git_branch.as_ref()?;
+        Some(if self.git_dirty {
+            format!("{}*", branch)
+        } else {
+            branch.clone()
+        })
+    }
+
+    /// Block window, reset time or time left, None when there is no active block
+    pub(crate) fn time_text(&self, colored: bool) -> Option<String> {
+        let paint = |s: ColoredString| if colored { s.to_string() } else { s.input };
+        if let Some(ref window) = self.block_window {
+            Some(paint(window.magenta()))
+        } else {
+            let remaining = self.remaining_time.filter(|r| r.has_remaining())?;
+            Some(match self.reset_clock {
+                Some(ref clock) => paint(format!("resets {}", clock).magenta()),
+                None => paint(remaining.to_colored_string()),
+            })
+        }
+    }
+
+    pub(crate) fn budget_text(&self, colored: bool) -> Option<String> {
+        let budget = self.daily_budget?;
+        Some(if colored {
+            budget.to_colored_string()
+        } else {
+            budget.to_plain_string()
+        })
+    }
+
+    pub(crate) fn burn_rate_text(&self, colored: bool) -> Option<String> {
+        let rate = self.burn_rate?.to_colored_string_with(self.burn_thresholds);
+        Some(if colored {
+            rate.to_string()
+        } else {
+            rate.input
+        })
+    }
+
+    /// Cache-hit percentage, green when most input comes from the cache
+    pub(crate) fn cache_hit_text(&self, colored: bool) -> Option<String> {
+        let percentage = self.cache_hit_ratio.filter(|ratio| ratio.is_finite())? * 100.0;
+        let text = format!("{:.0}%", percentage);
+        if !colored {
+            return Some(text);
+        }
+        let severity = if percentage >= CACHE_HIT_GOOD_PERCENTAGE {
+            Severity::Normal
+        } else if percentage >= CACHE_HIT_POOR_PERCENTAGE {
+            Severity::Warning
+        } else {
+            Severity::Critical
+        };
+        Some(Palette::from_env().paint(text, severity).to_string())
+    }
+
+    pub(crate) fn context_text(&self, colored: bool) -> Option<String> {
+        let context = self.context?;
+        Some(if colored {
+            context.to_colored_string()
+        } else {
+            context.to_plain_string()
+        })
+    }
+
+    /// Lines added and removed, e.g. "+23 -17"; None when nothing changed
+    pub(crate) fn lines_text(&self, colored: bool) -> Option<String> {
+        let paint = |s: ColoredString| if colored { s.to_string() } else { s.input };
+        let mut lines = Vec::new();
+        if self.lines_added > 0 {
+            lines.push(paint(format!("+{}", self.lines_added).green()));
+        }
+        if self.lines_removed > 0 {
+            lines.push(paint(format!("-{}", self.lines_removed).red()));
+        }
+        (!lines.is_empty()).then(|| lines.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SESSION_BLOCK_DURATION;
+    use crate::types::{ContextTokens, SessionBlock};
+    use chrono::{TimeZone, Utc};
+
+    fn sample() -> StatusLine {
+        StatusLine {
+            current_dir: "ccr".to_string(),
+            git_branch: Some("main".to_string()),
+            git_dirty: false,
+            model: "Opus 4.1".to_string(),
+            output_style: Some("Learning".to_string()),
+            remaining_time: Some(RemainingTime::new(78)),
+            block_window: None,
+            reset_clock: None,
+            block_ending_soon: false,
+            today_cost: Some(Cost::new(63.87)),
+            cost_per_message: None,
+            session_cost: Cost::new(11.58),
+            block_cost: Cost::new(62.35),
+            budget_left: None,
+            daily_budget: None,
+            burn_rate: Some(BurnRate::new(21.13)),
+            projected_block_cost: None,
+            token_rate: None,
+            cache_hit_ratio: None,
+            burn_thresholds: BurnThresholds::default(),
+            context: Some(ContextTokens::new(108_887).usage_with_api(70, 155_000)),
+            lines_added: 23,
+            lines_removed: 17,
+            version: None,
+        }
+    }
+
+    /// Forces colored output while alive and restores terminal detection on
+    /// drop, so the global override does not leak into other tests
+    struct ForceColor;
+
+    impl ForceColor {
+        fn new() -> Self {
+            colored::control::set_override(true);
+            ForceColor
+        }
+    }
+
+    impl Drop for ForceColor {
+        fn drop(&mut self) {
+            colored::control::unset_override();
+        }
+    }
+
+    #[test]
+    fn test_render_plain_has_no_escape_sequences() {
+        let line = sample();
+
+        let plain = line.render_plain();
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(
+            plain,
+            "ccr main 👤 Opus 4.1 [Learning] ⏰ 1h 18m left 💰 $63.87 today, $11.58 session, $62.35 block 🔥 $21.13/hr ⚖️ 70% (108,887 / 155,000) ✏️ +23 -17"
+        );
+
+        let colored = {
+            let _force = ForceColor::new();
+            line.render_colored()
+        };
+        assert!(colored.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_to_json_has_raw_values() {
+        assert_eq!(
+            sample().to_json(),
+            r#"{"current_dir":"ccr","git_branch":"main","git_dirty":false,"model":"Opus 4.1","today_cost":63.87,"session_cost":11.58,"block_cost":62.35,"burn_rate_per_hour":21.13,"projected_block_cost":null,"tokens_per_minute":null,"cache_hit_ratio":null,"remaining_minutes":78,"context_tokens":108887,"context_percentage":70,"daily_budget_percentage":null}"#
+        );
+
+        let empty = StatusLine::default().to_json();
+        assert!(empty.contains(r#""git_branch":null"#));
+        assert!(empty.contains(r#""burn_rate_per_hour":null"#));
+
+        assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_fit_to_width_drops_segments_in_order() {
+        let line = sample();
+        let full = line.display_width();
+        assert_eq!(line.fit_to_width(full).render_plain(), line.render_plain());
+
+        // Emoji are two columns wide, so the width exceeds the char count
+        let plain = line.render_plain();
+        assert!(full > plain.chars().count());
+
+        let narrower = line.fit_to_width(full - 1).render_plain();
+        assert!(!narrower.contains("✏️"));
+        assert!(narrower.contains("block"));
+
+        assert_eq!(
+            line.fit_to_width(40).render_plain(),
+            "ccr 👤 Opus 4.1 💰 $63.87 today, $11.58 session"
+        );
+        // Costs are never dropped, even if the line stays too wide
+        assert_eq!(
+            line.fit_to_width(10).render_plain(),
+            line.fit_to_width(40).render_plain()
+        );
+    }
+
+    #[test]
+    fn test_render_plain_block_ending_soon() {
+        let line = StatusLine {
+            current_dir: "tmp".to_string(),
+            model: "Sonnet 4".to_string(),
+            remaining_time: Some(RemainingTime::new(7)),
+            block_ending_soon: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            line.render_plain(),
+            "tmp 👤 Sonnet 4 ⏰ 7m left ⏳ block ending 💰 $0.00 session"
+        );
+    }
+
+    #[test]
+    fn test_render_plain_optional_segments() {
+        let line = StatusLine {
+            current_dir: "tmp".to_string(),
+            model: "Sonnet 4".to_string(),
+            today_cost: Some(Cost::new(1.0)),
+            budget_left: Some(Cost::new(2.5)),
+            ..Default::default()
+        };
+
+        // Zero block cost hides the block and budget segments
+        assert_eq!(
+            line.render_plain(),
+            "tmp 👤 Sonnet 4 💰 $1.00 today, $0.00 session"
+        );
+
+        // Minimal mode has no cross-session total
+        let minimal = StatusLine {
+            today_cost: None,
+            cost_per_message: Some(Cost::new(0.1)),
+            ..line
+        };
+        assert_eq!(minimal.render_plain(), "tmp 👤 Sonnet 4 💰 $0.00 session");
+
+        let budgeted = StatusLine {
+            daily_budget: Some(Budget::from_cost_and_limit(
+                Cost::new(14.4),
+                Cost::new(20.0),
+            )),
+            ..minimal
+        };
+        assert_eq!(
+            budgeted.render_plain(),
+            "tmp 👤 Sonnet 4 💰 $0.00 session 💸 72% of $20"
+        );
+        assert!(
+            budgeted
+                .to_json()
+                .contains(r#""daily_budget_percentage":72"#)
+        );
+        // Dropped after the block segment when the line is too wide
+        let fitted = budgeted.fit_to_width(30);
+        assert_eq!(fitted.render_plain(), "tmp 👤 Sonnet 4 💰 $0.00 session");
+    }
+
+    #[test]
+    fn test_render_plain_projected_block_cost() {
+        let line = StatusLine {
+            projected_block_cost: Some(Cost::new(4.8)),
+            ..sample()
+        };
+        assert!(line.render_plain().contains("🔥 $21.13/hr → $4.80 proj ⚖️"));
+        assert!(line.to_json().contains(r#""projected_block_cost":4.8"#));
+
+        // Shown only together with the burn rate
+        let no_rate = StatusLine {
+            burn_rate: None,
+            ..line
+        };
+        assert!(!no_rate.render_plain().contains("proj"));
+    }
+
+    #[test]
+    fn test_render_plain_dirty_branch() {
+        let line = StatusLine {
+            git_dirty: true,
+            ..sample()
+        };
+        assert!(line.render_plain().starts_with("ccr main* 👤"));
+        assert!(
+            line.to_json()
+                .contains(r#""git_branch":"main","git_dirty":true"#)
+        );
+
+        // Nothing to mark without a branch
+        let no_branch = StatusLine {
+            git_branch: None,
+            ..line
+        };
+        assert!(no_branch.render_plain().starts_with("ccr 👤"));
+    }
+
+    #[test]
+    fn test_render_plain_token_rate() {
+        let line = StatusLine {
+            token_rate: Some(TokenBurnRate::new(12_345.0)),
+            ..sample()
+        };
+        assert!(
+            line.render_plain()
+                .contains("🔥 $21.13/hr ⚡ 12.3k tok/min ⚖️")
+        );
+        assert!(line.to_json().contains(r#""tokens_per_minute":12345.0"#));
+    }
+
+    #[test]
+    fn test_render_plain_cache_hit_ratio() {
+        let line = StatusLine {
+            cache_hit_ratio: Some(0.634),
+            ..sample()
+        };
+        assert!(line.render_plain().contains("🔥 $21.13/hr ♻️ 63% ⚖️"));
+        assert!(line.to_json().contains(r#""cache_hit_ratio":0.634"#));
+        assert_eq!(line.fit_to_width(0).cache_hit_ratio, None);
+
+        // A ratio from zero input tokens is not shown as "NaN%"
+        let undefined = StatusLine {
+            cache_hit_ratio: Some(f64::NAN),
+            ..sample()
+        };
+        assert!(!undefined.render_plain().contains("♻️"));
+    }
+
+    #[test]
+    fn test_render_plain_time_styles() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap();
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![],
+        };
+        let line = StatusLine {
+            current_dir: "tmp".to_string(),
+            model: "Sonnet 4".to_string(),
+            remaining_time: Some(RemainingTime::new(78)),
+            today_cost: Some(Cost::new(0.0)),
+            ..Default::default()
+        };
+
+        // Remaining (default)
+        assert_eq!(
+            line.render_plain(),
+            "tmp 👤 Sonnet 4 ⏰ 1h 18m left 💰 $0.00 today, $0.00 session"
+        );
+
+        // Window replaces the remaining time
+        let window = StatusLine {
+            block_window: Some(block.window_string(&Utc)),
+            ..line.clone()
+        };
+        assert_eq!(
+            window.render_plain(),
+            "tmp 👤 Sonnet 4 ⏰ 14:00–19:00 💰 $0.00 today, $0.00 session"
+        );
+
+        // Reset clock replaces the countdown, but not once the block expired
+        let reset = StatusLine {
+            reset_clock: Some(block.reset_string(&Utc)),
+            ..line
+        };
+        assert_eq!(
+            reset.render_plain(),
+            "tmp 👤 Sonnet 4 ⏰ resets 19:00 💰 $0.00 today, $0.00 session"
+        );
+        let expired = StatusLine {
+            remaining_time: Some(RemainingTime::new(0)),
+            ..reset
+        };
+        assert_eq!(
+            expired.render_plain(),
+            "tmp 👤 Sonnet 4 💰 $0.00 today, $0.00 session"
+        );
+    }
+}
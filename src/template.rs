@@ -0,0 +1,205 @@
+use crate::config;
+use crate::error::{CcrError, Result};
+use crate::statusline::StatusLine;
+use std::fs;
+use std::path::PathBuf;
+
+/// Placeholder names accepted in a template
+pub const PLACEHOLDERS: [&str; 16] = [
+    "dir",
+    "branch",
+    "model",
+    "style",
+    "version",
+    "remaining",
+    "today",
+    "session",
+    "block",
+    "budget",
+    "burn_rate",
+    "projected",
+    "token_rate",
+    "cache_hit",
+    "context",
+    "lines",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+/// A user-defined statusline layout such as `{dir} {model} 💰 {session}`
+///
+/// Each placeholder expands to the bare value, without the emoji of the
+/// default line; values that are absent (no active block, no git branch)
+/// expand to an empty string. `{{` and `}}` produce literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusTemplate {
+    parts: Vec<Part>,
+}
+
+impl StatusTemplate {
+    /// Parse a template, rejecting unknown or unclosed placeholders
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |message: String| CcrError::InvalidTemplate { message };
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(invalid(format!("unclosed placeholder '{{{}'", name)));
+                            }
+                        }
+                    }
+                    let placeholder = PLACEHOLDERS
+                        .iter()
+                        .find(|valid| **valid == name)
+                        .ok_or_else(|| {
+                            invalid(format!(
+                                "unknown placeholder '{{{}}}', expected one of: {}",
+                                name,
+                                PLACEHOLDERS.join(", ")
+                            ))
+                        })?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder(placeholder));
+                }
+                other => text.push(other),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Template from CCR_TEMPLATE, else from `~/.config/ccr/template`
+    /// Returns None when neither is set, so the default line is used
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Some(template) = config::env_var("CCR_TEMPLATE") {
+            return Self::parse(&template).map(Some);
+        }
+        let Some(path) = template_path() else {
+            return Ok(None);
+        };
+        match fs::read_to_string(&path) {
+            Ok(template) => Self::parse(template.trim_end_matches(['\n', '\r'])).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Expand the placeholders with the statusline values
+    pub fn render(&self, line: &StatusLine, colored: bool) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(name) => placeholder_value(line, name, colored),
+            })
+            .collect()
+    }
+}
+
+fn template_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".config/ccr/template"))
+}
+
+fn placeholder_value(line: &StatusLine, name: &str, colored: bool) -> String {
+    let value = match name {
+        "dir" => Some(line.current_dir.clone()),
+        "branch" => line.branch_text(),
+        "model" => Some(line.model.clone()),
+        "style" => line.output_style.clone(),
+        "version" => line.version.clone(),
+        "remaining" => line.time_text(colored),
+        "today" => line.today_cost.map(|cost| cost.to_string()),
+        "session" => Some(line.session_cost.to_string()),
+        "block" => line
+            .block_cost
+            .is_positive()
+            .then(|| line.block_cost.to_string()),
+        "budget" => line.budget_text(colored),
+        "burn_rate" => line.burn_rate_text(colored),
+        "projected" => line.projected_block_cost.map(|cost| cost.to_string()),
+        "token_rate" => line.token_rate.map(|rate| rate.to_string()),
+        "cache_hit" => line.cache_hit_text(colored),
+        "context" => line.context_text(colored),
+        "lines" => line.lines_text(colored),
+        _ => None,
+    };
+    value.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BurnRate, Cost, RemainingTime};
+
+    fn sample() -> StatusLine {
+        StatusLine {
+            current_dir: "ccr".to_string(),
+            git_branch: Some("main".to_string()),
+            model: "Opus 4.1".to_string(),
+            version: Some("1.0.80".to_string()),
+            remaining_time: Some(RemainingTime::new(78)),
+            today_cost: Some(Cost::new(63.87)),
+            session_cost: Cost::new(11.58),
+            block_cost: Cost::new(62.35),
+            burn_rate: Some(BurnRate::new(21.13)),
+            lines_added: 23,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_template_render() {
+        let template =
+            StatusTemplate::parse("{dir} {model} v{version} ⏰ {remaining} 💰 {session} {lines}")
+                .unwrap();
+        assert_eq!(
+            template.render(&sample(), false),
+            "ccr Opus 4.1 v1.0.80 ⏰ 1h 18m left 💰 $11.58 +23"
+        );
+
+        // Absent values expand to nothing
+        let idle = StatusLine {
+            block_cost: Cost::default(),
+            burn_rate: None,
+            ..sample()
+        };
+        let template = StatusTemplate::parse("[{block}|{burn_rate}|{context}] {{x}}").unwrap();
+        assert_eq!(template.render(&idle, false), "[||] {x}");
+    }
+
+    #[test]
+    fn test_template_unknown_placeholder() {
+        let error = StatusTemplate::parse("{dir} {cost}").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("'{cost}'"));
+        assert!(message.contains("burn_rate"));
+
+        assert!(StatusTemplate::parse("{dir").is_err());
+    }
+}
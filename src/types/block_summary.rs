@@ -0,0 +1,112 @@
+use super::burn_rate::BurnRate;
+use super::cost::Cost;
+use super::ids::ModelId;
+use super::remaining_time::RemainingTime;
+use super::session::SessionBlock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Which variant of [`SessionBlock`] a summary describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    Active,
+    Completed,
+    Idle,
+}
+
+/// Flattened view of a session block for dashboards and machine-readable output
+///
+/// Fields that do not apply to a block are `None`: idle blocks have no burn
+/// rate or model, and only the active block has remaining minutes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockSummary {
+    pub kind: BlockKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub cost: Cost,
+    /// Dollars per hour over the span of the block's entries
+    pub burn_rate: Option<BurnRate>,
+    pub remaining_minutes: Option<i64>,
+    pub entry_count: usize,
+    pub dominant_model: Option<ModelId>,
+}
+
+impl SessionBlock {
+    /// Summarize the block as of now
+    pub fn summary(&self) -> BlockSummary {
+        self.summary_at(Utc::now())
+    }
+
+    /// Summarize the block as of `now`, which only affects remaining minutes
+    pub fn summary_at(&self, now: DateTime<Utc>) -> BlockSummary {
+        let kind = match self {
+            SessionBlock::Active { .. } => BlockKind::Active,
+            SessionBlock::Completed { .. } => BlockKind::Completed,
+            SessionBlock::Idle { .. } => BlockKind::Idle,
+        };
+        let remaining_minutes = self.is_active().then(|| {
+            RemainingTime::from_session_block_at(self, now)
+                .minutes()
+                .max(0)
+        });
+
+        BlockSummary {
+            kind,
+            start: self.start_time(),
+            end: self.end_time(),
+            cost: self.cost(),
+            burn_rate: BurnRate::from_session_block(self),
+            remaining_minutes,
+            entry_count: self.entries().len(),
+            dominant_model: self.dominant_model(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SESSION_BLOCK_DURATION;
+    use crate::types::{SessionId, UsageEntry, UsageEntryData};
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    fn entry(timestamp: &str, cost: f64) -> Arc<UsageEntry> {
+        let line = format!(
+            r#"{{"timestamp":"{}","costUSD":{},"model":"claude-sonnet-4-20250514","message":{{"id":"{}"}}}}"#,
+            timestamp, cost, timestamp
+        );
+        let data: UsageEntryData = serde_json::from_str(&line).unwrap();
+        Arc::new(UsageEntry::from_data(data, SessionId::from("s")))
+    }
+
+    #[test]
+    fn test_summary_json_for_each_kind() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let entries = vec![
+            entry("2024-01-15T10:00:00Z", 1.0),
+            entry("2024-01-15T10:30:00Z", 0.5),
+        ];
+        let now = start + chrono::Duration::hours(2);
+
+        let active = SessionBlock::new(start, entries.clone(), start, now, SESSION_BLOCK_DURATION);
+        assert_eq!(
+            serde_json::to_string(&active.summary_at(now)).unwrap(),
+            r#"{"kind":"active","start":"2024-01-15T10:00:00Z","end":"2024-01-15T15:00:00Z","cost":1.5,"burn_rate":3.0,"remaining_minutes":180,"entry_count":2,"dominant_model":"claude-sonnet-4-20250514"}"#
+        );
+
+        let later = start + chrono::Duration::hours(6);
+        let completed = SessionBlock::new(start, entries, start, later, SESSION_BLOCK_DURATION);
+        assert_eq!(
+            serde_json::to_string(&completed.summary_at(later)).unwrap(),
+            r#"{"kind":"completed","start":"2024-01-15T10:00:00Z","end":"2024-01-15T15:00:00Z","cost":1.5,"burn_rate":3.0,"remaining_minutes":null,"entry_count":2,"dominant_model":"claude-sonnet-4-20250514"}"#
+        );
+
+        let idle = SessionBlock::idle(start, start + chrono::Duration::hours(1));
+        assert_eq!(
+            serde_json::to_string(&idle.summary_at(now)).unwrap(),
+            r#"{"kind":"idle","start":"2024-01-15T10:00:00Z","end":"2024-01-15T11:00:00Z","cost":0.0,"burn_rate":null,"remaining_minutes":null,"entry_count":0,"dominant_model":null}"#
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::config;
+use crate::types::cost::Cost;
+use crate::types::palette::{Palette, Severity};
+use chrono::NaiveDate;
+use colored::ColoredString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Percentage of the budget at which the segment turns yellow
+const WARNING_PERCENTAGE: usize = 70;
+
+/// Percentage of the budget at which the segment turns red
+const CRITICAL_PERCENTAGE: usize = 90;
+
+/// Largest percentage shown, so a corrupt spend cannot widen the segment
+const MAX_PERCENTAGE: usize = 999;
+
+/// Spend measured against a daily limit, e.g. "72% of $20"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    pub spent: Cost,
+    /// Always positive
+    pub limit: Cost,
+    pub percentage: usize,
+}
+
+impl Budget {
+    /// Read the daily limit in dollars from CCR_DAILY_BUDGET
+    /// Zero, negative and unparsable values disable the budget
+    pub fn daily_limit_from_env() -> Option<Cost> {
+        config::env_parse::<f64>("CCR_DAILY_BUDGET")
+            .filter(|limit| *limit > 0.0)
+            .map(Cost::new)
+    }
+
+    /// Measure the spend against a positive limit
+    pub fn from_cost_and_limit(spent: Cost, limit: Cost) -> Self {
+        // A NaN spend would otherwise read as 0% and hide a corrupt total
+        let ratio = spent.value() / limit.value();
+        let percentage = if ratio.is_finite() {
+            ((ratio * 100.0).round().max(0.0) as usize).min(MAX_PERCENTAGE)
+        } else {
+            MAX_PERCENTAGE
+        };
+        Self {
+            spent,
+            limit,
+            percentage,
+        }
+    }
+
+    /// Spent more than the limit
+    pub fn is_exceeded(&self) -> bool {
+        self.spent.value() > self.limit.value()
+    }
+
+    pub fn severity(&self) -> Severity {
+        if self.is_exceeded() || self.percentage >= CRITICAL_PERCENTAGE {
+            Severity::Critical
+        } else if self.percentage >= WARNING_PERCENTAGE {
+            Severity::Warning
+        } else {
+            Severity::Normal
+        }
+    }
+
+    /// The limit without cents when it is whole, e.g. "$20" or "$12.50"
+    fn limit_string(&self) -> String {
+        let limit = self.limit.value();
+        if limit.fract() == 0.0 {
+            format!("${:.0}", limit)
+        } else {
+            format!("${:.2}", limit)
+        }
+    }
+
+    /// Get the percentage colored by severity
+    pub fn to_colored_percentage(&self) -> ColoredString {
+        Palette::from_env().paint(format!("{}%", self.percentage), self.severity())
+    }
+
+    /// Get formatted string with color coding for terminal output
+    pub fn to_colored_string(&self) -> String {
+        format!(
+            "{} of {}",
+            self.to_colored_percentage(),
+            self.limit_string()
+        )
+    }
+
+    /// Get formatted string without color codes
+    pub fn to_plain_string(&self) -> String {
+        format!("{}% of {}", self.percentage, self.limit_string())
+    }
+}
+
+/// Marker remembering the day the exceeded budget was last reported,
+/// `~/.cache/ccr/budget-alert`
+pub fn budget_alert_marker_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".cache/ccr/budget-alert"))
+}
+
+/// Claim today's budget alert, true when it was not reported yet today
+/// The statusline normally runs as one process per render, so the day is
+/// kept in a marker file; when it cannot be written the alert still fires
+pub fn claim_daily_alert(marker: &Path, today: NaiveDate) -> bool {
+    let today = today.format("%Y-%m-%d").to_string();
+    if fs::read_to_string(marker).is_ok_and(|day| day.trim() == today) {
+        return false;
+    }
+    if let Some(dir) = marker.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(marker, today);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_levels() {
+        let budget = Budget::from_cost_and_limit(Cost::new(14.4), Cost::new(20.0));
+        assert_eq!(budget.percentage, 72);
+        assert_eq!(budget.severity(), Severity::Warning);
+        assert_eq!(budget.to_plain_string(), "72% of $20");
+        assert!(!budget.is_exceeded());
+
+        let low = Budget::from_cost_and_limit(Cost::new(1.0), Cost::new(12.5));
+        assert_eq!(low.severity(), Severity::Normal);
+        assert_eq!(low.to_plain_string(), "8% of $12.50");
+
+        let over = Budget::from_cost_and_limit(Cost::new(25.0), Cost::new(20.0));
+        assert!(over.is_exceeded());
+        assert_eq!(over.severity(), Severity::Critical);
+        assert_eq!(over.to_plain_string(), "125% of $20");
+
+        let corrupt = Budget::from_cost_and_limit(Cost::new(f64::NAN), Cost::new(20.0));
+        assert_eq!(corrupt.percentage, MAX_PERCENTAGE);
+        assert_eq!(corrupt.severity(), Severity::Critical);
+        let huge = Budget::from_cost_and_limit(Cost::new(1e18), Cost::new(20.0));
+        assert_eq!(huge.percentage, MAX_PERCENTAGE);
+    }
+
+    #[test]
+    fn test_claim_daily_alert_once_per_day() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ccr").join("budget-alert");
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        assert!(claim_daily_alert(&marker, monday));
+        assert!(!claim_daily_alert(&marker, monday));
+        assert!(claim_daily_alert(&marker, monday.succ_opt().unwrap()));
+    }
+}
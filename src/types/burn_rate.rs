@@ -1,16 +1,159 @@
 use super::cost::Cost;
+use super::ids::ModelId;
+use super::palette::{Palette, Severity};
+use super::remaining_time::RemainingTime;
 use super::session::SessionBlock;
+use super::token_totals::TokenTotals;
+use crate::config;
+use chrono::{DateTime, Utc};
 use colored::ColoredString;
-use colored::Colorize;
+use serde::Serialize;
 use std::fmt;
+use std::str::FromStr;
+
+/// Time span used as the denominator of the burn rate
+///
+/// `Entries` divides the block cost by the span from the first to the last
+/// entry, which reacts quickly but spikes early in a block. `Elapsed` divides
+/// by the wall-clock time since the block started, which is smoother.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BurnBasis {
+    #[default]
+    Entries,
+    Elapsed,
+}
+
+impl BurnBasis {
+    /// Read the basis from CLAUDE_CCR_BURN_BASIS (entries|elapsed)
+    pub fn from_env() -> Self {
+        config::env_parse("CLAUDE_CCR_BURN_BASIS").unwrap_or_default()
+    }
+}
+
+impl FromStr for BurnBasis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "entries" => Ok(BurnBasis::Entries),
+            "elapsed" => Ok(BurnBasis::Elapsed),
+            other => Err(format!("unknown burn basis '{}'", other)),
+        }
+    }
+}
+
+/// Share of the block cost counted by the burn rate
+///
+/// Cache reads are cheap and fairly constant, so `ExcludeCacheRead` tracks
+/// only new work (input, output and cache writes) to better predict limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BurnCost {
+    #[default]
+    Total,
+    ExcludeCacheRead,
+}
+
+impl BurnCost {
+    /// ExcludeCacheRead when CLAUDE_CCR_BURN_EXCLUDE_CACHE_READ is enabled
+    pub fn from_env() -> Self {
+        if config::env_flag("CLAUDE_CCR_BURN_EXCLUDE_CACHE_READ") {
+            BurnCost::ExcludeCacheRead
+        } else {
+            BurnCost::Total
+        }
+    }
+
+    /// The counted part of a block's cost
+    pub fn of_block(&self, block: &SessionBlock) -> Cost {
+        match self {
+            BurnCost::Total => block.cost(),
+            BurnCost::ExcludeCacheRead => block.cost_composition().excluding_cache_read(),
+        }
+    }
+}
+
+/// Burn rate thresholds ($/hr) for the warning and critical colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BurnThresholds {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+impl Default for BurnThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 30.0,
+            critical: 100.0,
+        }
+    }
+}
+
+impl BurnThresholds {
+    /// Default threshold multiplier for Opus-dominated blocks
+    pub const DEFAULT_OPUS_SCALE: f64 = 2.0;
+
+    /// Multiply both thresholds by a factor
+    pub fn scaled(self, factor: f64) -> Self {
+        Self {
+            warning: self.warning * factor,
+            critical: self.critical * factor,
+        }
+    }
+
+    /// Thresholds for a block's dominant model
+    /// Opus legitimately burns faster, so its thresholds are scaled up;
+    /// mixed or unknown blocks keep the default thresholds
+    pub fn for_model(model: Option<&ModelId>, opus_scale: f64) -> Self {
+        match model {
+            Some(model) if model.is_opus() => Self::default().scaled(opus_scale),
+            _ => Self::default(),
+        }
+    }
+
+    /// Severity of a cost per hour under these thresholds
+    pub fn severity(&self, cost_per_hour: f64) -> Severity {
+        if cost_per_hour < self.warning {
+            Severity::Normal
+        } else if cost_per_hour < self.critical {
+            Severity::Warning
+        } else {
+            Severity::Critical
+        }
+    }
+
+    /// Thresholds for a dominant model, with the Opus scale read from
+    /// CLAUDE_CCR_OPUS_BURN_SCALE
+    pub fn from_env(model: Option<&ModelId>) -> Self {
+        let opus_scale = config::env_parse::<f64>("CLAUDE_CCR_OPUS_BURN_SCALE")
+            .filter(|scale| *scale > 0.0)
+            .unwrap_or(Self::DEFAULT_OPUS_SCALE);
+        Self::for_model(model, opus_scale)
+    }
+}
 
 /// Represents the burn rate (cost per hour) for a session
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+/// Serializes as the plain dollars per hour
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize)]
 pub struct BurnRate(f64);
 
 impl BurnRate {
+    /// Create from a raw cost per hour
+    pub fn new(cost_per_hour: f64) -> Self {
+        BurnRate(cost_per_hour)
+    }
+
+    /// Get the cost per hour
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+
     /// Create a BurnRate from a SessionBlock
     pub fn from_session_block(block: &SessionBlock) -> Option<Self> {
+        Self::entries_rate(block, block.cost())
+    }
+
+    // Cost per hour over the span from the first to the last entry
+    fn entries_rate(block: &SessionBlock, cost: Cost) -> Option<Self> {
         // Get actual duration in minutes from the block
         let duration_minutes = block.actual_duration_minutes()?;
 
@@ -20,20 +163,83 @@ impl BurnRate {
         }
 
         // Calculate cost per hour
-        let cost_per_hour = (block.cost().value() / duration_minutes) * 60.0;
+        let cost_per_hour = (cost.value() / duration_minutes) * 60.0;
+        Some(BurnRate(cost_per_hour))
+    }
+
+    /// Create a BurnRate over the block's wall-clock window (start to now)
+    /// For completed blocks the window ends at the block end
+    pub fn from_block_elapsed(block: &SessionBlock, now: DateTime<Utc>) -> Option<Self> {
+        Self::elapsed_rate(block, block.cost(), now)
+    }
+
+    // Cost per hour over the block's wall-clock window
+    fn elapsed_rate(block: &SessionBlock, cost: Cost, now: DateTime<Utc>) -> Option<Self> {
+        if block.is_idle() {
+            return None;
+        }
+
+        let window_end = now.min(block.end_time());
+        let elapsed_minutes = window_end
+            .signed_duration_since(block.start_time())
+            .num_minutes() as f64;
+
+        if elapsed_minutes <= 0.0 {
+            return None;
+        }
+
+        let cost_per_hour = (cost.value() / elapsed_minutes) * 60.0;
         Some(BurnRate(cost_per_hour))
     }
 
+    /// Create a BurnRate using the given basis
+    pub fn from_session_block_with(
+        block: &SessionBlock,
+        basis: BurnBasis,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        Self::from_block_cost(block, BurnCost::Total, basis, now)
+    }
+
+    /// Create a BurnRate counting only part of the block cost
+    pub fn from_block_cost(
+        block: &SessionBlock,
+        cost: BurnCost,
+        basis: BurnBasis,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let cost = cost.of_block(block);
+        match basis {
+            BurnBasis::Entries => Self::entries_rate(block, cost),
+            BurnBasis::Elapsed => Self::elapsed_rate(block, cost, now),
+        }
+    }
+
+    /// Cost of the block at its end if spending continues at this rate
+    /// Negative remaining time counts as none left
+    pub fn projected_block_cost(&self, remaining: RemainingTime, current: Cost) -> Cost {
+        let remaining_hours = remaining.minutes().max(0) as f64 / 60.0;
+        Cost::new(current.value() + self.0 * remaining_hours)
+    }
+
     /// Get a colored string representation for terminal output
     pub fn to_colored_string(&self) -> ColoredString {
+        self.to_colored_string_with(BurnThresholds::default())
+    }
+
+    /// Get a colored string representation using the given thresholds
+    pub fn to_colored_string_with(&self, thresholds: BurnThresholds) -> ColoredString {
+        self.to_colored_string_in(thresholds, Palette::from_env())
+    }
+
+    /// Get a colored string representation with explicit thresholds and palette
+    pub fn to_colored_string_in(
+        &self,
+        thresholds: BurnThresholds,
+        palette: Palette,
+    ) -> ColoredString {
         let rate_str = format!("{}/hr", Cost::new(self.0));
-        if self.0 < 30.0 {
-            rate_str.green()
-        } else if self.0 < 100.0 {
-            rate_str.yellow()
-        } else {
-            rate_str.red()
-        }
+        palette.paint(rate_str, thresholds.severity(self.0))
     }
 }
 
@@ -43,9 +249,150 @@ impl fmt::Display for BurnRate {
     }
 }
 
+/// Token throughput (input plus output tokens per minute) of a session block
+/// Rate limits count tokens rather than dollars, so this complements BurnRate
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize)]
+pub struct TokenBurnRate(f64);
+
+impl TokenBurnRate {
+    /// Create from a raw token count per minute
+    pub fn new(tokens_per_minute: f64) -> Self {
+        TokenBurnRate(tokens_per_minute)
+    }
+
+    /// Get the tokens per minute
+    pub fn tokens_per_minute(&self) -> f64 {
+        self.0
+    }
+
+    /// Input and output tokens over the span from the first to the last entry
+    /// Thinking tokens count as output; cache reads and writes are not counted
+    pub fn from_session_block(block: &SessionBlock) -> Option<Self> {
+        let duration_minutes = block.actual_duration_minutes()?;
+        if duration_minutes <= 0.0 {
+            return None;
+        }
+
+        let totals = TokenTotals::from_entries(block.entries().into_iter());
+        let tokens = totals.input_tokens + totals.output_tokens + totals.reasoning_output_tokens;
+        Some(TokenBurnRate(tokens as f64 / duration_minutes))
+    }
+}
+
+impl fmt::Display for TokenBurnRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 >= 1_000_000.0 {
+            write!(f, "{:.1}M tok/min", self.0 / 1_000_000.0)
+        } else if self.0 >= 1_000.0 {
+            write!(f, "{:.1}k tok/min", self.0 / 1_000.0)
+        } else {
+            write!(f, "{:.0} tok/min", self.0)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::SESSION_BLOCK_DURATION;
+    use crate::types::{SessionId, UsageEntry, UsageEntryData};
+    use chrono::{Duration, TimeZone};
+    use std::sync::Arc;
+
+    fn create_entry(timestamp: DateTime<Utc>, cost: f64) -> Arc<UsageEntry> {
+        Arc::new(UsageEntry::from_data(
+            UsageEntryData {
+                timestamp: Some(timestamp.to_rfc3339()),
+                model: None,
+                cost_usd: Some(cost),
+                message: None,
+                request_id: None,
+            },
+            SessionId::from("test-session"),
+        ))
+    }
+
+    #[test]
+    fn test_burn_rate_bases_on_same_block() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![
+                create_entry(start + Duration::minutes(30), 1.0),
+                create_entry(start + Duration::hours(1), 1.0),
+            ],
+        };
+        let now = start + Duration::hours(2);
+
+        // $2 over the 30 minutes between first and last entry
+        let entries = BurnRate::from_session_block_with(&block, BurnBasis::Entries, now).unwrap();
+        assert!((entries.0 - 4.0).abs() < 1e-9);
+
+        // $2 over the 2 hours since the block started
+        let elapsed = BurnRate::from_session_block_with(&block, BurnBasis::Elapsed, now).unwrap();
+        assert!((elapsed.0 - 1.0).abs() < 1e-9);
+
+        // Elapsed window never extends past the block end
+        let later = start + Duration::hours(10);
+        let capped = BurnRate::from_block_elapsed(&block, later).unwrap();
+        assert!((capped.0 - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_burn_rate_excluding_cache_read() {
+        use crate::types::{Message, Usage};
+
+        // Sonnet 4: 1M cache-read tokens ($0.30) and 10k output tokens ($0.15)
+        let entry = |timestamp: DateTime<Utc>| {
+            Arc::new(UsageEntry::from_data(
+                UsageEntryData {
+                    timestamp: Some(timestamp.to_rfc3339()),
+                    model: Some(ModelId::ClaudeSonnet4_20250514),
+                    cost_usd: None,
+                    message: Some(Message {
+                        id: None,
+                        model: Some(ModelId::ClaudeSonnet4_20250514),
+                        usage: Some(Usage {
+                            input_tokens: None,
+                            output_tokens: Some(10_000),
+                            cache_creation_input_tokens: None,
+                            cache_read_input_tokens: Some(1_000_000),
+                            cache_creation: None,
+                            service_tier: None,
+                            reasoning_output_tokens: None,
+                        }),
+                    }),
+                    request_id: None,
+                },
+                SessionId::from("test-session"),
+            ))
+        };
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![entry(start), entry(start + Duration::hours(1))],
+        };
+        let now = start + Duration::hours(1);
+
+        let total =
+            BurnRate::from_block_cost(&block, BurnCost::Total, BurnBasis::Entries, now).unwrap();
+        let new_work =
+            BurnRate::from_block_cost(&block, BurnCost::ExcludeCacheRead, BurnBasis::Entries, now)
+                .unwrap();
+        assert!((total.0 - 0.9).abs() < 1e-9);
+        assert!((new_work.0 - 0.3).abs() < 1e-9);
+        assert!(new_work < total);
+    }
+
+    #[test]
+    fn test_burn_basis_from_str() {
+        assert_eq!("entries".parse::<BurnBasis>(), Ok(BurnBasis::Entries));
+        assert_eq!("Elapsed".parse::<BurnBasis>(), Ok(BurnBasis::Elapsed));
+        assert!("hourly".parse::<BurnBasis>().is_err());
+        assert_eq!(BurnBasis::default(), BurnBasis::Entries);
+    }
 
     #[test]
     fn test_burn_rate_display() {
@@ -79,4 +426,117 @@ mod tests {
                 .contains("$150.00/hr")
         );
     }
+
+    #[test]
+    fn test_burn_rate_palette() {
+        use colored::Color;
+
+        let thresholds = BurnThresholds::default();
+        let rate = BurnRate(150.0);
+        assert_eq!(
+            rate.to_colored_string_in(thresholds, Palette::Default)
+                .fgcolor,
+            Some(Color::Red)
+        );
+        assert_eq!(
+            rate.to_colored_string_in(thresholds, Palette::Deuteranopia)
+                .fgcolor,
+            Some(Color::Magenta)
+        );
+        assert_eq!(
+            BurnRate(10.0)
+                .to_colored_string_in(thresholds, Palette::Deuteranopia)
+                .fgcolor,
+            Some(Color::Blue)
+        );
+    }
+
+    #[test]
+    fn test_burn_rate_model_scaled_thresholds() {
+        use colored::Color;
+
+        let opus = ModelId::ClaudeOpus4_1_20250805;
+        let opus_thresholds = BurnThresholds::for_model(Some(&opus), 2.0);
+
+        // A busy Opus block is a warning, not critical
+        let rate = BurnRate(80.0);
+        assert_eq!(
+            rate.to_colored_string_with(opus_thresholds).fgcolor,
+            Some(Color::Yellow)
+        );
+        let rate = BurnRate(150.0);
+        assert_eq!(rate.to_colored_string().fgcolor, Some(Color::Red));
+        assert_eq!(
+            rate.to_colored_string_with(opus_thresholds).fgcolor,
+            Some(Color::Yellow)
+        );
+
+        // Mixed/unknown and non-Opus blocks keep the fixed thresholds
+        assert_eq!(
+            BurnThresholds::for_model(None, 2.0),
+            BurnThresholds::default()
+        );
+        let sonnet = ModelId::ClaudeSonnet4_20250514;
+        assert_eq!(
+            BurnThresholds::for_model(Some(&sonnet), 2.0),
+            BurnThresholds::default()
+        );
+    }
+
+    #[test]
+    fn test_projected_block_cost() {
+        let current = Cost::new(2.0);
+        let remaining = RemainingTime::new(90);
+
+        // $2.00 so far plus 1.5 hours at $30/hr
+        let projected = BurnRate::new(30.0).projected_block_cost(remaining, current);
+        assert!((projected.value() - 47.0).abs() < 1e-9);
+
+        // At $3/hr the same block ends at $6.50
+        let projected = BurnRate::new(3.0).projected_block_cost(remaining, current);
+        assert!((projected.value() - 6.5).abs() < 1e-9);
+
+        // An expired block stays at its current cost
+        let expired = BurnRate::new(30.0).projected_block_cost(RemainingTime::new(-15), current);
+        assert_eq!(expired, current);
+    }
+
+    #[test]
+    fn test_token_burn_rate() {
+        let entry = |timestamp: DateTime<Utc>, line: &str| {
+            let mut data: UsageEntryData = serde_json::from_str(line).unwrap();
+            data.timestamp = Some(timestamp.to_rfc3339());
+            Arc::new(UsageEntry::from_data(data, SessionId::from("test-session")))
+        };
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        // 600k input and output tokens over 50 minutes; cache reads are not counted
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![
+                entry(
+                    start,
+                    r#"{"message":{"usage":{"input_tokens":100000,"output_tokens":200000,"cache_read_input_tokens":5000000}}}"#,
+                ),
+                entry(
+                    start + Duration::minutes(50),
+                    r#"{"message":{"usage":{"input_tokens":250000,"output_tokens":50000}}}"#,
+                ),
+            ],
+        };
+
+        let rate = TokenBurnRate::from_session_block(&block).unwrap();
+        assert!((rate.tokens_per_minute() - 12_000.0).abs() < 1e-9);
+        assert_eq!(rate.to_string(), "12.0k tok/min");
+        assert_eq!(TokenBurnRate::new(850.0).to_string(), "850 tok/min");
+        assert_eq!(TokenBurnRate::new(1_250_000.0).to_string(), "1.2M tok/min");
+
+        // A single entry spans no time
+        let single = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![entry(start, r#"{"message":{"usage":{"input_tokens":1}}}"#)],
+        };
+        assert_eq!(TokenBurnRate::from_session_block(&single), None);
+    }
 }
@@ -1,8 +1,62 @@
+use crate::config;
+use crate::types::ids::ModelId;
+use crate::types::palette::{Palette, Severity};
 use crate::types::{ContextWindow, TranscriptUsage};
-use colored::Colorize;
+use colored::ColoredString;
 use std::env;
 use std::fmt;
 
+/// Full context window size before reserves are subtracted
+const CONTEXT_WINDOW_TOKENS: usize = 200_000;
+
+/// Output reserve used when CLAUDE_CODE_MAX_OUTPUT_TOKENS is not set
+const DEFAULT_MAX_OUTPUT_TOKENS: usize = 32_000;
+
+/// Margin reserved before Claude Code auto-compacts the conversation
+const AUTO_COMPACT_MARGIN: usize = 13_000;
+
+/// Upper bound for the displayed percentage
+const MAX_PERCENTAGE: usize = 999;
+
+/// Percentage at which the context turns yellow
+const DEFAULT_WARNING_PERCENTAGE: usize = 70;
+
+/// Window of the 1M-context model variants
+const EXTENDED_CONTEXT_WINDOW_TOKENS: usize = 1_000_000;
+
+/// Full context window size for a model
+/// CLAUDE_CCR_CONTEXT_MAX overrides it for accounts with a different window;
+/// the output reserve and auto-compact margin are still subtracted from it
+pub fn context_window_for(model: Option<&ModelId>) -> usize {
+    context_window_in(
+        model,
+        config::env_parse::<usize>("CLAUDE_CCR_CONTEXT_MAX").filter(|tokens| *tokens > 0),
+    )
+}
+
+/// Full context window size for a model, unless overridden
+/// Claude Code marks the 1M-context variants with a `[1m]` suffix
+/// (e.g. `claude-sonnet-4-20250514[1m]`); other models have the 200K window
+fn context_window_in(model: Option<&ModelId>, override_tokens: Option<usize>) -> usize {
+    if let Some(tokens) = override_tokens {
+        return tokens;
+    }
+    match model {
+        Some(model) if model.as_str().to_ascii_lowercase().ends_with("[1m]") => {
+            EXTENDED_CONTEXT_WINDOW_TOKENS
+        }
+        _ => CONTEXT_WINDOW_TOKENS,
+    }
+}
+
+/// Output reserve from CLAUDE_CODE_MAX_OUTPUT_TOKENS
+fn max_output_tokens() -> usize {
+    env::var("CLAUDE_CODE_MAX_OUTPUT_TOKENS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS)
+}
+
 /// Represents the context token usage for a session
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct ContextTokens(u64);
@@ -13,14 +67,19 @@ impl ContextTokens {
         ContextTokens(tokens)
     }
 
+    /// Create from explicit input token counts
+    /// The context size is the total input including cache creation and cache reads
+    pub fn from_parts(input: u64, cache_creation: u64, cache_read: u64) -> Self {
+        ContextTokens(input + cache_creation + cache_read)
+    }
+
     /// Create from transcript usage data
     pub fn from_usage(usage: &TranscriptUsage) -> Self {
-        // Calculate total input tokens including cache
-        let total_input = usage.input_tokens.unwrap_or(0)
-            + usage.cache_creation_input_tokens.unwrap_or(0)
-            + usage.cache_read_input_tokens.unwrap_or(0);
-
-        ContextTokens(total_input)
+        Self::from_parts(
+            usage.input_tokens.unwrap_or(0),
+            usage.cache_creation_tokens(),
+            usage.cache_read_input_tokens.unwrap_or(0),
+        )
     }
 
     /// Create from API-provided context_window data
@@ -28,50 +87,87 @@ impl ContextTokens {
         ContextTokens(ctx.total_input_tokens)
     }
 
-    /// Calculate usage percentage and actual max tokens
-    fn calculate_percentage(&self) -> (usize, usize) {
-        let max_output_tokens = env::var("CLAUDE_CODE_MAX_OUTPUT_TOKENS")
-            .ok()
-            .and_then(|s| s.parse::<usize>().ok())
-            .unwrap_or(32_000);
+    /// Calculate usage percentage and actual max tokens against a model's window
+    /// The output reserve (CLAUDE_CODE_MAX_OUTPUT_TOKENS) and auto-compact
+    /// margin are subtracted from the window first
+    pub fn calculate_percentage(&self, model: &ModelId) -> (usize, usize) {
+        self.calculate_percentage_in(context_window_for(Some(model)), max_output_tokens())
+    }
 
-        let max_tokens = 200_000usize;
-        let auto_compact_margin = 13_000usize;
-        let actual_max_tokens = max_tokens
+    /// Calculate usage percentage and actual max tokens for a window and output reserve
+    fn calculate_percentage_in(&self, window: usize, max_output_tokens: usize) -> (usize, usize) {
+        let actual_max_tokens = window
             .saturating_sub(max_output_tokens)
-            .saturating_sub(auto_compact_margin);
+            .saturating_sub(AUTO_COMPACT_MARGIN);
 
-        let percentage = if actual_max_tokens > 0 {
-            ((self.0 as usize * 100) / actual_max_tokens).min(9999)
+        // A huge output reserve leaves no usable window at all;
+        // fall back to the raw window rather than producing nonsense
+        let actual_max_tokens = if actual_max_tokens == 0 {
+            window
         } else {
-            0
+            actual_max_tokens
         };
 
+        let percentage = ((self.0 as usize * 100) / actual_max_tokens).min(MAX_PERCENTAGE);
+
         (percentage, actual_max_tokens)
     }
 
-    /// Get formatted string with color coding for terminal output
-    pub fn to_formatted_string(&self) -> String {
-        let (percentage, actual_max_tokens) = self.calculate_percentage();
+    /// Compute context usage against the local window estimate
+    pub fn usage(&self) -> ContextUsage {
+        self.usage_for_model(None)
+    }
+
+    /// Compute context usage against the given model's window
+    /// The same token count is near-full on a 200K model but fine on a 1M one
+    pub fn usage_for_model(&self, model: Option<&ModelId>) -> ContextUsage {
+        self.usage_in(context_window_for(model), max_output_tokens())
+    }
+
+    /// Compute context usage for a window size and output reserve
+    fn usage_in(&self, window: usize, max_output_tokens: usize) -> ContextUsage {
+        let (percentage, actual_max_tokens) =
+            self.calculate_percentage_in(window, max_output_tokens);
         let warning_margin = 20_000usize;
         let warning_threshold = actual_max_tokens.saturating_sub(warning_margin);
 
-        let percentage_str = format!("{}%", percentage);
-        let percentage_str = if percentage < 70 {
-            percentage_str.green()
+        let level = if percentage < DEFAULT_WARNING_PERCENTAGE {
+            Severity::Normal
         } else if self.0 as usize <= warning_threshold {
-            percentage_str.yellow()
+            Severity::Warning
         } else {
-            percentage_str.red()
+            Severity::Critical
         };
 
-        let formatted_total = Self::format_number(self.0 as usize);
-        let formatted_max = Self::format_number(actual_max_tokens);
+        ContextUsage {
+            percentage,
+            used_tokens: self.0,
+            max_tokens: actual_max_tokens as u64,
+            level,
+        }
+    }
 
-        format!(
-            "{} ({} / {})",
-            percentage_str, formatted_total, formatted_max
-        )
+    /// Compute context usage from the API-provided percentage and window size
+    pub fn usage_with_api(&self, used_percentage: u8, context_window_size: u64) -> ContextUsage {
+        let level = if (used_percentage as usize) < DEFAULT_WARNING_PERCENTAGE {
+            Severity::Normal
+        } else if used_percentage < 90 {
+            Severity::Warning
+        } else {
+            Severity::Critical
+        };
+
+        ContextUsage {
+            percentage: used_percentage as usize,
+            used_tokens: self.0,
+            max_tokens: context_window_size,
+            level,
+        }
+    }
+
+    /// Get formatted string with color coding for terminal output
+    pub fn to_formatted_string(&self) -> String {
+        self.usage().to_colored_string()
     }
 
     /// Get formatted string using API-provided percentage and context window size
@@ -80,22 +176,8 @@ impl ContextTokens {
         used_percentage: u8,
         context_window_size: u64,
     ) -> String {
-        let percentage_str = format!("{}%", used_percentage);
-        let percentage_str = if used_percentage < 70 {
-            percentage_str.green()
-        } else if used_percentage < 90 {
-            percentage_str.yellow()
-        } else {
-            percentage_str.red()
-        };
-
-        let formatted_total = Self::format_number(self.0 as usize);
-        let formatted_max = Self::format_number(context_window_size as usize);
-
-        format!(
-            "{} ({} / {})",
-            percentage_str, formatted_total, formatted_max
-        )
+        self.usage_with_api(used_percentage, context_window_size)
+            .to_colored_string()
     }
 
     /// Format a number with thousands separator (private helper)
@@ -117,6 +199,85 @@ impl ContextTokens {
     }
 }
 
+/// User-configured percentages at which the context turns yellow and red
+/// Unset values keep the built-in rules: yellow from 70%, red near the
+/// auto-compact point (or from 90% with the API percentage)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ContextThresholds {
+    pub warning: Option<usize>,
+    pub critical: Option<usize>,
+}
+
+impl ContextThresholds {
+    /// Read the thresholds from CLAUDE_CCR_CTX_WARN and CLAUDE_CCR_CTX_CRIT
+    pub fn from_env() -> Self {
+        Self {
+            warning: config::env_parse("CLAUDE_CCR_CTX_WARN"),
+            critical: config::env_parse("CLAUDE_CCR_CTX_CRIT"),
+        }
+    }
+}
+
+/// Context usage ready for display, e.g. "70% (108,887 / 155,000)"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextUsage {
+    pub percentage: usize,
+    pub used_tokens: u64,
+    pub max_tokens: u64,
+    /// Severity of the usage, shown with the palette's ramp
+    pub level: Severity,
+}
+
+impl ContextUsage {
+    /// Recompute the level with user thresholds; only affects display
+    pub fn with_thresholds(self, thresholds: ContextThresholds) -> Self {
+        let critical = match thresholds.critical {
+            Some(critical) => self.percentage >= critical,
+            None => self.level == Severity::Critical,
+        };
+        let warning = self.percentage >= thresholds.warning.unwrap_or(DEFAULT_WARNING_PERCENTAGE);
+
+        let level = if critical {
+            Severity::Critical
+        } else if warning {
+            Severity::Warning
+        } else {
+            Severity::Normal
+        };
+
+        Self { level, ..self }
+    }
+
+    /// Get the percentage colored by level
+    pub fn to_colored_percentage(&self) -> ColoredString {
+        self.to_colored_percentage_in(Palette::from_env())
+    }
+
+    /// Get the percentage colored by level with an explicit palette
+    pub fn to_colored_percentage_in(&self, palette: Palette) -> ColoredString {
+        palette.paint(format!("{}%", self.percentage), self.level)
+    }
+
+    /// Get the token counts part, e.g. "(108,887 / 155,000)"
+    fn tokens_string(&self) -> String {
+        format!(
+            "({} / {})",
+            ContextTokens::format_number(self.used_tokens as usize),
+            ContextTokens::format_number(self.max_tokens as usize)
+        )
+    }
+
+    /// Get formatted string with color coding for terminal output
+    pub fn to_colored_string(&self) -> String {
+        format!("{} {}", self.to_colored_percentage(), self.tokens_string())
+    }
+
+    /// Get formatted string without color codes
+    pub fn to_plain_string(&self) -> String {
+        format!("{}% {}", self.percentage, self.tokens_string())
+    }
+}
+
 impl fmt::Display for ContextTokens {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} tokens", self.0)
@@ -133,15 +294,124 @@ mod tests {
         assert_eq!(format!("{}", tokens), "150000 tokens");
     }
 
+    #[test]
+    fn test_context_tokens_from_parts_matches_usage() {
+        let usage = TranscriptUsage {
+            input_tokens: Some(1_200),
+            output_tokens: Some(800),
+            cache_creation_input_tokens: Some(3_000),
+            cache_creation: None,
+            cache_read_input_tokens: None,
+        };
+
+        assert_eq!(
+            ContextTokens::from_parts(1_200, 3_000, 0),
+            ContextTokens::from_usage(&usage)
+        );
+        assert_eq!(
+            ContextTokens::from_parts(10, 20, 30),
+            ContextTokens::new(60)
+        );
+    }
+
+    #[test]
+    fn test_context_tokens_from_split_cache_creation() {
+        let line = r#"{"type":"assistant","message":{"usage":{"input_tokens":10,"cache_creation_input_tokens":999,"cache_creation":{"ephemeral_5m_input_tokens":1500,"ephemeral_1h_input_tokens":2500},"cache_read_input_tokens":40000,"output_tokens":300}}}"#;
+        let message: crate::types::TranscriptMessage = serde_json::from_str(line).unwrap();
+        let usage = message.message.unwrap().usage.unwrap();
+
+        // The split replaces the flat field rather than adding to it
+        assert_eq!(usage.cache_creation_tokens(), 4_000);
+        assert_eq!(
+            ContextTokens::from_usage(&usage),
+            ContextTokens::new(44_010)
+        );
+    }
+
+    #[test]
+    fn test_context_usage_with_api() {
+        let usage = ContextTokens::new(108_887).usage_with_api(70, 155_000);
+        assert_eq!(usage.level, Severity::Warning);
+        assert_eq!(usage.to_plain_string(), "70% (108,887 / 155,000)");
+        assert_eq!(
+            ContextTokens::new(1_000).usage_with_api(95, 200_000).level,
+            Severity::Critical
+        );
+    }
+
+    #[test]
+    fn test_context_usage_with_thresholds() {
+        let usage = ContextTokens::new(100_750).usage_with_api(65, 155_000);
+        assert_eq!(usage.level, Severity::Normal);
+
+        // A 60% warning threshold turns 65% yellow
+        let warn_60 = ContextThresholds {
+            warning: Some(60),
+            critical: None,
+        };
+        assert_eq!(usage.with_thresholds(warn_60).level, Severity::Warning);
+
+        // A critical threshold overrides the built-in red rule
+        let crit_64 = ContextThresholds {
+            warning: Some(60),
+            critical: Some(64),
+        };
+        assert_eq!(usage.with_thresholds(crit_64).level, Severity::Critical);
+        let high = ContextTokens::new(1_000).usage_with_api(92, 200_000);
+        let crit_95 = ContextThresholds {
+            warning: None,
+            critical: Some(95),
+        };
+        assert_eq!(high.with_thresholds(crit_95).level, Severity::Warning);
+
+        // Defaults keep the current levels
+        for usage in [
+            usage,
+            high,
+            ContextTokens::new(108_887).usage_with_api(70, 155_000),
+        ] {
+            assert_eq!(
+                usage.with_thresholds(ContextThresholds::default()).level,
+                usage.level
+            );
+        }
+    }
+
     #[test]
     fn test_context_tokens_percentage() {
         // This test depends on environment variables, so we just verify it doesn't panic
         let tokens = ContextTokens::new(50000);
-        let (percentage, actual_max) = tokens.calculate_percentage();
-        assert!(percentage <= 9999);
+        let (percentage, actual_max) =
+            tokens.calculate_percentage_in(CONTEXT_WINDOW_TOKENS, max_output_tokens());
+        assert!(percentage <= 999);
         assert!(actual_max > 0);
     }
 
+    #[test]
+    fn test_context_tokens_percentage_default_reserve() {
+        let tokens = ContextTokens::new(77_500);
+        let (percentage, actual_max) =
+            tokens.calculate_percentage_in(CONTEXT_WINDOW_TOKENS, 32_000);
+        assert_eq!(actual_max, 155_000);
+        assert_eq!(percentage, 50);
+    }
+
+    #[test]
+    fn test_context_tokens_percentage_extreme_max_output_tokens() {
+        // An output reserve larger than the window falls back to the raw window
+        let tokens = ContextTokens::new(50_000);
+        let (percentage, actual_max) =
+            tokens.calculate_percentage_in(CONTEXT_WINDOW_TOKENS, 10_000_000);
+        assert_eq!(actual_max, 200_000);
+        assert_eq!(percentage, 25);
+
+        // A reserve that leaves only a sliver of window clamps at 999%
+        let (percentage, actual_max) =
+            tokens.calculate_percentage_in(CONTEXT_WINDOW_TOKENS, 186_999);
+        assert_eq!(actual_max, 1);
+        assert_eq!(percentage, 999);
+    }
+
     #[test]
     fn test_context_tokens_formatted_string() {
         let tokens = ContextTokens::new(50000);
@@ -193,4 +463,91 @@ mod tests {
         assert!(formatted.contains("95%"));
         assert!(formatted.contains("180,000"));
     }
+
+    #[test]
+    fn test_context_usage_palette() {
+        use colored::Color;
+
+        let usage = ContextTokens::new(180000).usage_with_api(95, 200000);
+        assert_eq!(usage.level, Severity::Critical);
+        assert_eq!(
+            usage.to_colored_percentage_in(Palette::Default).fgcolor,
+            Some(Color::Red)
+        );
+        assert_eq!(
+            usage
+                .to_colored_percentage_in(Palette::Deuteranopia)
+                .fgcolor,
+            Some(Color::Magenta)
+        );
+    }
+
+    #[test]
+    fn test_context_usage_for_model_window() {
+        let tokens = ContextTokens::new(180_000);
+
+        // Near-full on a 200K model, comfortable on a 1M one
+        let standard = tokens.usage_in(CONTEXT_WINDOW_TOKENS, DEFAULT_MAX_OUTPUT_TOKENS);
+        let extended = tokens.usage_in(EXTENDED_CONTEXT_WINDOW_TOKENS, DEFAULT_MAX_OUTPUT_TOKENS);
+        assert_eq!(standard.level, Severity::Critical);
+        assert_eq!(extended.level, Severity::Normal);
+        assert_eq!(extended.max_tokens, 955_000);
+        assert_ne!(
+            standard.to_colored_percentage_in(Palette::Default).fgcolor,
+            extended.to_colored_percentage_in(Palette::Default).fgcolor
+        );
+
+        let sonnet_1m = ModelId::from("claude-sonnet-4-20250514[1m]");
+        assert_eq!(
+            context_window_for(Some(&sonnet_1m)),
+            EXTENDED_CONTEXT_WINDOW_TOKENS
+        );
+        assert_eq!(
+            context_window_for(Some(&ModelId::ClaudeSonnet4_20250514)),
+            CONTEXT_WINDOW_TOKENS
+        );
+        assert_eq!(context_window_for(None), CONTEXT_WINDOW_TOKENS);
+    }
+
+    #[test]
+    fn test_calculate_percentage_per_model() {
+        let tokens = ContextTokens::new(155_000);
+        let sonnet = context_window_in(Some(&ModelId::ClaudeSonnet4_20250514), None);
+        assert_eq!(
+            tokens.calculate_percentage_in(sonnet, DEFAULT_MAX_OUTPUT_TOKENS),
+            (100, 155_000)
+        );
+
+        let sonnet_1m = ModelId::from("claude-sonnet-4-20250514[1M]");
+        let extended = context_window_in(Some(&sonnet_1m), None);
+        assert_eq!(
+            tokens.calculate_percentage_in(extended, DEFAULT_MAX_OUTPUT_TOKENS),
+            (16, 955_000)
+        );
+
+        // A larger output reserve shrinks both windows alike
+        assert_eq!(
+            tokens.calculate_percentage_in(sonnet, 64_000),
+            (126, 123_000)
+        );
+        assert_eq!(
+            tokens.calculate_percentage_in(extended, 64_000),
+            (16, 923_000)
+        );
+    }
+
+    #[test]
+    fn test_context_max_override() {
+        // The override replaces the base window, reserves are still subtracted
+        let window = context_window_in(Some(&ModelId::ClaudeSonnet4_20250514), Some(1_000_000));
+        assert_eq!(window, 1_000_000);
+        let usage = ContextTokens::new(191_000).usage_in(window, DEFAULT_MAX_OUTPUT_TOKENS);
+        assert_eq!(usage.max_tokens, 955_000);
+        assert_eq!(usage.percentage, 20);
+
+        let default = context_window_in(None, None);
+        let usage = ContextTokens::new(191_000).usage_in(default, DEFAULT_MAX_OUTPUT_TOKENS);
+        assert_eq!(usage.max_tokens, 155_000);
+        assert_eq!(usage.percentage, 123);
+    }
 }
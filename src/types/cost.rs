@@ -1,8 +1,48 @@
-use crate::types::{ModelPricing, SessionBlock, UsageEntry, input::SessionCost};
+use crate::config;
+use crate::types::{ModelPricing, SessionBlock, UsageEntry, ids::ModelId, input::SessionCost};
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Rounding applied when a cost is displayed
+///
+/// Rounding only happens at display time: totals are summed at full
+/// precision and the final figure is rounded once. Rounding every entry up
+/// before summing would overstate the total by up to a cent per entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CostRounding {
+    /// Round to the nearest cent
+    #[default]
+    Nearest,
+    /// Round up to the next cent so the shown number never understates
+    Ceil,
+}
+
+impl CostRounding {
+    /// Read the rounding mode from CLAUDE_CCR_ROUND (nearest|ceil), once per process
+    pub fn from_env() -> Self {
+        static ROUNDING: OnceLock<CostRounding> = OnceLock::new();
+        *ROUNDING.get_or_init(|| config::env_parse("CLAUDE_CCR_ROUND").unwrap_or_default())
+    }
+}
+
+impl FromStr for CostRounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nearest" => Ok(CostRounding::Nearest),
+            "ceil" => Ok(CostRounding::Ceil),
+            other => Err(format!("unknown rounding mode '{}'", other)),
+        }
+    }
+}
 
 /// A newtype wrapper for cost values in USD
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+/// Serializes as the plain dollar amount
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Serialize)]
 pub struct Cost(f64);
 
 impl Cost {
@@ -12,13 +52,34 @@ impl Cost {
         Cost(value)
     }
 
+    /// Create a Cost for a single UsageEntry
+    #[inline]
+    pub fn from_entry(entry: &UsageEntry) -> Self {
+        Cost(calculate_entry_cost(entry))
+    }
+
     /// Create a Cost from an iterator of UsageEntry references
     pub fn from_entries<'a, I>(entries: I) -> Self
     where
         I: Iterator<Item = &'a UsageEntry>,
     {
-        let total = entries.map(calculate_entry_cost).sum();
-        Cost(total)
+        entries.map(Cost::from_entry).sum()
+    }
+
+    /// Cost of an entry's tokens had they been served by another model
+    /// Any pre-calculated cost is ignored; entries without token usage cost 0
+    pub fn from_entry_as_model(entry: &UsageEntry, model: &ModelId) -> Self {
+        calculate_entry_components_as(entry, model).map_or(Cost(0.0), |c| c.total())
+    }
+
+    /// Cost of an entry, pricing usage without its own model as the given model
+    /// A pre-calculated cost still takes precedence
+    pub fn from_entry_or_model(entry: &UsageEntry, fallback: &ModelId) -> Self {
+        if let Some(cost) = entry.data.cost_usd {
+            return Cost(cost);
+        }
+        let model = entry.model().unwrap_or(fallback);
+        calculate_entry_components_as(entry, model).map_or(Cost(0.0), |c| c.total())
     }
 
     /// Create a Cost from a SessionBlock
@@ -37,13 +98,56 @@ impl Cost {
         self.0
     }
 
-    /// Format as currency string (e.g., "$1.23")
+    /// Format as currency string (e.g., "$1.23") using the configured rounding
     pub fn to_formatted_string(&self) -> String {
+        self.to_formatted_string_with(CostRounding::from_env())
+    }
+
+    /// Format as currency string with an explicit rounding mode
+    pub fn to_formatted_string_with(&self, rounding: CostRounding) -> String {
+        self.to_formatted_string_clamped(rounding, cost_ceiling())
+    }
+
+    /// Format with explicit rounding and display ceiling
+    /// Costs above the ceiling (corrupt `costUSD` values such as 1e18) are
+    /// shown as e.g. "$>1M" so they cannot blow up the statusline layout;
+    /// so are NaN and infinite costs, which no comparison would catch
+    pub fn to_formatted_string_clamped(&self, rounding: CostRounding, ceiling: f64) -> String {
+        if !self.0.is_finite() || self.0 > ceiling {
+            return format!("$>{}", compact_dollars(ceiling));
+        }
+
+        let value = match rounding {
+            CostRounding::Nearest => self.0,
+            // Tolerate float noise so exact cents (e.g. 1.10) are not bumped up
+            CostRounding::Ceil => ((self.0 * 100.0) - 1e-9).ceil() / 100.0,
+        };
         // Handle negative zero case
-        let formatted_value = if self.0.abs() < 0.005 { 0.00 } else { self.0 };
+        let formatted_value = if value.abs() < 0.005 { 0.00 } else { value };
         format!("${:.2}", formatted_value)
     }
 
+    /// Format as a signed delta with color, e.g. "+$1.20" (red) or "-$0.50" (green)
+    /// More spend is red and less spend is green; deltas that round to zero are uncolored
+    pub fn to_signed_colored_string(&self) -> ColoredString {
+        // Same negative-zero guard as to_formatted_string
+        if self.0.abs() < 0.005 {
+            return Cost(0.0).to_formatted_string().normal();
+        }
+
+        let magnitude = Cost(self.0.abs()).to_formatted_string();
+        if self.0 > 0.0 {
+            format!("+{}", magnitude).red()
+        } else {
+            format!("-{}", magnitude).green()
+        }
+    }
+
+    /// Check if the cost is above the configured display ceiling or not finite
+    pub fn exceeds_ceiling(&self) -> bool {
+        !self.0.is_finite() || self.0 > cost_ceiling()
+    }
+
     /// Check if the cost is positive (greater than tolerance)
     #[inline]
     pub fn is_positive(&self) -> bool {
@@ -51,6 +155,30 @@ impl Cost {
     }
 }
 
+/// Displayed costs above this many dollars are treated as corrupt data
+pub const DEFAULT_COST_CEILING: f64 = 1_000_000.0;
+
+/// Display ceiling from CLAUDE_CCR_COST_CEILING (dollars), read once per process
+fn cost_ceiling() -> f64 {
+    static CEILING: OnceLock<f64> = OnceLock::new();
+    *CEILING.get_or_init(|| {
+        config::env_parse::<f64>("CLAUDE_CCR_COST_CEILING")
+            .filter(|ceiling| *ceiling > 0.0)
+            .unwrap_or(DEFAULT_COST_CEILING)
+    })
+}
+
+/// Short dollar amount for the clamped form, e.g. 1000000 -> "1M", 5000 -> "5K"
+fn compact_dollars(amount: f64) -> String {
+    if amount >= 1_000_000.0 && amount % 1_000_000.0 == 0.0 {
+        format!("{}M", amount / 1_000_000.0)
+    } else if amount >= 1_000.0 && amount % 1_000.0 == 0.0 {
+        format!("{}K", amount / 1_000.0)
+    } else {
+        format!("{}", amount)
+    }
+}
+
 impl fmt::Display for Cost {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.to_formatted_string())
@@ -69,6 +197,43 @@ impl From<Cost> for f64 {
     }
 }
 
+/// Running compensated (Kahan-Babuska) sum of costs, so totals over many
+/// entries do not drift with the number or order of the entries
+/// Every cost total and breakdown accumulates through this, so the parts
+/// always add up to the same figure as the whole
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct CostAccumulator {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CostAccumulator {
+    pub(crate) fn add(&mut self, Cost(value): Cost) {
+        let t = self.sum + value;
+        // Recover the low-order bits lost by whichever operand is smaller
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - t) + value;
+        } else {
+            self.compensation += (value - t) + self.sum;
+        }
+        self.sum = t;
+    }
+
+    pub(crate) fn total(&self) -> Cost {
+        Cost(self.sum + self.compensation)
+    }
+}
+
+impl std::iter::Sum for Cost {
+    fn sum<I: Iterator<Item = Cost>>(iter: I) -> Self {
+        let mut accumulator = CostAccumulator::default();
+        for cost in iter {
+            accumulator.add(cost);
+        }
+        accumulator.total()
+    }
+}
+
 impl From<&SessionCost> for Cost {
     fn from(session_cost: &SessionCost) -> Self {
         Cost(session_cost.total_cost_usd)
@@ -89,49 +254,150 @@ fn calculate_entry_cost(entry: &UsageEntry) -> f64 {
     }
 
     // Otherwise calculate from token usage
-    if let Some(message) = &entry.data.message
-        && let Some(usage) = &message.usage
-        && let Some(model_id) = message.model.as_ref().or(entry.data.model.as_ref())
+    calculate_entry_components(entry).map_or(0.0, |components| components.total().0)
+}
+
+/// Price each token kind of a single entry (private helper function)
+/// Returns None when the entry has no usage or model to price
+fn calculate_entry_components(entry: &UsageEntry) -> Option<CostComposition> {
+    calculate_entry_components_as(entry, entry.model()?)
+}
+
+/// Price each token kind of a single entry with the given model's pricing
+/// Returns None when the entry has no usage to price
+fn calculate_entry_components_as(entry: &UsageEntry, model: &ModelId) -> Option<CostComposition> {
+    let usage = entry.data.message.as_ref()?.usage.as_ref()?;
+    let pricing = ModelPricing::from(model);
+
+    // Cache creation cost based on format
+    let cache_creation = if let Some(cache_creation) = &usage.cache_creation {
+        // New format: calculate 5m and 1h cache separately with different prices
+        calculate_token_cost(
+            cache_creation.ephemeral_5m_input_tokens,
+            pricing.cache_creation_input_token_cost,
+        ) + calculate_token_cost(
+            cache_creation.ephemeral_1h_input_tokens,
+            pricing.cache_creation_1h_token_cost,
+        )
+    } else {
+        // Old format: direct calculation
+        calculate_token_cost(
+            usage.cache_creation_input_tokens,
+            pricing.cache_creation_input_token_cost,
+        )
+    };
+
+    Some(CostComposition {
+        input: Cost(calculate_token_cost(
+            usage.input_tokens,
+            pricing.input_cost_per_token,
+        )),
+        // Thinking tokens are billed as output
+        output: Cost(
+            calculate_token_cost(usage.output_tokens, pricing.output_cost_per_token)
+                + calculate_token_cost(
+                    usage.reasoning_output_tokens,
+                    pricing.output_cost_per_token,
+                ),
+        ),
+        cache_creation: Cost(cache_creation),
+        cache_read: Cost(calculate_token_cost(
+            usage.cache_read_input_tokens,
+            pricing.cache_read_input_token_cost,
+        )),
+        unattributed: Cost(0.0),
+    })
+}
+
+/// Spend split by token kind, to see how much goes to cache writes and reads
+///
+/// Entries with a pre-calculated `costUSD` are split in proportion to their
+/// token-priced components, so the parts always add up to the total cost.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CostComposition {
+    pub input: Cost,
+    pub output: Cost,
+    /// Cache writes, 5m and 1h combined
+    pub cache_creation: Cost,
+    pub cache_read: Cost,
+    /// Pre-calculated costs of entries without token usage to split them by
+    pub unattributed: Cost,
+}
+
+impl CostComposition {
+    /// Split the cost of a single entry
+    pub fn from_entry(entry: &UsageEntry) -> Self {
+        let components = calculate_entry_components(entry);
+        match (entry.data.cost_usd, components) {
+            (None, components) => components.unwrap_or_default(),
+            (Some(cost), Some(components)) if components.total().0 > 0.0 => {
+                components.scaled(cost / components.total().0)
+            }
+            (Some(cost), _) => CostComposition {
+                unattributed: Cost(cost),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Split the combined cost of several entries
+    pub fn from_entries<'a, I>(entries: I) -> Self
+    where
+        I: Iterator<Item = &'a UsageEntry>,
     {
-        let pricing = ModelPricing::from(model_id);
-
-        // Common cost components
-        let mut cost = calculate_token_cost(usage.input_tokens, pricing.input_cost_per_token)
-            + calculate_token_cost(usage.output_tokens, pricing.output_cost_per_token)
-            + calculate_token_cost(
-                usage.cache_read_input_tokens,
-                pricing.cache_read_input_token_cost,
-            );
-
-        // Add cache creation cost based on format
-        if let Some(cache_creation) = &usage.cache_creation {
-            // New format: calculate 5m and 1h cache separately with different prices
-            cost += calculate_token_cost(
-                cache_creation.ephemeral_5m_input_tokens,
-                pricing.cache_creation_input_token_cost,
-            );
-            cost += calculate_token_cost(
-                cache_creation.ephemeral_1h_input_tokens,
-                pricing.cache_creation_1h_token_cost,
-            );
-        } else {
-            // Old format: direct calculation
-            cost += calculate_token_cost(
-                usage.cache_creation_input_tokens,
-                pricing.cache_creation_input_token_cost,
-            );
+        let mut sums = [CostAccumulator::default(); 5];
+        for c in entries.map(Self::from_entry) {
+            for (sum, part) in sums.iter_mut().zip(c.parts()) {
+                sum.add(part);
+            }
         }
+        let [input, output, cache_creation, cache_read, unattributed] = sums.map(|s| s.total());
+        CostComposition {
+            input,
+            output,
+            cache_creation,
+            cache_read,
+            unattributed,
+        }
+    }
 
-        return cost;
+    /// Components in field order
+    fn parts(&self) -> [Cost; 5] {
+        [
+            self.input,
+            self.output,
+            self.cache_creation,
+            self.cache_read,
+            self.unattributed,
+        ]
+    }
+
+    /// Sum of all components
+    pub fn total(&self) -> Cost {
+        self.parts().into_iter().sum()
     }
 
-    0.0
+    /// Total minus cache reads: spend on new input, output and cache writes
+    pub fn excluding_cache_read(&self) -> Cost {
+        Cost(self.total().0 - self.cache_read.0)
+    }
+
+    fn scaled(&self, factor: f64) -> Self {
+        CostComposition {
+            input: Cost(self.input.0 * factor),
+            output: Cost(self.output.0 * factor),
+            cache_creation: Cost(self.cache_creation.0 * factor),
+            cache_read: Cost(self.cache_read.0 * factor),
+            unattributed: Cost(self.unattributed.0 * factor),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ModelId;
+    use crate::constants::SESSION_BLOCK_DURATION;
     use crate::types::{
         Message, MessageId, RequestId, SessionId, Usage, UsageEntryData, usage::CacheCreation,
     };
@@ -160,11 +426,14 @@ mod tests {
                         cache_read_input_tokens: cache_read_tokens,
                         cache_creation: None,
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: Some(RequestId::from("req-1")),
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         }
     }
 
@@ -195,11 +464,14 @@ mod tests {
                             ephemeral_1h_input_tokens: cache_1h_tokens,
                         }),
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: Some(RequestId::from("req-1")),
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         }
     }
 
@@ -214,6 +486,8 @@ mod tests {
                 request_id: Some(RequestId::from("req-1")),
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         }
     }
 
@@ -227,6 +501,75 @@ mod tests {
         assert_eq!(Cost::new(100.999).to_formatted_string(), "$101.00");
     }
 
+    #[test]
+    fn test_cost_formatting_clamps_huge_values() {
+        let nearest = CostRounding::Nearest;
+        assert_eq!(Cost::new(1e18).to_formatted_string(), "$>1M");
+        assert_eq!(Cost::new(f64::INFINITY).to_formatted_string(), "$>1M");
+        assert_eq!(Cost::new(f64::NAN).to_formatted_string(), "$>1M");
+        assert!(Cost::new(f64::NAN).exceeds_ceiling());
+        assert!(!Cost::new(12.5).exceeds_ceiling());
+        assert_eq!(
+            Cost::new(999_999.0).to_formatted_string_clamped(nearest, DEFAULT_COST_CEILING),
+            "$999999.00"
+        );
+        assert_eq!(
+            Cost::new(6_000.0).to_formatted_string_clamped(nearest, 5_000.0),
+            "$>5K"
+        );
+        assert_eq!(
+            Cost::new(300.0).to_formatted_string_clamped(nearest, 250.0),
+            "$>250"
+        );
+    }
+
+    #[test]
+    fn test_cost_formatting_ceil() {
+        let ceil = |value: f64| Cost::new(value).to_formatted_string_with(CostRounding::Ceil);
+        assert_eq!(ceil(1.231), "$1.24");
+        assert_eq!(ceil(1.10), "$1.10");
+        assert_eq!(ceil(0.001), "$0.01");
+        assert_eq!(ceil(0.0), "$0.00");
+        assert_eq!(ceil(-0.0), "$0.00");
+
+        // Rounding happens once on the total, not per entry
+        let total = Cost::from_entries(
+            [
+                create_test_entry_with_cost(0.001),
+                create_test_entry_with_cost(0.001),
+                create_test_entry_with_cost(0.001),
+            ]
+            .iter(),
+        );
+        assert_eq!(total.to_formatted_string_with(CostRounding::Ceil), "$0.01");
+        assert_eq!(
+            total.to_formatted_string_with(CostRounding::Nearest),
+            "$0.00"
+        );
+
+        assert_eq!("Ceil".parse::<CostRounding>(), Ok(CostRounding::Ceil));
+        assert!("floor".parse::<CostRounding>().is_err());
+    }
+
+    #[test]
+    fn test_cost_signed_colored_string() {
+        use colored::Color;
+
+        let more = Cost::new(1.2).to_signed_colored_string();
+        assert_eq!(more.input, "+$1.20");
+        assert_eq!(more.fgcolor, Some(Color::Red));
+
+        let less = Cost::new(-0.5).to_signed_colored_string();
+        assert_eq!(less.input, "-$0.50");
+        assert_eq!(less.fgcolor, Some(Color::Green));
+
+        for near_zero in [0.004, -0.004, -0.0] {
+            let delta = Cost::new(near_zero).to_signed_colored_string();
+            assert_eq!(delta.input, "$0.00");
+            assert_eq!(delta.fgcolor, None);
+        }
+    }
+
     #[test]
     fn test_cost_zero_checks() {
         assert!(!Cost::new(0.0).is_positive());
@@ -350,6 +693,118 @@ mod tests {
         assert!(cost > cost_5m_only);
     }
 
+    #[test]
+    fn test_cost_composition_components() {
+        // Old format: all cache writes priced as 5m
+        let entry = create_test_entry_old_format(
+            Some(1000),
+            Some(500),
+            Some(200),
+            Some(300),
+            "claude-3-5-sonnet-20241022",
+        );
+        let composition = CostComposition::from_entry(&entry);
+        assert!((composition.input.value() - 0.003).abs() < 1e-12);
+        assert!((composition.output.value() - 0.0075).abs() < 1e-12);
+        assert!((composition.cache_creation.value() - 0.00075).abs() < 1e-12);
+        assert!((composition.cache_read.value() - 0.00009).abs() < 1e-12);
+        assert!((composition.total().value() - calculate_entry_cost(&entry)).abs() < 1e-12);
+
+        // New format: 5m and 1h writes both land in cache_creation
+        let entry = create_test_entry_new_format(
+            None,
+            None,
+            Some(200),
+            Some(400),
+            None,
+            "claude-3-5-sonnet-20241022",
+        );
+        let composition = CostComposition::from_entry(&entry);
+        assert!((composition.cache_creation.value() - (0.00075 + 0.0024)).abs() < 1e-12);
+        assert_eq!(composition.input.value(), 0.0);
+    }
+
+    #[test]
+    fn test_cost_paths_agree_on_new_cache_format() {
+        let model = ModelId::ClaudeOpus4_20250514;
+        let entry = create_test_entry_new_format(
+            Some(1000),
+            Some(500),
+            Some(200),
+            Some(400),
+            Some(3000),
+            model.as_str(),
+        );
+
+        // Every cost path prices through ModelPricing::from
+        let pricing = ModelPricing::from(&model);
+        let expected = 1000.0 * pricing.input_cost_per_token
+            + 500.0 * pricing.output_cost_per_token
+            + 200.0 * pricing.cache_creation_input_token_cost
+            + 400.0 * pricing.cache_creation_1h_token_cost
+            + 3000.0 * pricing.cache_read_input_token_cost;
+
+        let costs = [
+            Cost::from_entry(&entry).value(),
+            Cost::from_entries(std::iter::once(&entry)).value(),
+            CostComposition::from_entry(&entry).total().value(),
+            Cost::from_entry_as_model(&entry, &model).value(),
+            Cost::from_entry_or_model(&entry, &ModelId::from("unused")).value(),
+        ];
+        for cost in costs {
+            assert!((cost - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_calculate_entry_cost_reasoning_tokens() {
+        let data: UsageEntryData = serde_json::from_str(
+            r#"{"message":{"model":"claude-sonnet-4-20250514","usage":{"input_tokens":1000,"output_tokens":500,"reasoning_output_tokens":2000}}}"#,
+        )
+        .unwrap();
+        let entry = UsageEntry::from_data(data, SessionId::from("test-session"));
+
+        // 1000 input at $3/MTok, 500 + 2000 output at $15/MTok
+        let cost = calculate_entry_cost(&entry);
+        assert!((cost - (0.003 + 0.0375)).abs() < 1e-12);
+        let composition = CostComposition::from_entry(&entry);
+        assert!((composition.output.value() - 0.0375).abs() < 1e-12);
+
+        // Entries without the field are unchanged
+        let data: UsageEntryData = serde_json::from_str(
+            r#"{"message":{"model":"claude-sonnet-4-20250514","usage":{"output_tokens":500}}}"#,
+        )
+        .unwrap();
+        let entry = UsageEntry::from_data(data, SessionId::from("test-session"));
+        assert!((calculate_entry_cost(&entry) - 0.0075).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cost_composition_precalculated() {
+        // Pre-calculated cost without usage cannot be split
+        let composition = CostComposition::from_entry(&create_test_entry_with_cost(2.0));
+        assert_eq!(composition.unattributed.value(), 2.0);
+        assert_eq!(composition.total().value(), 2.0);
+
+        // With usage, costUSD is split in proportion to the token prices
+        let mut entry = create_test_entry_old_format(
+            Some(1000),
+            Some(1000),
+            None,
+            None,
+            "claude-3-5-sonnet-20241022",
+        );
+        entry.data.cost_usd = Some(0.036);
+        let composition = CostComposition::from_entry(&entry);
+        assert!((composition.input.value() - 0.006).abs() < 1e-12);
+        assert!((composition.output.value() - 0.03).abs() < 1e-12);
+        assert!((composition.total().value() - 0.036).abs() < 1e-12);
+
+        let entries = [entry, create_test_entry_with_cost(2.0)];
+        let total = CostComposition::from_entries(entries.iter()).total();
+        assert!((total.value() - Cost::from_entries(entries.iter()).value()).abs() < 1e-12);
+    }
+
     #[test]
     fn test_calculate_entry_cost_with_missing_data() {
         // Entry with no message
@@ -362,6 +817,8 @@ mod tests {
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         };
         assert_eq!(calculate_entry_cost(&entry_no_message), 0.0);
 
@@ -379,6 +836,8 @@ mod tests {
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         };
         assert_eq!(calculate_entry_cost(&entry_no_usage), 0.0);
 
@@ -398,11 +857,14 @@ mod tests {
                         cache_read_input_tokens: None,
                         cache_creation: None,
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         };
         assert_eq!(calculate_entry_cost(&entry_no_model), 0.0);
     }
@@ -424,11 +886,14 @@ mod tests {
                         cache_read_input_tokens: None,
                         cache_creation: None,
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         };
 
         // Should handle None values as 0
@@ -448,6 +913,29 @@ mod tests {
         assert_eq!(cost.value(), 6.0);
     }
 
+    #[test]
+    fn test_cost_sum_is_compensated() {
+        // Naive f64 summation ends up about 1.3e-6 off here
+        let total: Cost = std::iter::repeat_n(Cost::new(0.1), 1_000_000).sum();
+        assert!((total.value() - 100_000.0).abs() < 1e-9);
+
+        // Many tiny costs after a large one: each naive add loses low bits
+        let mut entries = vec![create_test_entry_with_cost(100_000_000.0)];
+        entries.extend((0..100_000).map(|_| create_test_entry_with_cost(0.000_1)));
+        // Reference summed exactly in integer hundredths of a cent
+        let reference_units: u64 = 1_000_000_000_000 + 100_000;
+        let reference = reference_units as f64 / 10_000.0;
+        let cost = Cost::from_entries(entries.iter());
+        assert!((cost.value() - reference).abs() < 1e-9);
+        let reversed = Cost::from_entries(entries.iter().rev());
+        assert!((reversed.value() - reference).abs() < 1e-9);
+
+        // The breakdown accumulates the same way as the total
+        let composition = CostComposition::from_entries(entries.iter());
+        assert!((composition.unattributed.value() - reference).abs() < 1e-9);
+        assert!((composition.total().value() - reference).abs() < 1e-9);
+    }
+
     #[test]
     fn test_cost_from_entries_mixed_formats() {
         let entries = [
@@ -494,6 +982,7 @@ mod tests {
 
         let block = SessionBlock::Active {
             start_time: chrono::Utc::now(),
+            duration: SESSION_BLOCK_DURATION,
             entries,
         };
 
@@ -510,6 +999,7 @@ mod tests {
 
         let block = SessionBlock::Completed {
             start_time: chrono::Utc::now() - chrono::Duration::hours(2),
+            duration: SESSION_BLOCK_DURATION,
             entries,
         };
 
@@ -604,11 +1094,14 @@ mod tests {
                         cache_read_input_tokens: None,
                         cache_creation: None,
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         };
 
         let cost = calculate_entry_cost(&entry);
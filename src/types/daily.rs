@@ -0,0 +1,112 @@
+use super::cost::Cost;
+use super::session::{MergedUsageSnapshot, distinct_message_count, parse_entry_timestamp};
+use super::token_totals::TokenTotals;
+use super::usage::UsageEntry;
+use chrono::{Local, NaiveDate, TimeZone};
+use std::collections::BTreeMap;
+
+/// Header row matching [`DailySummary::csv_row`]
+pub const DAILY_CSV_HEADER: &str = "date,cost,input,output,cache_creation,cache_read,messages";
+
+/// Cost, tokens and message count of one calendar day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailySummary {
+    pub date: NaiveDate,
+    pub cost: Cost,
+    pub tokens: TokenTotals,
+    /// Distinct message ids
+    pub messages: usize,
+}
+
+impl DailySummary {
+    fn from_entries(date: NaiveDate, entries: &[&UsageEntry]) -> Self {
+        Self {
+            date,
+            cost: Cost::from_entries(entries.iter().copied()),
+            tokens: TokenTotals::from_entries(entries.iter().copied()),
+            messages: distinct_message_count(entries.iter().copied()),
+        }
+    }
+
+    /// One CSV line; thinking tokens are billed as output and counted there
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{:.4},{},{},{},{},{}",
+            self.date,
+            self.cost.value(),
+            self.tokens.input_tokens,
+            self.tokens.output_tokens + self.tokens.reasoning_output_tokens,
+            self.tokens.cache_creation_tokens(),
+            self.tokens.cache_read_tokens,
+            self.messages
+        )
+    }
+}
+
+impl MergedUsageSnapshot {
+    /// One summary per local calendar day with activity, oldest first
+    pub fn daily_summaries(&self) -> Vec<DailySummary> {
+        self.daily_summaries_in(&Local)
+    }
+
+    /// One summary per calendar day in the given time zone, oldest first
+    /// Days without entries are omitted rather than zero-filled, and entries
+    /// without a usable timestamp belong to no day
+    pub fn daily_summaries_in<Tz: TimeZone>(&self, tz: &Tz) -> Vec<DailySummary> {
+        let mut by_day: BTreeMap<NaiveDate, Vec<&UsageEntry>> = BTreeMap::new();
+        for entry in &self.all_entries {
+            let Some(timestamp) = parse_entry_timestamp(entry) else {
+                continue;
+            };
+            let date = timestamp.with_timezone(tz).date_naive();
+            by_day.entry(date).or_default().push(entry);
+        }
+
+        by_day
+            .into_iter()
+            .map(|(date, entries)| DailySummary::from_entries(date, &entries))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SessionId, UsageEntryData};
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    fn entry(line: &str) -> Arc<UsageEntry> {
+        let data: UsageEntryData = serde_json::from_str(line).unwrap();
+        Arc::new(UsageEntry::from_data(data, SessionId::from("s")))
+    }
+
+    #[test]
+    fn test_daily_summaries_two_days() {
+        let snapshot = MergedUsageSnapshot::new(vec![
+            entry(
+                r#"{"timestamp":"2024-01-15T10:00:00Z","costUSD":1.5,"message":{"id":"m1","usage":{"input_tokens":100,"output_tokens":20,"cache_read_input_tokens":1000}}}"#,
+            ),
+            // Second write of the same message on the same day
+            entry(
+                r#"{"timestamp":"2024-01-15T10:00:05Z","costUSD":0.5,"message":{"id":"m1","usage":{"input_tokens":10,"output_tokens":5}}}"#,
+            ),
+            entry(
+                r#"{"timestamp":"2024-01-17T23:59:00Z","costUSD":0.25,"message":{"id":"m2","usage":{"input_tokens":40,"cache_creation_input_tokens":300}}}"#,
+            ),
+            entry(r#"{"costUSD":9.0}"#),
+        ]);
+
+        let days = snapshot.daily_summaries_in(&Utc);
+        let rows: Vec<String> = days.iter().map(DailySummary::csv_row).collect();
+        // The idle day in between is omitted
+        assert_eq!(
+            rows,
+            vec![
+                "2024-01-15,2.0000,110,25,0,1000,1",
+                "2024-01-17,0.2500,40,0,300,0,1",
+            ]
+        );
+        assert_eq!(DAILY_CSV_HEADER.split(',').count(), 7);
+    }
+}
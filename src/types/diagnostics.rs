@@ -0,0 +1,33 @@
+use crate::error::CcrError;
+use std::path::PathBuf;
+
+/// Non-fatal problems collected while loading usage data
+///
+/// Loading keeps going when a single Claude root cannot be read, so these
+/// are only surfaced on request (e.g. `ccr --verbose`)
+#[derive(Debug, Default)]
+pub struct LoadDiagnostics {
+    /// Projects directories that exist but could not be read
+    pub access_errors: Vec<CcrError>,
+    /// Entries of other sessions dropped for being older than the cutoff
+    pub filtered_entries: usize,
+    /// Timestamp before which other sessions' entries were dropped
+    pub cutoff_timestamp: Option<String>,
+    /// Number of JSONL files read
+    pub files_scanned: usize,
+    /// Lines that failed to parse, per file, sorted by path
+    /// Only files with at least one such line are listed
+    pub skipped_lines: Vec<(PathBuf, usize)>,
+}
+
+impl LoadDiagnostics {
+    /// Returns true if no loading errors occurred
+    pub fn is_empty(&self) -> bool {
+        self.access_errors.is_empty()
+    }
+
+    /// Malformed lines across all files
+    pub fn skipped_line_count(&self) -> usize {
+        self.skipped_lines.iter().map(|(_, count)| count).sum()
+    }
+}
@@ -0,0 +1,101 @@
+use super::cost::Cost;
+use super::ids::{DedupBuildHasher, UniqueHash};
+use super::session::MergedUsageSnapshot;
+use super::token_totals::TokenTotals;
+use super::usage::UsageEntry;
+use std::collections::HashSet;
+
+/// Header row matching [`entry_csv_row`]
+pub const ENTRY_CSV_HEADER: &str = "timestamp,session_id,model,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,cost_usd";
+
+/// One CSV line for a raw entry
+/// Missing timestamps and models are written as empty fields; thinking
+/// tokens are billed as output and counted there
+pub fn entry_csv_row(entry: &UsageEntry) -> String {
+    let tokens = TokenTotals::from_entries(std::iter::once(entry));
+    format!(
+        "{},{},{},{},{},{},{},{:.6}",
+        csv_field(entry.data.timestamp.as_deref().unwrap_or_default()),
+        csv_field(entry.session_id.as_str()),
+        csv_field(
+            entry
+                .model()
+                .map(|model| model.as_str())
+                .unwrap_or_default()
+        ),
+        tokens.input_tokens,
+        tokens.output_tokens + tokens.reasoning_output_tokens,
+        tokens.cache_creation_tokens(),
+        tokens.cache_read_tokens,
+        Cost::from_entry(entry).value()
+    )
+}
+
+/// Quote a field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl MergedUsageSnapshot {
+    /// CSV lines for every entry, without the header
+    /// Duplicated message/request pairs are written once, as when building
+    /// session blocks, but entries without a timestamp are kept
+    pub fn entry_csv_rows(&self) -> Vec<String> {
+        let mut seen: HashSet<UniqueHash, DedupBuildHasher> = HashSet::default();
+        self.all_entries
+            .iter()
+            .filter(|entry| {
+                UniqueHash::from_usage_entry_data(&entry.data).is_none_or(|hash| seen.insert(hash))
+            })
+            .map(|entry| entry_csv_row(entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SessionId, UsageEntryData};
+    use std::sync::Arc;
+
+    fn entry(line: &str) -> Arc<UsageEntry> {
+        let data: UsageEntryData = serde_json::from_str(line).unwrap();
+        Arc::new(UsageEntry::from_data(data, SessionId::from("s")))
+    }
+
+    #[test]
+    fn test_entry_csv_rows() {
+        let first = r#"{"timestamp":"2024-01-15T10:00:00Z","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4-20250514","usage":{"input_tokens":1000,"output_tokens":100,"cache_creation_input_tokens":200,"cache_read_input_tokens":3000}}}"#;
+        let snapshot = MergedUsageSnapshot::new(vec![
+            entry(first),
+            // Same message/request pair written twice
+            entry(first),
+            entry(r#"{"costUSD":0.5,"message":{"id":"m2"}}"#),
+        ]);
+
+        let rows = snapshot.entry_csv_rows();
+        // 1000 * 3 + 100 * 15 + 200 * 3.75 + 3000 * 0.3 per MTok
+        assert_eq!(
+            rows,
+            vec![
+                "2024-01-15T10:00:00Z,s,claude-sonnet-4-20250514,1000,100,200,3000,0.006150",
+                ",s,,0,0,0,0,0.500000",
+            ]
+        );
+        assert_eq!(
+            ENTRY_CSV_HEADER.split(',').count(),
+            rows[0].split(',').count()
+        );
+    }
+
+    #[test]
+    fn test_csv_field_quotes_separators() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
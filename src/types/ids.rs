@@ -61,8 +61,9 @@ impl PartialEq for SessionId {
     fn eq(&self, other: &Self) -> bool {
         // First try pointer comparison (very fast)
         Arc::ptr_eq(&self.0, &other.0)
-            // Fall back to string comparison if pointers differ
-            || self.0.as_ref() == other.0.as_ref()
+            // Fall back to string comparison if pointers differ,
+            // rejecting ids of different length before touching the bytes
+            || (self.0.len() == other.0.len() && self.0.as_ref() == other.0.as_ref())
     }
 }
 
@@ -188,6 +189,16 @@ impl fmt::Display for UniqueHash {
     }
 }
 
+/// Hasher for the internal dedup sets keyed by UniqueHash
+/// The `fast-hash` feature swaps SipHash for FxHash; the ids come from local
+/// files, so HashDoS resistance is not needed there
+#[cfg(feature = "fast-hash")]
+pub type DedupBuildHasher = rustc_hash::FxBuildHasher;
+
+/// Hasher for the internal dedup sets keyed by UniqueHash
+#[cfg(not(feature = "fast-hash"))]
+pub type DedupBuildHasher = std::collections::hash_map::RandomState;
+
 /// Enum for Model ID with common models as variants
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ModelId {
@@ -196,6 +207,8 @@ pub enum ModelId {
     ClaudeSonnet4_20250514,
     Claude3Opus20240229,
     Claude3_5Sonnet20241022,
+    Claude3_5Haiku20241022,
+    Claude3Haiku20240307,
     Other(String),
 }
 
@@ -208,6 +221,8 @@ impl ModelId {
             "claude-sonnet-4-20250514" => ModelId::ClaudeSonnet4_20250514,
             "claude-3-opus-20240229" => ModelId::Claude3Opus20240229,
             "claude-3-5-sonnet-20241022" => ModelId::Claude3_5Sonnet20241022,
+            "claude-3-5-haiku-20241022" => ModelId::Claude3_5Haiku20241022,
+            "claude-3-haiku-20240307" => ModelId::Claude3Haiku20240307,
             other => ModelId::Other(other.to_string()),
         }
     }
@@ -238,6 +253,18 @@ impl ModelId {
         })
     }
 
+    /// Check if this is a Haiku model
+    pub fn is_haiku(&self) -> bool {
+        matches!(
+            self,
+            ModelId::Claude3_5Haiku20241022 | ModelId::Claude3Haiku20240307
+        ) || (if let ModelId::Other(s) = self {
+            s.to_lowercase().contains("haiku")
+        } else {
+            false
+        })
+    }
+
     /// Get the string representation of the model
     pub fn as_str(&self) -> &str {
         match self {
@@ -246,6 +273,8 @@ impl ModelId {
             ModelId::ClaudeSonnet4_20250514 => "claude-sonnet-4-20250514",
             ModelId::Claude3Opus20240229 => "claude-3-opus-20240229",
             ModelId::Claude3_5Sonnet20241022 => "claude-3-5-sonnet-20241022",
+            ModelId::Claude3_5Haiku20241022 => "claude-3-5-haiku-20241022",
+            ModelId::Claude3Haiku20240307 => "claude-3-haiku-20240307",
             ModelId::Other(s) => s.as_str(),
         }
     }
@@ -272,6 +301,19 @@ impl serde::Serialize for ModelId {
     }
 }
 
+// Order by canonical id string so model lists sort deterministically
+impl PartialOrd for ModelId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ModelId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
 impl fmt::Display for ModelId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -327,12 +369,51 @@ mod tests {
         assert!(!id1.ptr_eq(&id4));
         assert!(id1 != id4);
 
+        // Different length, including a prefix of the other id
+        assert!(id1 != SessionId::from("test-session-2"));
+        assert!(id1 != SessionId::from("test"));
+        assert!(SessionId::from("") == SessionId::from(""));
+
         // fast_eq should work the same as ==
         assert!(id1.fast_eq(&id2));
         assert!(id1.fast_eq(&id3));
         assert!(!id1.fast_eq(&id4));
     }
 
+    #[test]
+    fn test_model_id_ordering() {
+        let opus = ModelId::ClaudeOpus4_1_20250805;
+        let sonnet = ModelId::ClaudeSonnet4_20250514;
+        let other = ModelId::from("claude-3-haiku-20240307");
+
+        // Ordering follows the canonical id string, not the variant order
+        assert!(other < opus);
+        assert!(opus < sonnet);
+
+        let mut models = vec![sonnet.clone(), opus.clone(), other.clone()];
+        models.sort();
+        assert_eq!(models, vec![other, opus, sonnet]);
+    }
+
+    #[test]
+    fn test_haiku_model_ids_round_trip() {
+        for (id, variant) in [
+            ("claude-3-5-haiku-20241022", ModelId::Claude3_5Haiku20241022),
+            ("claude-3-haiku-20240307", ModelId::Claude3Haiku20240307),
+        ] {
+            let parsed = ModelId::from(id);
+            assert_eq!(parsed, variant);
+            assert!(parsed.is_haiku());
+            assert!(!parsed.is_opus() && !parsed.is_sonnet());
+
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(json, format!("\"{}\"", id));
+            assert_eq!(serde_json::from_str::<ModelId>(&json).unwrap(), variant);
+        }
+        assert!(ModelId::from("claude-haiku-4-5").is_haiku());
+        assert!(!ModelId::ClaudeSonnet4_20250514.is_haiku());
+    }
+
     #[test]
     fn test_unique_hash_from_usage_entry_data() {
         use crate::types::{Message, UsageEntryData};
@@ -1,11 +1,46 @@
 use super::ids::{ModelId, SessionId};
+use super::usage::CacheCreation;
+use crate::config;
 use serde::Deserialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which directory the statusline names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DirSource {
+    /// Basename of the working directory
+    #[default]
+    Cwd,
+    /// Basename of the workspace project directory, falling back to the cwd
+    Project,
+}
+
+impl DirSource {
+    /// Read the source from CLAUDE_CCR_DIR_SOURCE (cwd|project)
+    pub fn from_env() -> Self {
+        config::env_parse("CLAUDE_CCR_DIR_SOURCE").unwrap_or_default()
+    }
+}
+
+impl FromStr for DirSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cwd" => Ok(DirSource::Cwd),
+            "project" => Ok(DirSource::Project),
+            other => Err(format!("unknown directory source '{}'", other)),
+        }
+    }
+}
 
 // Input structure
 #[derive(Debug, Deserialize)]
 pub struct StatuslineHookJson {
     pub session_id: SessionId,
     pub cwd: String,
+    /// May be omitted by hosts that pass `transcript_usage` inline
+    #[serde(default)]
     pub transcript_path: String,
     pub model: Model,
     #[serde(default)]
@@ -18,11 +53,40 @@ pub struct StatuslineHookJson {
     pub cost: Option<SessionCost>,
     #[serde(default)]
     pub context_window: Option<ContextWindow>,
+    /// Latest assistant usage provided inline, saving the transcript read
+    #[serde(default)]
+    pub transcript_usage: Option<TranscriptUsage>,
+}
+
+impl StatuslineHookJson {
+    /// Directory name to display for the given source
+    /// `Project` falls back to the cwd when the hook sends no workspace
+    pub fn dir_name(&self, source: DirSource) -> &str {
+        let dir = match (source, &self.workspace) {
+            (DirSource::Project, Some(workspace)) => workspace.project_dir.as_str(),
+            _ => self.cwd.as_str(),
+        };
+        Path::new(dir)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(dir)
+    }
+
+    /// Replace the hook's session id when an override is given
+    /// (`CLAUDE_CCR_SESSION_ID`), e.g. to replay a saved payload against
+    /// a different session on disk
+    /// Returns true if the session id was replaced
+    pub fn apply_session_override(&mut self, session_id: Option<String>) -> bool {
+        let Some(session_id) = session_id else {
+            return false;
+        };
+        self.session_id = SessionId::from(session_id.trim());
+        true
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Model {
-    #[allow(dead_code)]
     pub id: Option<ModelId>,
     pub display_name: String,
 }
@@ -47,6 +111,27 @@ pub struct SessionCost {
     pub total_lines_removed: u64,
 }
 
+impl SessionCost {
+    /// Fraction of the session's wall-clock time spent waiting on API calls
+    /// (`total_api_duration_ms / total_duration_ms`)
+    /// Returns None when no session duration has been recorded yet
+    pub fn api_efficiency(&self) -> Option<f64> {
+        if self.total_duration_ms == 0 {
+            return None;
+        }
+
+        Some(self.total_api_duration_ms as f64 / self.total_duration_ms as f64)
+    }
+
+    /// Lines added minus lines removed in this session
+    /// Negative when the session deleted more than it wrote
+    /// Lines changed only come from the hook payload, so there is no
+    /// aggregate across sessions
+    pub fn net_lines(&self) -> i64 {
+        self.total_lines_added as i64 - self.total_lines_removed as i64
+    }
+}
+
 /// Context window information from Claude Code API
 #[derive(Debug, Deserialize)]
 pub struct ContextWindow {
@@ -79,6 +164,9 @@ pub struct CurrentUsage {
 pub struct TranscriptMessage {
     #[serde(rename = "type")]
     pub message_type: String,
+    /// True for subagent (sidechain) messages, absent in older transcripts
+    #[serde(default, rename = "isSidechain")]
+    pub is_sidechain: Option<bool>,
     #[serde(default)]
     pub message: Option<TranscriptMessageContent>,
 }
@@ -89,7 +177,7 @@ pub struct TranscriptMessageContent {
     pub usage: Option<TranscriptUsage>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct TranscriptUsage {
     #[serde(default)]
     pub input_tokens: Option<u64>,
@@ -98,10 +186,27 @@ pub struct TranscriptUsage {
     pub output_tokens: Option<u64>,
     #[serde(default)]
     pub cache_creation_input_tokens: Option<u64>,
+    /// 5m/1h split written by newer Claude Code versions
+    #[serde(default)]
+    pub cache_creation: Option<CacheCreation>,
     #[serde(default)]
     pub cache_read_input_tokens: Option<u64>,
 }
 
+impl TranscriptUsage {
+    /// Cache writes of both durations
+    /// The split wins over the flat field when present, as in cost calculation
+    pub fn cache_creation_tokens(&self) -> u64 {
+        match &self.cache_creation {
+            Some(split) => {
+                split.ephemeral_5m_input_tokens.unwrap_or(0) as u64
+                    + split.ephemeral_1h_input_tokens.unwrap_or(0) as u64
+            }
+            None => self.cache_creation_input_tokens.unwrap_or(0),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +272,81 @@ mod tests {
         assert!(ctx.used_percentage.is_none());
     }
 
+    #[test]
+    fn test_session_override_wins_over_hook() {
+        let json = r#"{
+            "session_id": "from-hook",
+            "cwd": "/tmp",
+            "transcript_path": "/dev/null",
+            "model": {"display_name": "Opus 4.1"}
+        }"#;
+        let mut hook: StatuslineHookJson = serde_json::from_str(json).unwrap();
+
+        assert!(!hook.apply_session_override(None));
+        assert_eq!(hook.session_id.as_str(), "from-hook");
+
+        assert!(hook.apply_session_override(Some("from-env".to_string())));
+        assert_eq!(hook.session_id.as_str(), "from-env");
+    }
+
+    #[test]
+    fn test_dir_name_source() {
+        let json = r#"{
+            "session_id": "s",
+            "cwd": "/work/ccr/src/types",
+            "transcript_path": "/dev/null",
+            "model": {"display_name": "Opus 4.1"},
+            "workspace": {"current_dir": "/work/ccr/src/types", "project_dir": "/work/ccr"}
+        }"#;
+        let mut hook: StatuslineHookJson = serde_json::from_str(json).unwrap();
+        assert_eq!(hook.dir_name(DirSource::Cwd), "types");
+        assert_eq!(hook.dir_name(DirSource::Project), "ccr");
+
+        hook.workspace = None;
+        assert_eq!(hook.dir_name(DirSource::Project), "types");
+
+        assert_eq!("Project".parse::<DirSource>(), Ok(DirSource::Project));
+        assert!("home".parse::<DirSource>().is_err());
+    }
+
+    #[test]
+    fn test_session_cost_api_efficiency() {
+        let cost = SessionCost {
+            total_cost_usd: 5.14,
+            total_duration_ms: 1_000_000,
+            total_api_duration_ms: 250_000,
+            total_lines_added: 23,
+            total_lines_removed: 17,
+        };
+        assert_eq!(cost.api_efficiency(), Some(0.25));
+
+        // A fresh session has no duration yet
+        let empty = SessionCost {
+            total_cost_usd: 0.0,
+            total_duration_ms: 0,
+            total_api_duration_ms: 0,
+            total_lines_added: 0,
+            total_lines_removed: 0,
+        };
+        assert_eq!(empty.api_efficiency(), None);
+    }
+
+    #[test]
+    fn test_session_cost_net_lines() {
+        let mut cost = SessionCost {
+            total_cost_usd: 1.0,
+            total_duration_ms: 0,
+            total_api_duration_ms: 0,
+            total_lines_added: 23,
+            total_lines_removed: 17,
+        };
+        assert_eq!(cost.net_lines(), 6);
+
+        cost.total_lines_added = 5;
+        cost.total_lines_removed = 40;
+        assert_eq!(cost.net_lines(), -35);
+    }
+
     #[test]
     fn test_statusline_hook_with_context_window() {
         let json = r#"{
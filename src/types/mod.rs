@@ -1,22 +1,46 @@
+pub mod block_summary;
+pub mod budget;
 pub mod burn_rate;
 pub mod context_tokens;
 pub mod cost;
+pub mod daily;
+pub mod diagnostics;
+pub mod export;
 pub mod ids;
 pub mod input;
+pub mod model_family;
+pub mod palette;
 pub mod pricing;
 pub mod remaining_time;
+pub mod report;
 pub mod session;
+pub mod token_totals;
 pub mod usage;
 
-pub use burn_rate::BurnRate;
-pub use context_tokens::ContextTokens;
-pub use cost::Cost;
-pub use ids::{MessageId, RequestId, SessionId, UniqueHash};
+pub use block_summary::{BlockKind, BlockSummary};
+pub use budget::{Budget, budget_alert_marker_path, claim_daily_alert};
+pub use burn_rate::{BurnBasis, BurnCost, BurnRate, BurnThresholds, TokenBurnRate};
+pub use context_tokens::{ContextThresholds, ContextTokens, ContextUsage, context_window_for};
+pub use cost::{Cost, CostComposition, CostRounding};
+pub use daily::{DAILY_CSV_HEADER, DailySummary};
+pub use diagnostics::LoadDiagnostics;
+pub use export::{ENTRY_CSV_HEADER, entry_csv_row};
+pub use ids::{DedupBuildHasher, MessageId, RequestId, SessionId, UniqueHash};
 pub use input::{
-    ContextWindow, CurrentUsage, Model, StatuslineHookJson, TranscriptMessage,
+    ContextWindow, CurrentUsage, DirSource, Model, StatuslineHookJson, TranscriptMessage,
     TranscriptMessageContent, TranscriptUsage,
 };
-pub use pricing::ModelPricing;
-pub use remaining_time::RemainingTime;
-pub use session::{MergedUsageSnapshot, SessionBlock};
+pub use model_family::{ModelFamily, short_model_name};
+pub use palette::{Palette, Severity};
+pub use pricing::{
+    ModelPricing, PricingOverride, install_pricing_table, parse_pricing_overrides,
+    pricing_override_path,
+};
+pub use remaining_time::{RemainingTime, TimeStyle};
+pub use report::{ModelCost, PeriodCost, ReportPeriod, format_report_table};
+pub use session::{
+    MergedUsageSnapshot, SessionBlock, SessionScope, identify_session_blocks,
+    identify_session_blocks_parallel, identify_session_blocks_with, project_key_from_path,
+};
+pub use token_totals::TokenTotals;
 pub use usage::{Message, Usage, UsageEntry, UsageEntryData};
@@ -0,0 +1,114 @@
+use super::ids::ModelId;
+use std::fmt;
+
+/// Model family classified from a model id or display name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelFamily {
+    Opus,
+    Sonnet,
+    Haiku,
+    Other,
+}
+
+impl ModelFamily {
+    /// Classify a model id ("claude-opus-4-1-20250805") or display name ("Claude Opus 4.1")
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        if name.contains("opus") {
+            ModelFamily::Opus
+        } else if name.contains("sonnet") {
+            ModelFamily::Sonnet
+        } else if name.contains("haiku") {
+            ModelFamily::Haiku
+        } else {
+            ModelFamily::Other
+        }
+    }
+
+    /// Get the family name as shown to users
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelFamily::Opus => "Opus",
+            ModelFamily::Sonnet => "Sonnet",
+            ModelFamily::Haiku => "Haiku",
+            ModelFamily::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for ModelFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ModelId {
+    /// Get the model family of this id
+    pub fn family(&self) -> ModelFamily {
+        ModelFamily::from_name(self.as_str())
+    }
+}
+
+/// Abbreviate a model name to family and version, e.g. "Claude Opus 4.1" -> "Opus 4.1"
+/// Names of unknown families are returned unchanged
+pub fn short_model_name(name: &str) -> String {
+    let family = ModelFamily::from_name(name);
+    if family == ModelFamily::Other {
+        return name.to_string();
+    }
+
+    match model_version(name) {
+        Some(version) => format!("{} {}", family, version),
+        None => family.to_string(),
+    }
+}
+
+/// Extract the version from a display name ("3.5") or model id ("3-5", "4-1")
+/// Date suffixes such as "20241022" are ignored
+fn model_version(name: &str) -> Option<String> {
+    let parts: Vec<&str> = name
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '.')
+        .filter(|part| {
+            !part.is_empty()
+                && part.len() <= 4
+                && part.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && part.starts_with(|c: char| c.is_ascii_digit())
+        })
+        .collect();
+
+    match parts.as_slice() {
+        [] => None,
+        // Display names carry the dotted version as one token
+        [dotted, ..] if dotted.contains('.') => Some(dotted.to_string()),
+        // Model ids split major and minor with a dash
+        [major, minor, ..] if minor.len() == 1 => Some(format!("{}.{}", major, minor)),
+        [major, ..] => Some(major.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_family() {
+        assert_eq!(ModelFamily::from_name("Claude Opus 4.1"), ModelFamily::Opus);
+        assert_eq!(
+            ModelId::from("claude-3-5-haiku-20241022").family(),
+            ModelFamily::Haiku
+        );
+        assert_eq!(ModelFamily::from_name("gpt-4o"), ModelFamily::Other);
+    }
+
+    #[test]
+    fn test_short_model_name() {
+        assert_eq!(short_model_name("Claude Opus 4.1"), "Opus 4.1");
+        assert_eq!(short_model_name("Sonnet 4"), "Sonnet 4");
+        assert_eq!(short_model_name("Claude 3.5 Haiku"), "Haiku 3.5");
+        assert_eq!(short_model_name("claude-3-5-haiku-20241022"), "Haiku 3.5");
+        assert_eq!(short_model_name("claude-opus-4-1-20250805"), "Opus 4.1");
+        assert_eq!(short_model_name("claude-sonnet-4-20250514"), "Sonnet 4");
+        assert_eq!(short_model_name("Claude Opus"), "Opus");
+        assert_eq!(short_model_name("Custom Model"), "Custom Model");
+    }
+}
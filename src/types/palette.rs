@@ -0,0 +1,88 @@
+use crate::config;
+use colored::{Color, ColoredString, Colorize};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// How urgent a colored value is, from fine to needing attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Colors used for the normal/warning/critical ramps
+///
+/// Shared by the burn rate and context segments so both stay consistent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// Green, yellow, red
+    #[default]
+    Default,
+    /// Blue, yellow, magenta, distinguishable with red-green color blindness
+    Deuteranopia,
+}
+
+impl Palette {
+    /// Read the palette from CLAUDE_CCR_PALETTE (default|deuteranopia), once per process
+    pub fn from_env() -> Self {
+        static PALETTE: OnceLock<Palette> = OnceLock::new();
+        *PALETTE.get_or_init(|| config::env_parse("CLAUDE_CCR_PALETTE").unwrap_or_default())
+    }
+
+    /// Color for a severity
+    pub fn color(&self, severity: Severity) -> Color {
+        match (self, severity) {
+            (Palette::Default, Severity::Normal) => Color::Green,
+            (Palette::Default, Severity::Warning) => Color::Yellow,
+            (Palette::Default, Severity::Critical) => Color::Red,
+            (Palette::Deuteranopia, Severity::Normal) => Color::Blue,
+            (Palette::Deuteranopia, Severity::Warning) => Color::Yellow,
+            (Palette::Deuteranopia, Severity::Critical) => Color::Magenta,
+        }
+    }
+
+    /// Color text for a severity
+    pub fn paint(&self, text: String, severity: Severity) -> ColoredString {
+        text.color(self.color(severity))
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(Palette::Default),
+            "deuteranopia" => Ok(Palette::Deuteranopia),
+            other => Err(format!("unknown palette '{}'", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_from_str() {
+        assert_eq!("default".parse::<Palette>(), Ok(Palette::Default));
+        assert_eq!("Deuteranopia".parse::<Palette>(), Ok(Palette::Deuteranopia));
+        assert!("sepia".parse::<Palette>().is_err());
+        assert_eq!(Palette::default(), Palette::Default);
+    }
+
+    #[test]
+    fn test_palette_avoids_red_and_green() {
+        for severity in [Severity::Normal, Severity::Warning, Severity::Critical] {
+            let color = Palette::Deuteranopia.color(severity);
+            assert!(!matches!(color, Color::Red | Color::Green));
+        }
+        assert_eq!(
+            Palette::Default
+                .paint("x".to_string(), Severity::Critical)
+                .fgcolor,
+            Some(Color::Red)
+        );
+    }
+}
@@ -1,6 +1,123 @@
 use super::ids::ModelId;
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, PartialEq)]
+/// Pricing table installed at startup, consulted before the built-in prices
+static PRICING_TABLE: OnceLock<HashMap<String, ModelPricing>> = OnceLock::new();
+
+/// Install a pricing table keyed by model id for the rest of the process
+/// Models missing from it keep their built-in prices
+/// Returns false if a table was already installed
+pub fn install_pricing_table(table: HashMap<String, ModelPricing>) -> bool {
+    PRICING_TABLE.set(table).is_ok()
+}
+
+/// Per-model prices from the user's override file
+/// Fields left out keep the price the model would otherwise have
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PricingOverride {
+    pub input_cost_per_token: Option<f64>,
+    pub output_cost_per_token: Option<f64>,
+    pub cache_creation_input_token_cost: Option<f64>,
+    pub cache_read_input_token_cost: Option<f64>,
+    pub cache_creation_1h_token_cost: Option<f64>,
+}
+
+impl PricingOverride {
+    /// Replace the fields set in the override
+    pub fn apply(&self, base: ModelPricing) -> ModelPricing {
+        ModelPricing {
+            input_cost_per_token: self
+                .input_cost_per_token
+                .unwrap_or(base.input_cost_per_token),
+            output_cost_per_token: self
+                .output_cost_per_token
+                .unwrap_or(base.output_cost_per_token),
+            cache_creation_input_token_cost: self
+                .cache_creation_input_token_cost
+                .unwrap_or(base.cache_creation_input_token_cost),
+            cache_read_input_token_cost: self
+                .cache_read_input_token_cost
+                .unwrap_or(base.cache_read_input_token_cost),
+            cache_creation_1h_token_cost: self
+                .cache_creation_1h_token_cost
+                .unwrap_or(base.cache_creation_1h_token_cost),
+        }
+    }
+}
+
+/// Parse a pricing override file: one table per model id, e.g.
+///
+/// ```toml
+/// ["claude-sonnet-4-20250514"]
+/// input_cost_per_token = 0.0000025
+/// ```
+pub fn parse_pricing_overrides(
+    toml: &str,
+) -> Result<HashMap<String, PricingOverride>, toml::de::Error> {
+    toml::from_str(toml)
+}
+
+/// Override file path: CCR_PRICING_FILE, else `~/.config/ccr/pricing.toml`
+pub fn pricing_override_path() -> Option<PathBuf> {
+    config::env_var("CCR_PRICING_FILE")
+        .map(PathBuf::from)
+        .or_else(|| {
+            let home = std::env::var_os("HOME")?;
+            Some(PathBuf::from(home).join(".config/ccr/pricing.toml"))
+        })
+}
+
+/// Read and parse an override file; a missing file means no overrides
+/// The error names the file and the parse problem, e.g. an unknown field
+pub fn read_pricing_overrides(path: &Path) -> Result<HashMap<String, PricingOverride>, String> {
+    let toml = match fs::read_to_string(path) {
+        Ok(toml) => toml,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(format!("cannot read {}: {}", path.display(), e)),
+    };
+    parse_pricing_overrides(&toml).map_err(|e| {
+        format!(
+            "invalid pricing overrides in {}: {}",
+            path.display(),
+            e.message()
+        )
+    })
+}
+
+/// The user's overrides, read and parsed once per process
+/// A broken file is reported on stderr and ignored, so the built-in prices apply
+fn pricing_overrides() -> &'static HashMap<String, PricingOverride> {
+    static OVERRIDES: OnceLock<HashMap<String, PricingOverride>> = OnceLock::new();
+    OVERRIDES.get_or_init(|| {
+        let Some(path) = pricing_override_path() else {
+            return HashMap::new();
+        };
+        read_pricing_overrides(&path).unwrap_or_else(|message| {
+            eprintln!("ccr: {}", message);
+            HashMap::new()
+        })
+    })
+}
+
+/// One model in LiteLLM's `model_prices_and_context_window.json`
+/// Only the fields ccr uses are read
+#[derive(Debug, Deserialize)]
+struct LiteLlmModel {
+    input_cost_per_token: Option<f64>,
+    output_cost_per_token: Option<f64>,
+    cache_creation_input_token_cost: Option<f64>,
+    cache_read_input_token_cost: Option<f64>,
+    cache_creation_input_token_cost_above_1hr: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModelPricing {
     pub input_cost_per_token: f64,
     pub output_cost_per_token: f64,
@@ -9,8 +126,47 @@ pub struct ModelPricing {
     pub cache_creation_1h_token_cost: f64,    // 1h cache write
 }
 
-impl From<&ModelId> for ModelPricing {
-    fn from(model_id: &ModelId) -> Self {
+impl ModelPricing {
+    /// Parse LiteLLM's pricing JSON into a table keyed by model id
+    /// Entries without input and output prices (e.g. the `sample_spec` entry or
+    /// image models) are skipped; a missing 1h cache write price is derived
+    /// from the input price as Anthropic does (2x)
+    pub fn parse_litellm(json: &str) -> serde_json::Result<HashMap<String, ModelPricing>> {
+        let raw: HashMap<String, serde_json::Value> = serde_json::from_str(json)?;
+        Ok(raw
+            .into_iter()
+            .filter_map(|(model, value)| {
+                let entry: LiteLlmModel = serde_json::from_value(value).ok()?;
+                let input = entry.input_cost_per_token?;
+                let pricing = ModelPricing {
+                    input_cost_per_token: input,
+                    output_cost_per_token: entry.output_cost_per_token?,
+                    cache_creation_input_token_cost: entry
+                        .cache_creation_input_token_cost
+                        .unwrap_or(0.0),
+                    cache_read_input_token_cost: entry.cache_read_input_token_cost.unwrap_or(0.0),
+                    cache_creation_1h_token_cost: entry
+                        .cache_creation_input_token_cost_above_1hr
+                        .unwrap_or(input * 2.0),
+                };
+                Some((model, pricing))
+            })
+            .collect())
+    }
+
+    /// Price from the installed table, trying the bare id and then LiteLLM's
+    /// provider-prefixed `anthropic/` key
+    fn from_table(model_id: &ModelId) -> Option<Self> {
+        let table = PRICING_TABLE.get()?;
+        let id = model_id.as_str();
+        table
+            .get(id)
+            .or_else(|| table.get(&format!("anthropic/{}", id)))
+            .cloned()
+    }
+
+    /// Built-in prices for known models and model families
+    fn builtin(model_id: &ModelId) -> Self {
         match model_id {
             ModelId::ClaudeOpus4_1_20250805
             | ModelId::ClaudeOpus4_20250514
@@ -28,6 +184,20 @@ impl From<&ModelId> for ModelPricing {
                 cache_read_input_token_cost: 0.0000003,      // $0.30/MTok
                 cache_creation_1h_token_cost: 0.000006,      // $6/MTok (1h cache)
             },
+            ModelId::Claude3_5Haiku20241022 => ModelPricing {
+                input_cost_per_token: 0.0000008,           // $0.80/MTok
+                output_cost_per_token: 0.000004,           // $4/MTok
+                cache_creation_input_token_cost: 0.000001, // $1/MTok (5m cache)
+                cache_read_input_token_cost: 0.00000008,   // $0.08/MTok
+                cache_creation_1h_token_cost: 0.0000016,   // $1.6/MTok (1h cache)
+            },
+            ModelId::Claude3Haiku20240307 => ModelPricing {
+                input_cost_per_token: 0.00000025,           // $0.25/MTok
+                output_cost_per_token: 0.00000125,          // $1.25/MTok
+                cache_creation_input_token_cost: 0.0000003, // $0.30/MTok (5m cache)
+                cache_read_input_token_cost: 0.00000003,    // $0.03/MTok
+                cache_creation_1h_token_cost: 0.0000005,    // $0.50/MTok (1h cache)
+            },
             ModelId::Other(s) => {
                 // Fallback based on model name
                 if s.to_lowercase().contains("opus") {
@@ -47,7 +217,7 @@ impl From<&ModelId> for ModelPricing {
                         cache_creation_1h_token_cost: 0.000006,
                     }
                 } else if s.to_lowercase().contains("haiku") {
-                    // Haiku 3.5 pricing
+                    // Unlisted Haiku models get Haiku 3.5 pricing
                     ModelPricing {
                         input_cost_per_token: 0.0000008,           // $0.80/MTok
                         output_cost_per_token: 0.000004,           // $4/MTok
@@ -70,6 +240,31 @@ impl From<&ModelId> for ModelPricing {
     }
 }
 
+impl ModelPricing {
+    /// Price for a model before the user's overrides: the installed (remote)
+    /// table, then the built-in prices
+    fn without_overrides(model_id: &ModelId) -> Self {
+        Self::from_table(model_id).unwrap_or_else(|| Self::builtin(model_id))
+    }
+
+    /// Apply an override, if any, on top of the usual price
+    fn resolve(model_id: &ModelId, overrides: &HashMap<String, PricingOverride>) -> Self {
+        let base = Self::without_overrides(model_id);
+        match overrides.get(model_id.as_str()) {
+            Some(override_) => override_.apply(base),
+            None => base,
+        }
+    }
+}
+
+/// Precedence, highest first: the pricing override file, the installed
+/// (remote) table, the built-in prices
+impl From<&ModelId> for ModelPricing {
+    fn from(model_id: &ModelId) -> Self {
+        Self::resolve(model_id, pricing_overrides())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +295,95 @@ mod tests {
 
         assert!((cost_zero - 0.0525).abs() < 1e-10);
     }
+
+    #[test]
+    fn test_parse_litellm() {
+        let json = r#"{
+            "sample_spec": {"max_tokens": "set to max_output_tokens if provider specifies it"},
+            "claude-haiku-4-5": {
+                "input_cost_per_token": 1e-06,
+                "output_cost_per_token": 5e-06,
+                "cache_creation_input_token_cost": 1.25e-06,
+                "cache_creation_input_token_cost_above_1hr": 2e-06,
+                "cache_read_input_token_cost": 1e-07,
+                "litellm_provider": "anthropic",
+                "mode": "chat"
+            },
+            "anthropic/claude-3-haiku-20240307": {
+                "input_cost_per_token": 2.5e-07,
+                "output_cost_per_token": 1.25e-06
+            },
+            "dall-e-3": {"output_cost_per_pixel": 0.0}
+        }"#;
+        let table = ModelPricing::parse_litellm(json).unwrap();
+
+        assert_eq!(table.len(), 2);
+        let haiku = &table["claude-haiku-4-5"];
+        assert_eq!(haiku.input_cost_per_token, 1e-06);
+        assert_eq!(haiku.cache_creation_1h_token_cost, 2e-06);
+        // Missing cache prices default to zero, the 1h write to twice the input
+        let old = &table["anthropic/claude-3-haiku-20240307"];
+        assert_eq!(old.cache_read_input_token_cost, 0.0);
+        assert_eq!(old.cache_creation_1h_token_cost, 5e-07);
+
+        assert!(ModelPricing::parse_litellm("[]").is_err());
+    }
+
+    #[test]
+    fn test_pricing_override_inherits_missing_fields() {
+        let overrides = parse_pricing_overrides(
+            r#"
+            ["claude-sonnet-4-20250514"]
+            input_cost_per_token = 0.0000025
+            output_cost_per_token = 0.00001
+            "#,
+        )
+        .unwrap();
+
+        let sonnet = ModelId::ClaudeSonnet4_20250514;
+        let builtin = ModelPricing::builtin(&sonnet);
+        let priced = ModelPricing::resolve(&sonnet, &overrides);
+        assert_eq!(priced.input_cost_per_token, 0.0000025);
+        assert_eq!(priced.output_cost_per_token, 0.00001);
+        assert_eq!(
+            priced.cache_read_input_token_cost,
+            builtin.cache_read_input_token_cost
+        );
+        assert_eq!(
+            priced.cache_creation_1h_token_cost,
+            builtin.cache_creation_1h_token_cost
+        );
+
+        // Other models are untouched
+        let opus = ModelId::ClaudeOpus4_20250514;
+        assert_eq!(
+            ModelPricing::resolve(&opus, &overrides),
+            ModelPricing::builtin(&opus)
+        );
+
+        // Misspelled fields are rejected rather than silently ignored
+        assert!(parse_pricing_overrides("[m]\ninput_cost = 1.0").is_err());
+    }
+
+    #[test]
+    fn test_read_pricing_overrides_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pricing.toml");
+        assert_eq!(read_pricing_overrides(&path), Ok(HashMap::new()));
+
+        fs::write(&path, "[m]\ninput_cost = 1.0").unwrap();
+        let message = read_pricing_overrides(&path).unwrap_err();
+        assert!(message.contains(&path.display().to_string()), "{}", message);
+        assert!(message.contains("input_cost"), "{}", message);
+    }
+
+    #[test]
+    fn test_haiku_pricing() {
+        let haiku_3_5 = ModelPricing::builtin(&ModelId::Claude3_5Haiku20241022);
+        let haiku_3 = ModelPricing::builtin(&ModelId::Claude3Haiku20240307);
+        assert_eq!(haiku_3_5.input_cost_per_token, 0.0000008);
+        assert_eq!(haiku_3.input_cost_per_token, 0.00000025);
+        assert_eq!(haiku_3.cache_read_input_token_cost, 0.00000003);
+        assert!(haiku_3.output_cost_per_token < haiku_3_5.output_cost_per_token);
+    }
 }
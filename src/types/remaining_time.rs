@@ -1,7 +1,38 @@
+use crate::config;
 use crate::types::SessionBlock;
-use chrono::{Local, Utc};
+use chrono::{DateTime, Local, Utc};
 use colored::{ColoredString, Colorize};
 use std::fmt;
+use std::str::FromStr;
+
+/// How the active block's time is shown in the statusline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeStyle {
+    /// Time left until the block ends, e.g. "1h 18m left"
+    #[default]
+    Remaining,
+    /// The block's local clock window, e.g. "14:00–19:00"
+    Window,
+}
+
+impl TimeStyle {
+    /// Read the style from CLAUDE_CCR_TIME_STYLE (remaining|window)
+    pub fn from_env() -> Self {
+        config::env_parse("CLAUDE_CCR_TIME_STYLE").unwrap_or_default()
+    }
+}
+
+impl FromStr for TimeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "remaining" => Ok(TimeStyle::Remaining),
+            "window" => Ok(TimeStyle::Window),
+            other => Err(format!("unknown time style '{}'", other)),
+        }
+    }
+}
 
 /// Represents the remaining time until a session block expires
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -15,13 +46,20 @@ impl RemainingTime {
 
     /// Calculate remaining time from a SessionBlock
     pub fn from_session_block(block: &SessionBlock) -> Self {
-        let remaining_minutes = block
-            .end_time()
-            .signed_duration_since(Local::now().with_timezone(&Utc))
-            .num_minutes();
+        Self::from_session_block_at(block, Local::now().with_timezone(&Utc))
+    }
+
+    /// Calculate remaining time from a SessionBlock at a given time
+    pub fn from_session_block_at(block: &SessionBlock, now: DateTime<Utc>) -> Self {
+        let remaining_minutes = block.end_time().signed_duration_since(now).num_minutes();
         RemainingTime(remaining_minutes)
     }
 
+    /// Get the remaining minutes
+    pub fn minutes(&self) -> i64 {
+        self.0
+    }
+
     /// Check if there's time remaining
     pub fn has_remaining(&self) -> bool {
         self.0 > 0
@@ -73,6 +111,15 @@ mod tests {
         assert_eq!(RemainingTime::new(135).to_formatted_string(), "2h 15m left");
     }
 
+    #[test]
+    fn test_remaining_time_hour_boundaries() {
+        assert_eq!(RemainingTime::new(59).to_formatted_string(), "59m left");
+        assert_eq!(RemainingTime::new(60).to_formatted_string(), "1h left");
+        assert_eq!(RemainingTime::new(119).to_formatted_string(), "1h 59m left");
+        assert_eq!(RemainingTime::new(120).to_formatted_string(), "2h left");
+        assert_eq!(RemainingTime::new(121).to_formatted_string(), "2h 1m left");
+    }
+
     #[test]
     fn test_remaining_time_has_remaining() {
         assert!(RemainingTime::new(10).has_remaining());
@@ -86,4 +133,12 @@ mod tests {
         let time = RemainingTime::new(75);
         assert_eq!(format!("{}", time), "1h 15m left");
     }
+
+    #[test]
+    fn test_time_style_from_str() {
+        assert_eq!("remaining".parse(), Ok(TimeStyle::Remaining));
+        assert_eq!("Window".parse(), Ok(TimeStyle::Window));
+        assert!("clock".parse::<TimeStyle>().is_err());
+        assert_eq!(TimeStyle::default(), TimeStyle::Remaining);
+    }
 }
@@ -0,0 +1,210 @@
+use super::cost::Cost;
+use super::ids::ModelId;
+use super::session::{MergedUsageSnapshot, cost_by_model, parse_entry_timestamp};
+use super::usage::UsageEntry;
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Calendar period that a cost report groups by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportPeriod {
+    #[default]
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    /// First day of the period containing the date
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            ReportPeriod::Day => date,
+            ReportPeriod::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday().into())
+            }
+            ReportPeriod::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Label of the period starting on the date, e.g. 2024-01-15, 2024-W03, 2024-01
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            ReportPeriod::Day => start.format("%Y-%m-%d").to_string(),
+            ReportPeriod::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            ReportPeriod::Month => start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+impl FromStr for ReportPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "day" => Ok(ReportPeriod::Day),
+            "week" => Ok(ReportPeriod::Week),
+            "month" => Ok(ReportPeriod::Month),
+            other => Err(format!("unknown report period '{}'", other)),
+        }
+    }
+}
+
+/// Cost of one model within a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelCost {
+    pub model: ModelId,
+    pub cost: Cost,
+}
+
+/// Cost of one calendar period with its per-model breakdown
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodCost {
+    /// Period label, see [`ReportPeriod::label`]
+    pub period: String,
+    pub cost: Cost,
+    /// Sorted by cost descending
+    pub models: Vec<ModelCost>,
+}
+
+impl PeriodCost {
+    fn from_entries(label: String, entries: &[&UsageEntry]) -> Self {
+        Self {
+            period: label,
+            cost: Cost::from_entries(entries.iter().copied()),
+            models: cost_by_model(entries.iter().copied())
+                .into_iter()
+                .map(|(model, cost)| ModelCost { model, cost })
+                .collect(),
+        }
+    }
+}
+
+impl MergedUsageSnapshot {
+    /// One cost row per local calendar period with activity, oldest first
+    pub fn period_costs(&self, period: ReportPeriod) -> Vec<PeriodCost> {
+        self.period_costs_in(period, &Local)
+    }
+
+    /// One cost row per calendar period in the given time zone, oldest first
+    /// Periods without entries are omitted, like the daily summaries
+    pub fn period_costs_in<Tz: TimeZone>(&self, period: ReportPeriod, tz: &Tz) -> Vec<PeriodCost> {
+        let mut by_period: BTreeMap<NaiveDate, Vec<&UsageEntry>> = BTreeMap::new();
+        for entry in &self.all_entries {
+            let Some(timestamp) = parse_entry_timestamp(entry) else {
+                continue;
+            };
+            let start = period.start_of(timestamp.with_timezone(tz).date_naive());
+            by_period.entry(start).or_default().push(entry);
+        }
+
+        by_period
+            .into_iter()
+            .map(|(start, entries)| PeriodCost::from_entries(period.label(start), &entries))
+            .collect()
+    }
+}
+
+/// Render period costs as plain aligned columns, models indented under
+/// their period
+pub fn format_report_table(rows: &[PeriodCost]) -> String {
+    let mut lines: Vec<(String, String)> = vec![("PERIOD".to_string(), "COST".to_string())];
+    for row in rows {
+        lines.push((row.period.clone(), format!("${:.2}", row.cost.value())));
+        for model in &row.models {
+            lines.push((
+                format!("  {}", model.model),
+                format!("${:.2}", model.cost.value()),
+            ));
+        }
+    }
+
+    let label_width = lines
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    let cost_width = lines.iter().map(|(_, cost)| cost.len()).max().unwrap_or(0);
+    lines
+        .iter()
+        .map(|(label, cost)| format!("{:<label_width$}  {:>cost_width$}\n", label, cost))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SessionId, UsageEntryData};
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    fn entry(line: &str) -> Arc<UsageEntry> {
+        let data: UsageEntryData = serde_json::from_str(line).unwrap();
+        Arc::new(UsageEntry::from_data(data, SessionId::from("s")))
+    }
+
+    fn snapshot() -> MergedUsageSnapshot {
+        MergedUsageSnapshot::new(vec![
+            // Monday and Wednesday of ISO week 3, then Monday of week 5
+            entry(
+                r#"{"timestamp":"2024-01-15T10:00:00Z","costUSD":1.5,"message":{"id":"m1","model":"claude-sonnet-4-20250514"}}"#,
+            ),
+            entry(
+                r#"{"timestamp":"2024-01-17T10:00:00Z","costUSD":3.0,"message":{"id":"m2","model":"claude-opus-4-1-20250805"}}"#,
+            ),
+            entry(
+                r#"{"timestamp":"2024-01-29T10:00:00Z","costUSD":0.25,"message":{"id":"m3","model":"claude-sonnet-4-20250514"}}"#,
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_report_period_from_str() {
+        assert_eq!("Week".parse::<ReportPeriod>(), Ok(ReportPeriod::Week));
+        assert!("year".parse::<ReportPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_period_costs_by_week_and_month() {
+        let weeks = snapshot().period_costs_in(ReportPeriod::Week, &Utc);
+        let labels: Vec<&str> = weeks.iter().map(|row| row.period.as_str()).collect();
+        assert_eq!(labels, vec!["2024-W03", "2024-W05"]);
+        assert_eq!(weeks[0].cost.value(), 4.5);
+        assert_eq!(
+            weeks[0].models,
+            vec![
+                ModelCost {
+                    model: ModelId::from("claude-opus-4-1-20250805"),
+                    cost: Cost::new(3.0),
+                },
+                ModelCost {
+                    model: ModelId::from("claude-sonnet-4-20250514"),
+                    cost: Cost::new(1.5),
+                },
+            ]
+        );
+
+        let months = snapshot().period_costs_in(ReportPeriod::Month, &Utc);
+        assert_eq!(months.len(), 1);
+        assert_eq!(months[0].period, "2024-01");
+        assert_eq!(months[0].cost.value(), 4.75);
+        assert_eq!(snapshot().period_costs_in(ReportPeriod::Day, &Utc).len(), 3);
+    }
+
+    #[test]
+    fn test_format_report_table_aligns_columns() {
+        let rows = snapshot().period_costs_in(ReportPeriod::Month, &Utc);
+        assert_eq!(
+            format_report_table(&rows),
+            "PERIOD                       COST\n\
+             2024-01                     $4.75\n\
+             \x20 claude-opus-4-1-20250805  $3.00\n\
+             \x20 claude-sonnet-4-20250514  $1.75\n"
+        );
+    }
+}
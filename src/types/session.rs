@@ -1,16 +1,30 @@
-use super::cost::Cost;
-use super::ids::{SessionId, UniqueHash};
+use super::cost::{Cost, CostAccumulator, CostComposition};
+use super::diagnostics::LoadDiagnostics;
+use super::ids::{DedupBuildHasher, ModelId, SessionId, UniqueHash};
+use super::remaining_time::RemainingTime;
+use super::token_totals::TokenTotals;
 use super::usage::UsageEntry;
-use crate::constants::SESSION_BLOCK_DURATION;
-use chrono::{DateTime, Duration, Local, Timelike, Utc};
-use std::collections::HashSet;
+use crate::config;
+use crate::constants::session_block_duration;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
 /// Type alias for parsed entry with timestamp and Arc-wrapped entry
 type ParsedEntry = (DateTime<Utc>, Arc<UsageEntry>);
 
+/// Model id used to group entries that carry no model information
+const UNKNOWN_MODEL: &str = "unknown";
+
+/// Entry count from which session blocks are built on the rayon thread pool
+const PARALLEL_BLOCKS_MIN_ENTRIES: usize = 10_000;
+
 /// Parse a UsageEntry and extract its timestamp
-fn parse_entry_timestamp(entry: &UsageEntry) -> Option<DateTime<Utc>> {
+pub(super) fn parse_entry_timestamp(entry: &UsageEntry) -> Option<DateTime<Utc>> {
     entry
         .data
         .timestamp
@@ -18,6 +32,65 @@ fn parse_entry_timestamp(entry: &UsageEntry) -> Option<DateTime<Utc>> {
         .and_then(|t| t.parse::<DateTime<Utc>>().ok())
 }
 
+/// Derive the `projects/<dir>` key Claude Code uses for a project path
+/// Every character other than an ASCII letter or digit becomes `-`,
+/// e.g. `/Users/me/src/ccr` -> `-Users-me-src-ccr`
+pub fn project_key_from_path(path: &str) -> String {
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Local midnight of the given date as an ISO 8601 UTC timestamp string
+/// Returns None when the local time is ambiguous or skipped (DST transitions)
+fn local_midnight_utc(date: NaiveDate) -> Option<String> {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_local_timezone(Local).single())
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+/// Extract the UTC offset suffix of an RFC 3339 timestamp
+fn timestamp_offset(timestamp: &str) -> Option<&str> {
+    if timestamp.ends_with(['Z', 'z']) {
+        return Some("Z");
+    }
+
+    // The offset sign can only appear in the time part
+    let time_start = timestamp.find(['T', 't', ' '])?;
+    let sign = timestamp[time_start..].rfind(['+', '-'])?;
+    Some(&timestamp[time_start + sign..])
+}
+
+/// Span of the session cost shown in the statusline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionScope {
+    /// Everything recorded for the session id
+    #[default]
+    All,
+    /// Only today's part, for session ids kept open across days
+    Today,
+}
+
+impl SessionScope {
+    /// Read the scope from CLAUDE_CCR_SESSION_SCOPE (all|today)
+    pub fn from_env() -> Self {
+        config::env_parse("CLAUDE_CCR_SESSION_SCOPE").unwrap_or_default()
+    }
+}
+
+impl FromStr for SessionScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(SessionScope::All),
+            "today" => Ok(SessionScope::Today),
+            other => Err(format!("unknown session scope '{}'", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum SessionBlock {
     /// Idle period between sessions
@@ -26,15 +99,17 @@ pub enum SessionBlock {
         end_time: DateTime<Utc>,
     },
 
-    /// Currently active session (within SESSION_BLOCK_DURATION)
+    /// Currently active session (within the block duration)
     Active {
         start_time: DateTime<Utc>,
+        duration: Duration,
         entries: Vec<Arc<UsageEntry>>,
     },
 
     /// Completed past session
     Completed {
         start_time: DateTime<Utc>,
+        duration: Duration,
         entries: Vec<Arc<UsageEntry>>,
     },
 }
@@ -45,19 +120,21 @@ impl SessionBlock {
         entries: Vec<Arc<UsageEntry>>,
         last_entry_time: DateTime<Utc>,
         now: DateTime<Utc>,
+        duration: Duration,
     ) -> Self {
-        let block_end = block_start + SESSION_BLOCK_DURATION;
-        let is_active =
-            now.signed_duration_since(last_entry_time) < SESSION_BLOCK_DURATION && now < block_end;
+        let block_end = block_start + duration;
+        let is_active = now.signed_duration_since(last_entry_time) < duration && now < block_end;
 
         if is_active {
             SessionBlock::Active {
                 start_time: block_start,
+                duration,
                 entries,
             }
         } else {
             SessionBlock::Completed {
                 start_time: block_start,
+                duration,
                 entries,
             }
         }
@@ -70,12 +147,29 @@ impl SessionBlock {
         }
     }
 
+    #[inline(always)]
+    pub fn start_time(&self) -> DateTime<Utc> {
+        match self {
+            SessionBlock::Idle { start_time, .. }
+            | SessionBlock::Active { start_time, .. }
+            | SessionBlock::Completed { start_time, .. } => *start_time,
+        }
+    }
+
     #[inline(always)]
     pub fn end_time(&self) -> DateTime<Utc> {
         match self {
             SessionBlock::Idle { end_time, .. } => *end_time,
-            SessionBlock::Active { start_time, .. } => *start_time + SESSION_BLOCK_DURATION,
-            SessionBlock::Completed { start_time, .. } => *start_time + SESSION_BLOCK_DURATION,
+            SessionBlock::Active {
+                start_time,
+                duration,
+                ..
+            }
+            | SessionBlock::Completed {
+                start_time,
+                duration,
+                ..
+            } => *start_time + *duration,
         }
     }
 
@@ -83,6 +177,87 @@ impl SessionBlock {
         Cost::from_session_block(self)
     }
 
+    /// Split the block cost by token kind
+    pub fn cost_composition(&self) -> CostComposition {
+        CostComposition::from_entries(self.entries().into_iter())
+    }
+
+    /// Block window as local clock times, e.g. "14:00–19:00"
+    pub fn window_local_string(&self) -> String {
+        self.window_string(&Local)
+    }
+
+    /// Block window as clock times in the given time zone
+    pub fn window_string<Tz>(&self, tz: &Tz) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        format!(
+            "{}–{}",
+            self.start_time().with_timezone(tz).format("%H:%M"),
+            self.end_time().with_timezone(tz).format("%H:%M")
+        )
+    }
+
+    /// Local clock time at which the block resets, e.g. "19:00"
+    pub fn reset_local_string(&self) -> String {
+        self.reset_string(&Local)
+    }
+
+    /// Clock time of the block's end in the given time zone
+    pub fn reset_string<Tz>(&self, tz: &Tz) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        self.end_time()
+            .with_timezone(tz)
+            .format("%H:%M")
+            .to_string()
+    }
+
+    /// Whether an active block has less than `threshold_minutes` left
+    /// Idle and completed blocks are never ending soon
+    pub fn is_ending_soon(&self, threshold_minutes: i64) -> bool {
+        self.is_ending_soon_at(threshold_minutes, Utc::now())
+    }
+
+    /// Whether an active block has less than `threshold_minutes` left at `now`
+    pub fn is_ending_soon_at(&self, threshold_minutes: i64, now: DateTime<Utc>) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        let remaining = RemainingTime::from_session_block_at(self, now);
+        remaining.has_remaining() && remaining.minutes() < threshold_minutes
+    }
+
+    /// Budget left in this block for a given limit, never below zero
+    pub fn remaining_budget(&self, limit: Cost) -> Cost {
+        Cost::new((limit.value() - self.cost().value()).max(0.0))
+    }
+
+    /// Number of distinct messages in this block
+    /// Streaming writes the same message id several times, so ids are counted once
+    pub fn message_count(&self) -> usize {
+        distinct_message_count(self.entries().into_iter())
+    }
+
+    /// Messages left in this block for a plan's message cap, never below zero
+    pub fn remaining_messages(&self, cap: usize) -> usize {
+        cap.saturating_sub(self.message_count())
+    }
+
+    /// Model accounting for more than half of the block cost
+    /// Returns None for idle, empty, or mixed blocks
+    pub fn dominant_model(&self) -> Option<ModelId> {
+        let by_model = cost_by_model(self.entries().into_iter());
+        let total: Cost = by_model.iter().map(|(_, cost)| *cost).sum();
+        let (model, cost) = by_model.into_iter().next()?;
+
+        (cost.value() * 2.0 > total.value() && model.as_str() != UNKNOWN_MODEL).then_some(model)
+    }
+
     #[inline(always)]
     pub fn entries(&self) -> Vec<&UsageEntry> {
         match self {
@@ -141,50 +316,209 @@ impl SessionBlock {
 }
 
 /// Merged snapshot with all session data
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MergedUsageSnapshot {
     pub all_entries: Vec<Arc<UsageEntry>>,
+    /// Non-fatal problems encountered while loading
+    pub diagnostics: LoadDiagnostics,
+    /// Loaded with deduplication disabled, so block costs keep duplicates too
+    pub keeps_duplicates: bool,
 }
 
 impl MergedUsageSnapshot {
+    /// Create a snapshot from entries already sorted by timestamp
+    pub fn new(all_entries: Vec<Arc<UsageEntry>>) -> Self {
+        Self {
+            all_entries,
+            diagnostics: LoadDiagnostics::default(),
+            keeps_duplicates: false,
+        }
+    }
+
     /// Returns a slice of today's entries from all_entries
+    /// Today is the local [midnight, next midnight) window, so entries with
+    /// future timestamps (skewed clocks) are not counted as today
     /// Uses binary search since all_entries is sorted by timestamp
     fn today_entries(&self) -> &[Arc<UsageEntry>] {
         if self.all_entries.is_empty() {
             return &self.all_entries;
         }
 
-        // Get today's start in the same format as UsageEntry.timestamp (ISO 8601 UTC)
+        // Get today's bounds in the same format as UsageEntry.timestamp (ISO 8601 UTC)
         // This accounts for timezone differences
-        // If time calculation fails, return all entries as fallback
-        let today_start = Local::now()
-            .date_naive()
-            .and_hms_opt(0, 0, 0)
-            .and_then(|dt| dt.and_local_timezone(Local).single())
-            .map(|dt| dt.with_timezone(&chrono::Utc))
-            .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
-            .unwrap_or_else(|| {
-                // Fallback: use a very early timestamp to include all entries
-                "1970-01-01T00:00:00.000Z".to_string()
-            });
+        // If time calculation fails, fall back to an open bound on that side
+        let today = Local::now().date_naive();
+        let today_start = local_midnight_utc(today).unwrap_or_else(|| {
+            // Fallback: use a very early timestamp to include all entries
+            "1970-01-01T00:00:00.000Z".to_string()
+        });
+        let tomorrow_start = today.succ_opt().and_then(local_midnight_utc);
 
         // Binary search to find the first entry of today
         // Since timestamps are ISO 8601 strings, we can compare them directly
         let start_idx = self.all_entries.partition_point(|entry| {
             entry.data.timestamp.as_deref().unwrap_or("") < today_start.as_str()
         });
+        let end_idx = match tomorrow_start {
+            Some(ref tomorrow_start) => self.all_entries.partition_point(|entry| {
+                entry.data.timestamp.as_deref().unwrap_or("") < tomorrow_start.as_str()
+            }),
+            None => self.all_entries.len(),
+        };
+
+        &self.all_entries[start_idx..end_idx.max(start_idx)]
+    }
 
-        &self.all_entries[start_idx..]
+    /// Iterate over today's entries in timestamp order
+    /// Uses the same local-midnight binary search as today_cost
+    pub fn today_entries_iter(&self) -> impl Iterator<Item = &UsageEntry> {
+        self.today_entries().iter().map(|e| e.as_ref())
     }
 
     /// Calculate today's cost
     /// Uses today_entries() to get today's data and calculates total cost
     pub fn today_cost(&self) -> Cost {
-        Cost::from_entries(self.today_entries().iter().map(|e| e.as_ref()))
+        Cost::from_entries(self.today_entries_iter())
+    }
+
+    /// Share of today's input tokens read from the prompt cache
+    /// None when nothing was sent today
+    pub fn today_cache_hit_ratio(&self) -> Option<f64> {
+        TokenTotals::from_entries(self.today_entries_iter()).cache_hit_ratio()
+    }
+
+    /// Cost of today's retried or regenerated responses
+    /// Entries sharing a request id but with different message ids survive the
+    /// message+request dedup; every one after the first (in timestamp order)
+    /// is counted as retry spend
+    pub fn retry_cost_today(&self) -> Cost {
+        let mut seen_requests = HashSet::new();
+        Cost::from_entries(self.today_entries_iter().filter(|entry| {
+            entry
+                .data
+                .request_id
+                .as_ref()
+                .is_some_and(|request_id| !seen_requests.insert(request_id))
+        }))
+    }
+
+    /// What today's usage would have cost if every entry had used `model`
+    /// Recomputes each entry from its tokens, ignoring its actual model and
+    /// any pre-calculated cost, to quantify savings from switching models
+    pub fn cost_if_model(&self, model: &ModelId) -> Cost {
+        self.today_entries_iter()
+            .map(|entry| Cost::from_entry_as_model(entry, model))
+            .sum()
+    }
+
+    /// Split today's cost into input, output, cache write and cache read spend
+    pub fn cost_composition_today(&self) -> CostComposition {
+        CostComposition::from_entries(self.today_entries_iter())
+    }
+
+    /// Count distinct message ids among today's entries
+    pub fn message_count_today(&self) -> usize {
+        distinct_message_count(self.today_entries_iter())
+    }
+
+    /// Calculate today's average cost per message
+    /// Returns None if no messages were sent today
+    pub fn today_cost_per_message(&self) -> Option<Cost> {
+        let count = self.message_count_today();
+        if count == 0 {
+            return None;
+        }
+        Some(Cost::new(self.today_cost().value() / count as f64))
+    }
+
+    /// Distinct UTC offset suffixes ("Z", "+09:00", ...) found in a sample of entries
+    /// The day boundaries assume "Z" timestamps, so any other offset may skew "today"
+    pub fn timestamp_offsets(&self, sample_size: usize) -> BTreeSet<String> {
+        let step = (self.all_entries.len() / sample_size.max(1)).max(1);
+
+        self.all_entries
+            .iter()
+            .step_by(step)
+            .filter_map(|entry| entry.data.timestamp.as_deref())
+            .filter_map(timestamp_offset)
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Earliest and latest timestamps among the loaded entries
+    /// This is the effective window after the cutoff filter; since all_entries
+    /// is sorted, only the ends are inspected (skipping unparseable timestamps)
+    pub fn loaded_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let first = self
+            .all_entries
+            .iter()
+            .find_map(|entry| parse_entry_timestamp(entry))?;
+        let last = self
+            .all_entries
+            .iter()
+            .rev()
+            .find_map(|entry| parse_entry_timestamp(entry))?;
+        Some((first, last))
+    }
+
+    /// Calculate today's cost across all sessions of one project
+    /// `project_key` is the `projects/<dir>` name, see `project_key_from_path`
+    pub fn project_cost_today(&self, project_key: &str) -> Cost {
+        Cost::from_entries(
+            self.today_entries()
+                .iter()
+                .filter(|entry| entry.project.as_deref() == Some(project_key))
+                .map(|e| e.as_ref()),
+        )
+    }
+
+    /// Cost of all loaded entries per project key, most expensive first
+    /// Keys are left as the `projects/<dir>` names: the encoding turned slashes,
+    /// hyphens, dots and spaces alike into `-`, so it cannot be decoded reliably.
+    /// Entries outside `projects/` are left out
+    pub fn projects_by_cost(&self) -> Vec<(String, Cost)> {
+        let mut totals: HashMap<&str, Vec<&UsageEntry>> = HashMap::new();
+        for entry in &self.all_entries {
+            if let Some(project) = entry.project.as_deref() {
+                totals.entry(project).or_default().push(entry);
+            }
+        }
+
+        let mut by_project: Vec<(String, Cost)> = totals
+            .into_iter()
+            .map(|(key, entries)| (key.to_string(), Cost::from_entries(entries.into_iter())))
+            .collect();
+        sort_by_cost_desc(&mut by_project);
+        by_project
+    }
+
+    /// Claude data root the session's entries were loaded from
+    /// Helps confirm which install is being read when several are configured;
+    /// None if the session has no entries or they were not loaded from a root
+    pub fn source_of_session(&self, session_id: &SessionId) -> Option<PathBuf> {
+        self.all_entries
+            .iter()
+            .filter(|entry| entry.session_id == *session_id)
+            .find_map(|entry| entry.source.as_deref())
+            .map(Path::to_path_buf)
+    }
+
+    /// Number of entries whose timestamp is missing or not valid RFC 3339
+    /// Such entries still count toward session_cost, which needs no timestamp,
+    /// and are left out of every session block. Today's cost drops entries
+    /// without a timestamp, but compares the raw strings, so a malformed one
+    /// can still sort into today's range
+    pub fn timestampless_entries(&self) -> usize {
+        self.all_entries
+            .iter()
+            .filter(|entry| parse_entry_timestamp(entry).is_none())
+            .count()
     }
 
     /// Calculate cost for a specific session
     /// Filters entries by session_id and calculates total cost
+    /// Entries without a usable timestamp are included here but not in
+    /// the blocks (see timestampless_entries)
     pub fn session_cost(&self, session_id: &SessionId) -> Cost {
         Cost::from_entries(
             self.all_entries
@@ -194,111 +528,406 @@ impl MergedUsageSnapshot {
         )
     }
 
+    /// Calculate a session's cost, pricing its entries that have usage but no
+    /// model as `fallback` (the live model reported by the hook)
+    pub fn session_cost_with_model(
+        &self,
+        session_id: &SessionId,
+        fallback: Option<&ModelId>,
+    ) -> Cost {
+        let Some(fallback) = fallback else {
+            return self.session_cost(session_id);
+        };
+        self.all_entries
+            .iter()
+            .filter(|entry| entry.session_id == *session_id)
+            .map(|entry| Cost::from_entry_or_model(entry, fallback))
+            .sum()
+    }
+
+    /// Calculate the combined cost of several sessions in a single pass
+    pub fn cost_of_sessions(&self, session_ids: &[SessionId]) -> Cost {
+        let wanted: HashSet<&SessionId> = session_ids.iter().collect();
+        Cost::from_entries(
+            self.all_entries
+                .iter()
+                .filter(|entry| wanted.contains(&entry.session_id))
+                .map(|e| e.as_ref()),
+        )
+    }
+
+    /// Cost of each of several sessions in a single pass, in the order given
+    /// Sessions without entries are reported at zero
+    pub fn cost_by_session(&self, session_ids: &[SessionId]) -> Vec<(SessionId, Cost)> {
+        let mut totals: HashMap<&SessionId, CostAccumulator> = session_ids
+            .iter()
+            .map(|id| (id, CostAccumulator::default()))
+            .collect();
+        for entry in &self.all_entries {
+            if let Some(total) = totals.get_mut(&entry.session_id) {
+                total.add(Cost::from_entry(entry));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        session_ids
+            .iter()
+            .filter(|id| seen.insert(*id))
+            .map(|id| (id.clone(), totals[id].total()))
+            .collect()
+    }
+
+    /// Calculate today's cost for a specific session
+    /// Keeps the figure meaningful for session ids reused across many days
+    pub fn session_cost_today(&self, session_id: &SessionId) -> Cost {
+        Cost::from_entries(
+            self.today_entries_iter()
+                .filter(|entry| entry.session_id == *session_id),
+        )
+    }
+
+    /// Calculate today's cost grouped by model
+    /// Sorted by cost descending, then model name ascending
+    pub fn today_cost_by_model(&self) -> Vec<(ModelId, Cost)> {
+        cost_by_model(self.today_entries_iter())
+    }
+
     /// Identify session blocks from the snapshot's sorted entries
-    /// This matches the TypeScript implementation in ccusage
-    fn session_blocks(&self) -> Vec<SessionBlock> {
+    /// Always includes idle blocks, which diagnostics need to show the gaps
+    /// Duplicates are only dropped when the loader deduplicated the entries
+    pub fn session_blocks(&self) -> Vec<SessionBlock> {
         if self.all_entries.is_empty() {
             return Vec::new();
         }
 
-        // Phase 1: Parse and deduplicate entries
-        let parsed_entries = self.preprocess_entries();
+        let now = Local::now().with_timezone(&Utc);
+        let duration = session_block_duration();
+        let dedup = !self.keeps_duplicates;
+        // Lifetime reports over long histories are worth spreading over threads
+        if self.all_entries.len() >= PARALLEL_BLOCKS_MIN_ENTRIES {
+            let parsed_entries = preprocess_entries_parallel(&self.all_entries, dedup);
+            build_session_blocks_parallel(&parsed_entries, now, duration)
+        } else {
+            let parsed_entries = preprocess_entries(&self.all_entries, dedup);
+            build_session_blocks(&parsed_entries, now, duration)
+        }
+    }
 
-        // Phase 2: Build session blocks
-        self.build_session_blocks(parsed_entries)
+    /// Session blocks paired with their costs, oldest first
+    /// Reporting passes `include_idle = false`, since idle gaps only clutter a
+    /// timeline or rollup; diagnostics pass true to see them
+    pub fn blocks_with_costs(&self, include_idle: bool) -> Vec<(SessionBlock, Cost)> {
+        self.session_blocks()
+            .into_iter()
+            .filter(|block| include_idle || !block.is_idle())
+            .map(|block| {
+                let cost = block.cost();
+                (block, cost)
+            })
+            .collect()
     }
 
-    /// Preprocess entries: parse timestamps and deduplicate
-    fn preprocess_entries(&self) -> Vec<ParsedEntry> {
-        let mut processed_hashes: HashSet<UniqueHash> = HashSet::new();
-        let mut parsed_entries = Vec::new();
+    /// The `count` most expensive entries, most expensive first (ties oldest first)
+    /// The top list passes `include_negligible = false` to drop entries that
+    /// round to $0.00 (cache-read-only turns, tool results); totals never filter
+    pub fn top_entries(&self, count: usize, include_negligible: bool) -> Vec<(&UsageEntry, Cost)> {
+        let mut entries: Vec<(&UsageEntry, Cost)> = self
+            .all_entries
+            .iter()
+            .map(|entry| (entry.as_ref(), Cost::from_entry(entry)))
+            .filter(|(_, cost)| include_negligible || cost.is_positive())
+            .collect();
+        // Stable, so equal costs keep the snapshot's chronological order
+        entries.sort_by(|(_, a), (_, b)| b.value().total_cmp(&a.value()));
+        entries.truncate(count);
+        entries
+    }
 
-        for entry in self.all_entries.iter() {
-            // Parse timestamp - skip if invalid
-            let Some(timestamp) = parse_entry_timestamp(entry) else {
-                continue;
-            };
+    /// The most recent `count` session blocks, oldest first
+    /// Idle blocks are skipped before counting unless `include_idle` is set
+    pub fn recent_blocks(&self, count: usize, include_idle: bool) -> Vec<SessionBlock> {
+        let mut blocks: Vec<_> = self
+            .session_blocks()
+            .into_iter()
+            .filter(|block| include_idle || !block.is_idle())
+            .collect();
+        let skip = blocks.len().saturating_sub(count);
+        blocks.drain(..skip);
+        blocks
+    }
 
-            // Check for duplicate (only when BOTH IDs exist)
-            if let Some(hash) = UniqueHash::from_usage_entry_data(&entry.data) {
-                if processed_hashes.contains(&hash) {
-                    continue;
-                }
-                processed_hashes.insert(hash);
-            }
+    /// Find the active block from the session blocks
+    pub fn active_block(&self) -> Option<SessionBlock> {
+        self.session_blocks().into_iter().find(|b| b.is_active())
+    }
+}
 
-            parsed_entries.push((timestamp, Arc::clone(entry)));
-        }
+/// Identify session blocks from entries sorted by timestamp
+/// This matches the TypeScript implementation in ccusage
+///
+/// This is the single block-identification entry point shared by the
+/// binaries; an empty slice simply yields no blocks.
+///
+/// ```
+/// use std::sync::Arc;
+/// use ccr::types::identify_session_blocks;
+/// use ccr::{UsageEntry, UsageEntryData};
+///
+/// assert!(identify_session_blocks(&[]).is_empty());
+///
+/// let data: UsageEntryData =
+///     serde_json::from_str(r#"{"timestamp":"2024-01-15T10:30:00Z","costUSD":0.5}"#).unwrap();
+/// let entry = Arc::new(UsageEntry::from_data(data, "session".into()));
+///
+/// let blocks = identify_session_blocks(&[entry]);
+/// assert_eq!(blocks.len(), 1);
+/// assert_eq!(blocks[0].cost().value(), 0.5);
+/// ```
+pub fn identify_session_blocks(entries: &[Arc<UsageEntry>]) -> Vec<SessionBlock> {
+    identify_session_blocks_with(
+        entries,
+        session_block_duration(),
+        Local::now().with_timezone(&Utc),
+    )
+}
 
-        parsed_entries
+/// Identify session blocks with an explicit block duration and current time
+pub fn identify_session_blocks_with(
+    entries: &[Arc<UsageEntry>],
+    duration: Duration,
+    now: DateTime<Utc>,
+) -> Vec<SessionBlock> {
+    if entries.is_empty() {
+        return Vec::new();
     }
 
-    /// Build session blocks from parsed entries
-    fn build_session_blocks(&self, parsed_entries: Vec<ParsedEntry>) -> Vec<SessionBlock> {
-        if parsed_entries.is_empty() {
-            return Vec::new();
+    // Phase 1: Parse and deduplicate entries
+    let parsed_entries = preprocess_entries(entries, true);
+
+    // Phase 2: Build session blocks
+    build_session_blocks(&parsed_entries, now, duration)
+}
+
+/// Identify session blocks, spreading the work over the rayon thread pool
+///
+/// Produces the same blocks as [`identify_session_blocks`]. A gap longer than
+/// the block duration always closes the current block, so the entries are
+/// split at such gaps and each part is built independently.
+/// [`MergedUsageSnapshot::session_blocks`] takes this path for large
+/// histories.
+pub fn identify_session_blocks_parallel(entries: &[Arc<UsageEntry>]) -> Vec<SessionBlock> {
+    if entries.is_empty() {
+        return Vec::new();
+    }
+
+    let parsed_entries = preprocess_entries_parallel(entries, true);
+    build_session_blocks_parallel(
+        &parsed_entries,
+        Local::now().with_timezone(&Utc),
+        session_block_duration(),
+    )
+}
+
+/// Preprocess entries: parse timestamps and, when `dedup` is set, deduplicate
+fn preprocess_entries(entries: &[Arc<UsageEntry>], dedup: bool) -> Vec<ParsedEntry> {
+    let mut processed_hashes: HashSet<UniqueHash, DedupBuildHasher> = HashSet::default();
+    let mut parsed_entries = Vec::new();
+
+    for entry in entries.iter() {
+        // Parse timestamp - skip if invalid
+        let Some(timestamp) = parse_entry_timestamp(entry) else {
+            continue;
+        };
+
+        // Check for duplicate (only when BOTH IDs exist)
+        if dedup && let Some(hash) = UniqueHash::from_usage_entry_data(&entry.data) {
+            if processed_hashes.contains(&hash) {
+                continue;
+            }
+            processed_hashes.insert(hash);
         }
 
-        let now = Local::now().with_timezone(&Utc);
-        let mut blocks = Vec::new();
-
-        // Get the first entry to initialize
-        let (first_timestamp, first_entry) = &parsed_entries[0];
-        let mut current_block_start = floor_to_hour(*first_timestamp);
-        let mut current_block_entries = vec![Arc::clone(first_entry)];
-        let mut last_entry_time = *first_timestamp;
-
-        // Process remaining entries
-        for (timestamp, entry) in parsed_entries.iter().skip(1) {
-            let time_since_block_start = timestamp.signed_duration_since(current_block_start);
-            let time_since_last_entry = timestamp.signed_duration_since(last_entry_time);
-
-            // Check if we need to end the current block
-            if time_since_block_start > SESSION_BLOCK_DURATION
-                || time_since_last_entry > SESSION_BLOCK_DURATION
+        parsed_entries.push((timestamp, Arc::clone(entry)));
+    }
+
+    parsed_entries
+}
+
+/// Preprocess entries like preprocess_entries, parsing timestamps in parallel
+/// Deduplication stays sequential so the first occurrence still wins
+fn preprocess_entries_parallel(entries: &[Arc<UsageEntry>], dedup: bool) -> Vec<ParsedEntry> {
+    let timestamps: Vec<_> = entries
+        .par_iter()
+        .map(|entry| parse_entry_timestamp(entry))
+        .collect();
+
+    let mut processed_hashes: HashSet<UniqueHash, DedupBuildHasher> = HashSet::default();
+    entries
+        .iter()
+        .zip(timestamps)
+        .filter_map(|(entry, timestamp)| {
+            let timestamp = timestamp?;
+            if dedup
+                && let Some(hash) = UniqueHash::from_usage_entry_data(&entry.data)
+                && !processed_hashes.insert(hash)
             {
-                // Create and save the current block
-                blocks.push(SessionBlock::new(
-                    current_block_start,
-                    current_block_entries,
-                    last_entry_time,
-                    now,
-                ));
-
-                // If there's an idle period, create an idle block
-                if time_since_last_entry > SESSION_BLOCK_DURATION {
-                    blocks.push(SessionBlock::idle(
-                        last_entry_time + SESSION_BLOCK_DURATION,
-                        *timestamp,
-                    ));
-                }
-
-                // Start new block
-                current_block_start = floor_to_hour(*timestamp);
-                current_block_entries = vec![Arc::clone(entry)];
-            } else {
-                // Add to current block
-                current_block_entries.push(Arc::clone(entry));
+                return None;
             }
+            Some((timestamp, Arc::clone(entry)))
+        })
+        .collect()
+}
 
-            last_entry_time = *timestamp;
+/// Build session blocks by splitting the entries at idle gaps and building
+/// each part on its own thread
+fn build_session_blocks_parallel(
+    parsed_entries: &[ParsedEntry],
+    now: DateTime<Utc>,
+    duration: Duration,
+) -> Vec<SessionBlock> {
+    // Split wherever the sequential builder would insert an idle block
+    let mut partitions = Vec::new();
+    let mut partition_start = 0;
+    for i in 1..parsed_entries.len() {
+        let gap = parsed_entries[i]
+            .0
+            .signed_duration_since(parsed_entries[i - 1].0);
+        if gap > duration {
+            partitions.push(&parsed_entries[partition_start..i]);
+            partition_start = i;
+        }
+    }
+    partitions.push(&parsed_entries[partition_start..]);
+
+    let built: Vec<Vec<SessionBlock>> = partitions
+        .par_iter()
+        .map(|partition| build_session_blocks(partition, now, duration))
+        .collect();
+
+    // Concatenate, restoring the idle block between neighbouring partitions
+    let mut blocks = Vec::with_capacity(built.iter().map(Vec::len).sum::<usize>() + built.len());
+    for (i, partition_blocks) in built.into_iter().enumerate() {
+        if i > 0 {
+            let last_entry_time = partitions[i - 1][partitions[i - 1].len() - 1].0;
+            blocks.push(SessionBlock::idle(
+                last_entry_time + duration,
+                partitions[i][0].0,
+            ));
         }
+        blocks.extend(partition_blocks);
+    }
 
-        // Create the final block with remaining entries
-        blocks.push(SessionBlock::new(
-            current_block_start,
-            current_block_entries,
-            last_entry_time,
-            now,
-        ));
+    blocks
+}
 
-        blocks
+/// Build session blocks from parsed entries
+fn build_session_blocks(
+    parsed_entries: &[ParsedEntry],
+    now: DateTime<Utc>,
+    duration: Duration,
+) -> Vec<SessionBlock> {
+    if parsed_entries.is_empty() {
+        return Vec::new();
     }
 
-    /// Find the active block from the session blocks
-    pub fn active_block(&self) -> Option<SessionBlock> {
-        self.session_blocks().into_iter().find(|b| b.is_active())
+    let mut blocks = Vec::new();
+
+    // Get the first entry to initialize
+    let (first_timestamp, first_entry) = &parsed_entries[0];
+    let mut current_block_start = floor_to_hour(*first_timestamp);
+    let mut current_block_entries = vec![Arc::clone(first_entry)];
+    let mut last_entry_time = *first_timestamp;
+
+    // Process remaining entries
+    for (timestamp, entry) in parsed_entries.iter().skip(1) {
+        let time_since_block_start = timestamp.signed_duration_since(current_block_start);
+        let time_since_last_entry = timestamp.signed_duration_since(last_entry_time);
+
+        // Check if we need to end the current block
+        if time_since_block_start > duration || time_since_last_entry > duration {
+            // Create and save the current block
+            blocks.push(SessionBlock::new(
+                current_block_start,
+                current_block_entries,
+                last_entry_time,
+                now,
+                duration,
+            ));
+
+            // If there's an idle period, create an idle block
+            if time_since_last_entry > duration {
+                blocks.push(SessionBlock::idle(last_entry_time + duration, *timestamp));
+            }
+
+            // Start new block
+            current_block_start = floor_to_hour(*timestamp);
+            current_block_entries = vec![Arc::clone(entry)];
+        } else {
+            // Add to current block
+            current_block_entries.push(Arc::clone(entry));
+        }
+
+        last_entry_time = *timestamp;
     }
+
+    // Create the final block with remaining entries
+    blocks.push(SessionBlock::new(
+        current_block_start,
+        current_block_entries,
+        last_entry_time,
+        now,
+        duration,
+    ));
+
+    blocks
+}
+
+/// Count distinct message ids; entries without an id are not counted
+pub(super) fn distinct_message_count<'a, I>(entries: I) -> usize
+where
+    I: Iterator<Item = &'a UsageEntry>,
+{
+    entries
+        .filter_map(|entry| entry.data.message.as_ref()?.id.as_ref())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Group entry costs by model
+/// The result is sorted by cost descending with the model name as tiebreaker,
+/// so repeated runs produce the same order regardless of HashMap iteration
+pub(super) fn cost_by_model<'a, I>(entries: I) -> Vec<(ModelId, Cost)>
+where
+    I: Iterator<Item = &'a UsageEntry>,
+{
+    let mut totals: HashMap<ModelId, CostAccumulator> = HashMap::new();
+    for entry in entries {
+        let model = entry
+            .model()
+            .cloned()
+            .unwrap_or_else(|| ModelId::from(UNKNOWN_MODEL));
+        totals
+            .entry(model)
+            .or_default()
+            .add(Cost::from_entry(entry));
+    }
+
+    let mut by_model: Vec<(ModelId, Cost)> = totals
+        .into_iter()
+        .map(|(model, total)| (model, total.total()))
+        .collect();
+    sort_by_cost_desc(&mut by_model);
+    by_model
+}
+
+/// Sort (key, cost) pairs by cost descending, then key ascending
+fn sort_by_cost_desc<K: Ord>(items: &mut [(K, Cost)]) {
+    items.sort_by(|(key_a, cost_a), (key_b, cost_b)| {
+        cost_b
+            .value()
+            .total_cmp(&cost_a.value())
+            .then_with(|| key_a.cmp(key_b))
+    });
 }
 
 /// Floor timestamp to the hour (e.g., 14:37:22 → 14:00:00)
@@ -316,6 +945,7 @@ fn floor_to_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
 mod tests {
     use super::*;
     use crate::ModelId;
+    use crate::constants::SESSION_BLOCK_DURATION;
     use crate::types::{Message, MessageId, RequestId, Usage, UsageEntryData};
     use chrono::{Datelike, TimeZone, Timelike};
 
@@ -343,14 +973,28 @@ mod tests {
                         cache_read_input_tokens: None,
                         cache_creation: None,
                         service_tier: None,
+                        reasoning_output_tokens: None,
                     }),
                 }),
                 request_id: request_id.map(RequestId::from),
             },
             session_id: SessionId::from(session_id),
+            project: None,
+            source: None,
         })
     }
 
+    /// Start of today in local time, as UTC
+    fn local_today_start() -> DateTime<Utc> {
+        Local::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
     #[test]
     fn test_floor_to_hour() {
         let timestamp = Utc.with_ymd_and_hms(2024, 1, 15, 14, 37, 22).unwrap();
@@ -395,6 +1039,8 @@ mod tests {
                 request_id: None,
             },
             session_id: SessionId::from("test-session"),
+            project: None,
+            source: None,
         });
 
         let timestamp = parse_entry_timestamp(&entry);
@@ -416,7 +1062,13 @@ mod tests {
             Some(50),
         )];
 
-        let block = SessionBlock::new(block_start, entries.clone(), last_entry_time, now);
+        let block = SessionBlock::new(
+            block_start,
+            entries.clone(),
+            last_entry_time,
+            now,
+            SESSION_BLOCK_DURATION,
+        );
 
         assert!(block.is_active());
         assert!(!block.is_idle());
@@ -424,9 +1076,11 @@ mod tests {
         match block {
             SessionBlock::Active {
                 start_time,
+                duration,
                 entries: block_entries,
             } => {
                 assert_eq!(start_time, block_start);
+                assert_eq!(duration, SESSION_BLOCK_DURATION);
                 assert_eq!(block_entries.len(), 1);
             }
             _ => panic!("Expected Active block"),
@@ -448,7 +1102,13 @@ mod tests {
             Some(50),
         )];
 
-        let block = SessionBlock::new(block_start, entries.clone(), last_entry_time, now);
+        let block = SessionBlock::new(
+            block_start,
+            entries.clone(),
+            last_entry_time,
+            now,
+            SESSION_BLOCK_DURATION,
+        );
 
         assert!(!block.is_active());
         assert!(!block.is_idle());
@@ -456,9 +1116,11 @@ mod tests {
         match block {
             SessionBlock::Completed {
                 start_time,
+                duration,
                 entries: block_entries,
             } => {
                 assert_eq!(start_time, block_start);
+                assert_eq!(duration, SESSION_BLOCK_DURATION);
                 assert_eq!(block_entries.len(), 1);
             }
             _ => panic!("Expected Completed block"),
@@ -489,6 +1151,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_session_block_remaining_budget() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let entry_with_cost = |cost: f64| {
+            Arc::new(UsageEntry {
+                data: UsageEntryData {
+                    timestamp: Some(start.to_rfc3339()),
+                    model: None,
+                    cost_usd: Some(cost),
+                    message: None,
+                    request_id: None,
+                },
+                session_id: SessionId::from("test-session"),
+                project: None,
+                source: None,
+            })
+        };
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![entry_with_cost(1.5), entry_with_cost(1.0)],
+        };
+
+        // Below the limit
+        assert_eq!(block.remaining_budget(Cost::new(5.0)).value(), 2.5);
+        // Above the limit never goes negative
+        assert_eq!(block.remaining_budget(Cost::new(2.0)).value(), 0.0);
+        // Idle blocks have the whole budget left
+        let idle = SessionBlock::idle(start, start + Duration::hours(1));
+        assert_eq!(idle.remaining_budget(Cost::new(5.0)).value(), 5.0);
+    }
+
+    #[test]
+    fn test_session_block_window_string() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap();
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![],
+        };
+        assert_eq!(block.window_string(&Utc), "14:00–19:00");
+
+        let tokyo = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(block.window_string(&tokyo), "23:00–04:00");
+        assert_eq!(block.reset_string(&Utc), "19:00");
+        assert_eq!(block.reset_string(&tokyo), "04:00");
+
+        let idle = SessionBlock::idle(start, start + Duration::minutes(90));
+        assert_eq!(idle.window_string(&Utc), "14:00–15:30");
+    }
+
+    #[test]
+    fn test_session_block_message_count() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let at = |minutes| (start + Duration::minutes(minutes)).to_rfc3339();
+        let block = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![
+                create_test_entry("s", &at(0), Some("msg-1"), Some("req-1"), Some(10), None),
+                // Streaming chunk of the same message
+                create_test_entry("s", &at(1), Some("msg-1"), Some("req-2"), None, Some(20)),
+                create_test_entry("s", &at(2), Some("msg-2"), Some("req-3"), Some(10), None),
+                // Entries without a message id are not counted
+                create_test_entry("s", &at(3), None, None, Some(10), None),
+            ],
+        };
+
+        assert_eq!(block.message_count(), 2);
+        assert_eq!(block.remaining_messages(45), 43);
+        assert_eq!(block.remaining_messages(1), 0);
+
+        let idle = SessionBlock::idle(start, start + Duration::hours(1));
+        assert_eq!(idle.message_count(), 0);
+        assert_eq!(idle.remaining_messages(45), 45);
+    }
+
     #[test]
     fn test_session_block_actual_duration() {
         let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
@@ -522,6 +1261,7 @@ mod tests {
 
         let block = SessionBlock::Active {
             start_time: base_time,
+            duration: SESSION_BLOCK_DURATION,
             entries,
         };
 
@@ -549,6 +1289,7 @@ mod tests {
         // Test Active block
         let active_block = SessionBlock::Active {
             start_time: start,
+            duration: SESSION_BLOCK_DURATION,
             entries: vec![],
         };
         assert_eq!(active_block.end_time(), start + SESSION_BLOCK_DURATION);
@@ -556,6 +1297,7 @@ mod tests {
         // Test Completed block
         let completed_block = SessionBlock::Completed {
             start_time: start,
+            duration: SESSION_BLOCK_DURATION,
             entries: vec![],
         };
         assert_eq!(completed_block.end_time(), start + SESSION_BLOCK_DURATION);
@@ -569,13 +1311,7 @@ mod tests {
     #[test]
     fn test_merged_usage_snapshot_today_entries() {
         let _now = Local::now().with_timezone(&Utc);
-        let today_start = Local::now()
-            .date_naive()
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_local_timezone(Local)
-            .unwrap()
-            .with_timezone(&Utc);
+        let today_start = local_today_start();
 
         let yesterday = today_start - Duration::days(1);
         let today_morning = today_start + Duration::hours(8);
@@ -608,12 +1344,16 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
         let today_entries = snapshot.today_entries();
         assert_eq!(today_entries.len(), 2);
+        assert_eq!(snapshot.today_entries_iter().count(), 2);
+        assert!(
+            snapshot
+                .today_entries_iter()
+                .all(|entry| entry.session_id.as_str() != "session-1")
+        );
 
         // Verify that only today's entries are included
         for entry in today_entries {
@@ -622,6 +1362,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_merged_usage_snapshot_cost_per_message() {
+        let today_start = local_today_start();
+        let yesterday = (today_start - Duration::hours(1)).to_rfc3339();
+        let first = (today_start + Duration::seconds(1)).to_rfc3339();
+        let second = (today_start + Duration::seconds(2)).to_rfc3339();
+
+        let entries = vec![
+            create_test_entry(
+                "s",
+                &yesterday,
+                Some("msg-0"),
+                Some("req-0"),
+                Some(1),
+                Some(1),
+            ),
+            create_test_entry(
+                "s",
+                &first,
+                Some("msg-1"),
+                Some("req-1"),
+                Some(1000),
+                Some(500),
+            ),
+            // Streaming chunk of the same message with a different request
+            create_test_entry(
+                "s",
+                &first,
+                Some("msg-1"),
+                Some("req-2"),
+                Some(1000),
+                Some(500),
+            ),
+            create_test_entry(
+                "s",
+                &second,
+                Some("msg-2"),
+                Some("req-3"),
+                Some(1000),
+                Some(500),
+            ),
+            create_test_entry("s", &second, None, Some("req-4"), Some(1000), Some(500)),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        // Yesterday's message and the id-less entry are not counted
+        assert_eq!(snapshot.message_count_today(), 2);
+        let average = snapshot.today_cost_per_message().unwrap();
+        assert!((average.value() - snapshot.today_cost().value() / 2.0).abs() < 1e-12);
+        assert!(average.is_positive());
+
+        let empty = MergedUsageSnapshot::new(vec![]);
+        assert_eq!(empty.message_count_today(), 0);
+        assert!(empty.today_cost_per_message().is_none());
+    }
+
     #[test]
     fn test_merged_usage_snapshot_session_cost() {
         let entries = vec![
@@ -651,9 +1447,7 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
         // Session 1 should have 2 entries
         let session1_cost = snapshot.session_cost(&SessionId::from("session-1"));
@@ -697,11 +1491,9 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
-        let processed = snapshot.preprocess_entries();
+        let processed = preprocess_entries(&snapshot.all_entries, true);
 
         // Should have 2 entries after deduplication
         assert_eq!(processed.len(), 2);
@@ -746,9 +1538,7 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
         let blocks = snapshot.session_blocks();
 
@@ -784,9 +1574,7 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
         let active_block = snapshot.active_block();
         assert!(active_block.is_some());
@@ -798,9 +1586,7 @@ mod tests {
 
     #[test]
     fn test_merged_usage_snapshot_empty() {
-        let snapshot = MergedUsageSnapshot {
-            all_entries: vec![],
-        };
+        let snapshot = MergedUsageSnapshot::new(vec![]);
 
         assert_eq!(snapshot.today_entries().len(), 0);
         assert_eq!(snapshot.today_cost().value(), 0.0);
@@ -809,6 +1595,585 @@ mod tests {
         assert_eq!(snapshot.session_blocks().len(), 0);
     }
 
+    #[test]
+    fn test_breakdowns_match_compensated_total() {
+        // Many tiny costs after a large one drift under naive summation
+        let with_cost = |cost: f64| {
+            let mut entry =
+                (*create_test_entry("s", "2024-01-15T10:00:00Z", None, None, None, None)).clone();
+            entry.data.cost_usd = Some(cost);
+            Arc::new(entry)
+        };
+        let mut entries = vec![with_cost(100_000_000.0)];
+        entries.extend((0..100_000).map(|_| with_cost(0.000_1)));
+        let snapshot = MergedUsageSnapshot::new(entries);
+        let total = Cost::from_entries(snapshot.all_entries.iter().map(|e| e.as_ref()));
+        assert!((total.value() - 100_000_010.0).abs() < 1e-9);
+
+        let by_model = cost_by_model(snapshot.all_entries.iter().map(|e| e.as_ref()));
+        assert_eq!(by_model.len(), 1);
+        assert_eq!(by_model[0].1, total);
+        let by_session = snapshot.cost_by_session(&[SessionId::from("s")]);
+        assert_eq!(by_session[0].1, total);
+    }
+
+    #[test]
+    fn test_cost_by_model_deterministic_order() {
+        let entry_with_cost = |model: &str, cost: f64| UsageEntry {
+            data: UsageEntryData {
+                timestamp: Some("2024-01-15T10:00:00.000Z".to_string()),
+                model: Some(ModelId::from(model)),
+                cost_usd: Some(cost),
+                message: None,
+                request_id: None,
+            },
+            session_id: SessionId::from("session-1"),
+            project: None,
+            source: None,
+        };
+
+        let entries = [
+            entry_with_cost("claude-sonnet-4-20250514", 1.0),
+            entry_with_cost("claude-opus-4-1-20250805", 3.0),
+            entry_with_cost("claude-3-5-sonnet-20241022", 1.0),
+        ];
+
+        let by_model = cost_by_model(entries.iter());
+        let models: Vec<&str> = by_model.iter().map(|(m, _)| m.as_str()).collect();
+
+        // Highest cost first, equal costs ordered by model name
+        assert_eq!(
+            models,
+            vec![
+                "claude-opus-4-1-20250805",
+                "claude-3-5-sonnet-20241022",
+                "claude-sonnet-4-20250514",
+            ]
+        );
+        assert_eq!(by_model[0].1.value(), 3.0);
+    }
+
+    #[test]
+    fn test_today_entries_excludes_future_entries() {
+        let today_start = local_today_start();
+
+        let today_noon = today_start + Duration::hours(12);
+        let far_future = today_start + Duration::days(400);
+
+        let entries = vec![
+            create_test_entry(
+                "session-1",
+                &today_noon.to_rfc3339(),
+                Some("msg-1"),
+                Some("req-1"),
+                Some(100),
+                Some(50),
+            ),
+            create_test_entry(
+                "session-2",
+                &far_future.to_rfc3339(),
+                Some("msg-2"),
+                Some("req-2"),
+                Some(100),
+                Some(50),
+            ),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        let today_entries = snapshot.today_entries();
+        assert_eq!(today_entries.len(), 1);
+        assert_eq!(today_entries[0].session_id.as_str(), "session-1");
+
+        // Only future entries: nothing counts as today
+        let future_only = MergedUsageSnapshot::new(vec![create_test_entry(
+            "session-2",
+            &far_future.to_rfc3339(),
+            Some("msg-2"),
+            Some("req-2"),
+            Some(100),
+            Some(50),
+        )]);
+        assert!(future_only.today_entries().is_empty());
+        assert_eq!(future_only.message_count_today(), 0);
+    }
+
+    #[test]
+    fn test_cost_composition_today_sums_to_today_cost() {
+        let today_start = local_today_start();
+        let at = |hours| (today_start + Duration::hours(hours)).to_rfc3339();
+
+        let mut cached = (*create_test_entry(
+            "session-1",
+            &at(1),
+            Some("msg-1"),
+            Some("req-1"),
+            Some(100),
+            Some(50),
+        ))
+        .clone();
+        if let Some(usage) = cached.data.message.as_mut().and_then(|m| m.usage.as_mut()) {
+            usage.cache_creation_input_tokens = Some(2_000);
+            usage.cache_read_input_tokens = Some(10_000);
+        }
+        let mut precalculated = (*create_test_entry(
+            "session-1",
+            &at(2),
+            Some("msg-2"),
+            Some("req-2"),
+            Some(300),
+            Some(100),
+        ))
+        .clone();
+        precalculated.data.cost_usd = Some(0.42);
+
+        let snapshot = MergedUsageSnapshot::new(vec![
+            create_test_entry(
+                "session-0",
+                &(today_start - Duration::hours(3)).to_rfc3339(),
+                Some("msg-0"),
+                Some("req-0"),
+                Some(5_000),
+                Some(5_000),
+            ),
+            Arc::new(cached),
+            Arc::new(precalculated),
+        ]);
+
+        let composition = snapshot.cost_composition_today();
+        assert!(composition.cache_creation.value() > 0.0);
+        assert!(composition.cache_read.value() > 0.0);
+        assert!((composition.total().value() - snapshot.today_cost().value()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cost_if_model() {
+        let today_start = local_today_start();
+        let at = |minutes| (today_start + Duration::minutes(minutes)).to_rfc3339();
+        let with_model = |entry: Arc<UsageEntry>, model: ModelId| {
+            let mut entry = (*entry).clone();
+            entry.data.model = Some(model.clone());
+            if let Some(message) = entry.data.message.as_mut() {
+                message.model = Some(model);
+            }
+            Arc::new(entry)
+        };
+
+        let opus = ModelId::ClaudeOpus4_1_20250805;
+        let sonnet = ModelId::ClaudeSonnet4_20250514;
+        let mut no_tokens = (*create_test_entry("s", &at(3), None, None, None, None)).clone();
+        no_tokens.data.message = None;
+        no_tokens.data.cost_usd = Some(5.0);
+        let snapshot = MergedUsageSnapshot::new(vec![
+            with_model(
+                create_test_entry(
+                    "s",
+                    &at(1),
+                    Some("m1"),
+                    Some("r1"),
+                    Some(10_000),
+                    Some(5_000),
+                ),
+                opus.clone(),
+            ),
+            with_model(
+                create_test_entry("s", &at(2), Some("m2"), Some("r2"), Some(1_000), Some(500)),
+                sonnet.clone(),
+            ),
+            // Pre-calculated cost without tokens contributes nothing
+            Arc::new(no_tokens),
+        ]);
+
+        let on_opus = snapshot.cost_if_model(&opus);
+        let on_sonnet = snapshot.cost_if_model(&sonnet);
+        assert!(on_sonnet < on_opus);
+        assert!(on_sonnet.value() > 0.0);
+        // Opus is priced at five times Sonnet for every token kind
+        assert!((on_opus.value() - on_sonnet.value() * 5.0).abs() < 1e-9);
+        assert_eq!(
+            MergedUsageSnapshot::default().cost_if_model(&opus).value(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_session_cost_with_hook_model() {
+        let without_model = |entry: Arc<UsageEntry>| {
+            let mut entry = (*entry).clone();
+            entry.data.model = None;
+            if let Some(message) = entry.data.message.as_mut() {
+                message.model = None;
+            }
+            Arc::new(entry)
+        };
+        let at = "2024-01-15T10:00:00Z";
+        let snapshot = MergedUsageSnapshot::new(vec![
+            without_model(create_test_entry(
+                "s",
+                at,
+                Some("m1"),
+                Some("r1"),
+                Some(1_000),
+                Some(100),
+            )),
+            without_model(create_test_entry(
+                "other",
+                at,
+                Some("m2"),
+                Some("r2"),
+                Some(1_000),
+                Some(100),
+            )),
+        ]);
+        let session = SessionId::from("s");
+        let sonnet = ModelId::ClaudeSonnet4_20250514;
+
+        // Unpriceable without a model
+        assert_eq!(snapshot.session_cost(&session).value(), 0.0);
+        assert_eq!(
+            snapshot.session_cost_with_model(&session, None).value(),
+            0.0
+        );
+
+        // Priced as the hook's model, for the requested session only
+        let priced = snapshot.session_cost_with_model(&session, Some(&sonnet));
+        let expected = Cost::from_entry_as_model(&snapshot.all_entries[0], &sonnet);
+        assert!(priced.value() > 0.0);
+        assert!((priced.value() - expected.value()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_top_entries_drops_negligible() {
+        let with_cost = |timestamp: &str, cost: f64| {
+            let mut entry = (*create_test_entry("s", timestamp, None, None, None, None)).clone();
+            entry.data.cost_usd = Some(cost);
+            Arc::new(entry)
+        };
+        let snapshot = MergedUsageSnapshot::new(vec![
+            with_cost("2024-01-15T10:00:00Z", 0.001),
+            with_cost("2024-01-15T10:01:00Z", 0.5),
+            with_cost("2024-01-15T10:02:00Z", 0.004),
+            with_cost("2024-01-15T10:03:00Z", 2.0),
+        ]);
+
+        let costs = |top: Vec<(&UsageEntry, Cost)>| -> Vec<f64> {
+            top.into_iter().map(|(_, cost)| cost.value()).collect()
+        };
+        assert_eq!(costs(snapshot.top_entries(10, false)), vec![2.0, 0.5]);
+        assert_eq!(costs(snapshot.top_entries(1, false)), vec![2.0]);
+        assert_eq!(
+            costs(snapshot.top_entries(10, true)),
+            vec![2.0, 0.5, 0.004, 0.001]
+        );
+        // The sub-cent entries still count towards the total
+        let total = Cost::from_entries(snapshot.all_entries.iter().map(|e| e.as_ref()));
+        assert!((total.value() - 2.505).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_projects_by_cost() {
+        let in_project = |project: &str, cost: f64| {
+            let mut entry =
+                (*create_test_entry("s", "2024-01-15T10:00:00Z", None, None, None, None)).clone();
+            entry.data.cost_usd = Some(cost);
+            Arc::new(entry.with_project(Arc::from(project)))
+        };
+        let mut loose =
+            (*create_test_entry("s", "2024-01-15T10:00:00Z", None, None, None, None)).clone();
+        loose.data.cost_usd = Some(100.0);
+        let snapshot = MergedUsageSnapshot::new(vec![
+            in_project("-work-api", 1.0),
+            in_project("-Users-me-src-my-app", 2.0),
+            in_project("-work-web", 0.5),
+            in_project("-work-api", 3.0),
+            Arc::new(loose),
+        ]);
+
+        assert_eq!(
+            snapshot.projects_by_cost(),
+            vec![
+                ("-work-api".to_string(), Cost::new(4.0)),
+                ("-Users-me-src-my-app".to_string(), Cost::new(2.0)),
+                ("-work-web".to_string(), Cost::new(0.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_cost_today_spanning_two_days() {
+        let today_start = local_today_start();
+        let at = |minutes| (today_start + Duration::minutes(minutes)).to_rfc3339();
+
+        let yesterday = create_test_entry("s", &at(-60), Some("m1"), Some("r1"), Some(1_000), None);
+        let today = create_test_entry("s", &at(60), Some("m2"), Some("r2"), Some(300), None);
+        let other = create_test_entry("o", &at(90), Some("m3"), Some("r3"), Some(500), None);
+        let expected = Cost::from_entry(&today);
+        let snapshot = MergedUsageSnapshot::new(vec![yesterday, today, other]);
+
+        let session = SessionId::from("s");
+        assert_eq!(snapshot.session_cost_today(&session), expected);
+        assert!(snapshot.session_cost(&session) > expected);
+    }
+
+    #[test]
+    fn test_session_scope_from_str() {
+        assert_eq!("today".parse::<SessionScope>(), Ok(SessionScope::Today));
+        assert_eq!("ALL".parse::<SessionScope>(), Ok(SessionScope::All));
+        assert!("week".parse::<SessionScope>().is_err());
+        assert_eq!(SessionScope::default(), SessionScope::All);
+    }
+
+    #[test]
+    fn test_timestampless_entries_count_toward_session_only() {
+        let today_start = local_today_start();
+        let timed = create_test_entry(
+            "s",
+            &(today_start + Duration::hours(1)).to_rfc3339(),
+            Some("m1"),
+            Some("r1"),
+            None,
+            None,
+        );
+        let mut untimed = (*timed).clone();
+        untimed.data.timestamp = None;
+        untimed.data.cost_usd = Some(2.0);
+        let mut garbled = untimed.clone();
+        garbled.data.timestamp = Some("yesterday".to_string());
+        let snapshot = MergedUsageSnapshot::new(vec![Arc::new(untimed), timed, Arc::new(garbled)]);
+
+        assert_eq!(snapshot.timestampless_entries(), 2);
+        let session = SessionId::from("s");
+        assert_eq!(snapshot.session_cost(&session).value(), 4.0);
+        assert_eq!(snapshot.session_cost_today(&session).value(), 0.0);
+        assert_eq!(snapshot.today_cost().value(), 0.0);
+    }
+
+    #[test]
+    fn test_cost_of_sessions() {
+        let entries = vec![
+            create_test_entry(
+                "a",
+                "2024-01-15T10:00:00Z",
+                Some("m1"),
+                None,
+                Some(1_000),
+                None,
+            ),
+            create_test_entry(
+                "b",
+                "2024-01-15T10:01:00Z",
+                Some("m2"),
+                None,
+                Some(2_000),
+                None,
+            ),
+            create_test_entry(
+                "c",
+                "2024-01-15T10:02:00Z",
+                Some("m3"),
+                None,
+                Some(4_000),
+                None,
+            ),
+            create_test_entry(
+                "a",
+                "2024-01-15T10:03:00Z",
+                Some("m4"),
+                None,
+                Some(8_000),
+                None,
+            ),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+        let a = SessionId::from("a");
+        let c = SessionId::from("c");
+        let selected = [a.clone(), c.clone()];
+
+        let expected = snapshot.session_cost(&a).value() + snapshot.session_cost(&c).value();
+        let combined = snapshot.cost_of_sessions(&selected);
+        assert!((combined.value() - expected).abs() < 1e-12);
+        assert!(combined < Cost::from_entries(snapshot.all_entries.iter().map(|e| e.as_ref())));
+
+        let breakdown = snapshot.cost_by_session(&[a.clone(), c.clone(), SessionId::from("x")]);
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!(breakdown[0], (a.clone(), snapshot.session_cost(&a)));
+        assert_eq!(breakdown[1], (c.clone(), snapshot.session_cost(&c)));
+        assert_eq!(breakdown[2].1.value(), 0.0);
+        assert_eq!(snapshot.cost_of_sessions(&[]).value(), 0.0);
+    }
+
+    #[test]
+    fn test_retry_cost_today() {
+        let today_start = local_today_start();
+        let at = |minutes| (today_start + Duration::minutes(minutes)).to_rfc3339();
+
+        let entries = vec![
+            create_test_entry("s", &at(10), Some("msg-1"), Some("req-1"), Some(100), None),
+            // Same request, new message: a retry
+            create_test_entry("s", &at(11), Some("msg-2"), Some("req-1"), Some(300), None),
+            create_test_entry("s", &at(12), Some("msg-3"), Some("req-2"), Some(500), None),
+            // No request id: never a retry
+            create_test_entry("s", &at(13), Some("msg-4"), None, Some(700), None),
+        ];
+        let retry = Cost::from_entry(&entries[1]);
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        assert!(retry.value() > 0.0);
+        assert_eq!(snapshot.retry_cost_today(), retry);
+        assert_eq!(
+            MergedUsageSnapshot::default().retry_cost_today().value(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_project_cost_today() {
+        let today_start = local_today_start();
+        let yesterday = (today_start - Duration::hours(1)).to_rfc3339();
+        let today = (today_start + Duration::seconds(1)).to_rfc3339();
+
+        let entry = |session: &str, timestamp: &str, project: &str, cost: f64| {
+            let data = UsageEntryData {
+                timestamp: Some(timestamp.to_string()),
+                model: None,
+                cost_usd: Some(cost),
+                message: None,
+                request_id: None,
+            };
+            Arc::new(
+                UsageEntry::from_data(data, SessionId::from(session)).with_project(project.into()),
+            )
+        };
+        let entries = vec![
+            entry("s1", &yesterday, "-work-ccr", 8.0),
+            entry("s1", &today, "-work-ccr", 1.0),
+            entry("s2", &today, "-work-ccr", 2.0),
+            entry("s3", &today, "-work-other", 4.0),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        // Both sessions of the project count, yesterday and other projects do not
+        assert_eq!(snapshot.project_cost_today("-work-ccr").value(), 3.0);
+        assert_eq!(snapshot.project_cost_today("-work-other").value(), 4.0);
+        assert_eq!(snapshot.project_cost_today("-missing").value(), 0.0);
+        assert_eq!(snapshot.today_cost().value(), 7.0);
+    }
+
+    #[test]
+    fn test_project_key_from_path() {
+        assert_eq!(
+            project_key_from_path("/Users/someone/src/mydev/ccr"),
+            "-Users-someone-src-mydev-ccr"
+        );
+        assert_eq!(project_key_from_path("/home/me/my.app"), "-home-me-my-app");
+    }
+
+    #[test]
+    fn test_timestamp_offsets() {
+        let entries = vec![
+            create_test_entry("s", "2024-01-15T01:00:00.000Z", None, None, None, None),
+            create_test_entry("s", "2024-01-15T10:00:00+09:00", None, None, None, None),
+            create_test_entry("s", "2024-01-15T10:30:00+09:00", None, None, None, None),
+            create_test_entry("s", "2024-01-15T05:00:00-05:00", None, None, None, None),
+            create_test_entry("s", "not a timestamp", None, None, None, None),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        let offsets: Vec<String> = snapshot.timestamp_offsets(100).into_iter().collect();
+        assert_eq!(offsets, vec!["+09:00", "-05:00", "Z"]);
+
+        // Sampling every other entry still sees a subset of the offsets
+        assert!(snapshot.timestamp_offsets(2).len() <= offsets.len());
+        assert!(
+            MergedUsageSnapshot::new(vec![])
+                .timestamp_offsets(10)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_loaded_range() {
+        let entries = vec![
+            create_test_entry("s", "2024-01-10T08:00:00.000Z", None, None, None, None),
+            create_test_entry("s", "2024-01-12T12:00:00.000Z", None, None, None, None),
+            create_test_entry("s", "2024-01-15T18:30:00.000Z", None, None, None, None),
+            create_test_entry("s", "not a timestamp", None, None, None, None),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        let (first, last) = snapshot.loaded_range().unwrap();
+        assert_eq!(first, Utc.with_ymd_and_hms(2024, 1, 10, 8, 0, 0).unwrap());
+        assert_eq!(last, Utc.with_ymd_and_hms(2024, 1, 15, 18, 30, 0).unwrap());
+
+        assert!(MergedUsageSnapshot::new(vec![]).loaded_range().is_none());
+    }
+
+    #[test]
+    fn test_session_block_is_ending_soon() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let end = start + SESSION_BLOCK_DURATION;
+        let active = SessionBlock::Active {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![],
+        };
+
+        assert!(active.is_ending_soon_at(10, end - Duration::minutes(9)));
+        assert!(!active.is_ending_soon_at(10, end - Duration::minutes(11)));
+        // Already over
+        assert!(!active.is_ending_soon_at(10, end));
+
+        let completed = SessionBlock::Completed {
+            start_time: start,
+            duration: SESSION_BLOCK_DURATION,
+            entries: vec![],
+        };
+        assert!(!completed.is_ending_soon_at(10, end - Duration::minutes(9)));
+        let idle = SessionBlock::idle(start, end);
+        assert!(!idle.is_ending_soon_at(10, end - Duration::minutes(9)));
+    }
+
+    #[test]
+    fn test_session_block_dominant_model() {
+        let entry_with_cost = |model: &str, cost: f64| {
+            Arc::new(UsageEntry {
+                data: UsageEntryData {
+                    timestamp: Some("2024-01-15T10:00:00.000Z".to_string()),
+                    model: Some(ModelId::from(model)),
+                    cost_usd: Some(cost),
+                    message: None,
+                    request_id: None,
+                },
+                session_id: SessionId::from("session-1"),
+                project: None,
+                source: None,
+            })
+        };
+        let block = |entries| SessionBlock::Completed {
+            start_time: Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap(),
+            duration: SESSION_BLOCK_DURATION,
+            entries,
+        };
+
+        let opus_heavy = block(vec![
+            entry_with_cost("claude-opus-4-1-20250805", 3.0),
+            entry_with_cost("claude-sonnet-4-20250514", 1.0),
+        ]);
+        assert_eq!(
+            opus_heavy.dominant_model(),
+            Some(ModelId::ClaudeOpus4_1_20250805)
+        );
+
+        // An even split has no dominant model
+        let mixed = block(vec![
+            entry_with_cost("claude-opus-4-1-20250805", 1.0),
+            entry_with_cost("claude-sonnet-4-20250514", 1.0),
+        ]);
+        assert_eq!(mixed.dominant_model(), None);
+
+        let idle = SessionBlock::idle(Utc::now(), Utc::now());
+        assert_eq!(idle.dominant_model(), None);
+    }
+
     #[test]
     fn test_session_blocks_with_exact_5_hour_gap() {
         let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
@@ -833,9 +2198,7 @@ mod tests {
             ),
         ];
 
-        let snapshot = MergedUsageSnapshot {
-            all_entries: entries,
-        };
+        let snapshot = MergedUsageSnapshot::new(entries);
 
         let blocks = snapshot.session_blocks();
 
@@ -843,4 +2206,178 @@ mod tests {
         // This test documents the actual behavior
         assert!(!blocks.is_empty());
     }
+
+    #[test]
+    fn test_identify_session_blocks_parallel_matches_sequential() {
+        let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+        // Offsets in minutes: dense activity, a block that runs past 5 hours,
+        // idle gaps longer than a block, an exact 5-hour gap, and recent entries
+        let offsets = [
+            0, 20, 90, 280, 310, 330, 800, 805, 1105, 1500, 1510, 3000, 3001,
+        ];
+        let mut entries: Vec<_> = offsets
+            .iter()
+            .enumerate()
+            .map(|(i, minutes)| {
+                create_test_entry(
+                    "session-1",
+                    &(base_time + Duration::minutes(*minutes)).to_rfc3339(),
+                    Some(&format!("msg-{}", i)),
+                    Some(&format!("req-{}", i)),
+                    Some(100),
+                    Some(50),
+                )
+            })
+            .collect();
+        // Duplicate and unparseable entries are dropped the same way
+        entries.insert(3, Arc::clone(&entries[2]));
+        entries.insert(
+            6,
+            create_test_entry("s", "not a timestamp", None, None, Some(1), None),
+        );
+        let now = Utc::now();
+        entries.push(create_test_entry(
+            "session-2",
+            &(now - Duration::minutes(10)).to_rfc3339(),
+            Some("msg-recent"),
+            Some("req-recent"),
+            Some(100),
+            Some(50),
+        ));
+
+        let sequential = identify_session_blocks(&entries);
+        let parallel = identify_session_blocks_parallel(&entries);
+        assert!(sequential.iter().filter(|b| b.is_idle()).count() >= 3);
+        assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+
+        // Also equivalent with a fixed clock straight through the builders
+        let parsed = preprocess_entries(&entries, true);
+        assert_eq!(
+            format!(
+                "{:?}",
+                build_session_blocks_parallel(&parsed, now, SESSION_BLOCK_DURATION)
+            ),
+            format!(
+                "{:?}",
+                build_session_blocks(&parsed, now, SESSION_BLOCK_DURATION)
+            )
+        );
+        assert!(identify_session_blocks_parallel(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_large_snapshot_blocks_match_sequential() {
+        let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 30, 0).unwrap();
+        // Bursts of activity separated by idle gaps, enough to take the parallel path
+        let entries: Vec<_> = (0..PARALLEL_BLOCKS_MIN_ENTRIES + 500)
+            .map(|i| {
+                let minutes = (i / 100) as i64 * 600 + (i % 100) as i64;
+                create_test_entry(
+                    "session-1",
+                    &(base_time + Duration::minutes(minutes)).to_rfc3339(),
+                    Some(&format!("msg-{}", i)),
+                    Some(&format!("req-{}", i)),
+                    Some(100),
+                    Some(50),
+                )
+            })
+            .collect();
+
+        let sequential = identify_session_blocks(&entries);
+        let snapshot = MergedUsageSnapshot::new(entries);
+        assert!(sequential.iter().filter(|b| b.is_idle()).count() > 50);
+        assert_eq!(
+            format!("{:?}", snapshot.session_blocks()),
+            format!("{:?}", sequential)
+        );
+    }
+
+    #[test]
+    fn test_identify_session_blocks_with_shorter_duration() {
+        let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let now = base_time + Duration::days(1);
+        let entries: Vec<_> = [0, 240]
+            .iter()
+            .map(|minutes| {
+                create_test_entry(
+                    "session-1",
+                    &(base_time + Duration::minutes(*minutes)).to_rfc3339(),
+                    Some(&format!("msg-{}", minutes)),
+                    Some(&format!("req-{}", minutes)),
+                    Some(100),
+                    Some(50),
+                )
+            })
+            .collect();
+
+        // A 4-hour gap stays inside one 5-hour block
+        let default_blocks = identify_session_blocks_with(&entries, SESSION_BLOCK_DURATION, now);
+        assert_eq!(default_blocks.len(), 1);
+
+        // but splits a 3-hour block, with an idle period after the first window
+        let blocks = identify_session_blocks_with(&entries, Duration::hours(3), now);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].end_time(), base_time + Duration::hours(3));
+        assert!(blocks[1].is_idle());
+        assert_eq!(blocks[1].start_time(), base_time + Duration::hours(3));
+        assert_eq!(blocks[1].end_time(), base_time + Duration::hours(4));
+        assert_eq!(blocks[2].end_time(), base_time + Duration::hours(7));
+
+        let parsed = preprocess_entries(&entries, true);
+        assert_eq!(
+            format!(
+                "{:?}",
+                build_session_blocks_parallel(&parsed, now, Duration::hours(3))
+            ),
+            format!("{:?}", blocks)
+        );
+    }
+
+    #[test]
+    fn test_blocks_idle_suppression() {
+        let base_time = Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap();
+        let entries = vec![
+            create_test_entry(
+                "session-1",
+                &base_time.to_rfc3339(),
+                Some("msg-1"),
+                Some("req-1"),
+                Some(100),
+                Some(50),
+            ),
+            // Ten hours later: a new block after an idle gap
+            create_test_entry(
+                "session-1",
+                &(base_time + Duration::hours(10)).to_rfc3339(),
+                Some("msg-2"),
+                Some("req-2"),
+                Some(200),
+                Some(100),
+            ),
+        ];
+        let snapshot = MergedUsageSnapshot::new(entries);
+
+        // Including idle: block, idle gap, block
+        let all = snapshot.blocks_with_costs(true);
+        assert_eq!(all.len(), 3);
+        assert!(all[1].0.is_idle());
+        assert_eq!(all[1].1.value(), 0.0);
+
+        // Excluding idle: only the blocks with activity
+        let active = snapshot.blocks_with_costs(false);
+        assert_eq!(active.len(), 2);
+        assert!(
+            active
+                .iter()
+                .all(|(block, cost)| !block.is_idle() && cost.value() > 0.0)
+        );
+
+        // Recent blocks count only the kept blocks
+        let recent = snapshot.recent_blocks(2, false);
+        assert_eq!(recent.len(), 2);
+        assert!(recent.iter().all(|block| !block.is_idle()));
+        let recent = snapshot.recent_blocks(2, true);
+        assert!(recent[0].is_idle());
+        assert_eq!(snapshot.recent_blocks(10, true).len(), 3);
+    }
 }
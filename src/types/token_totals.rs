@@ -0,0 +1,142 @@
+use super::usage::UsageEntry;
+
+/// Aggregated token counts across a set of usage entries
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TokenTotals {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Extended-thinking tokens reported separately from output
+    pub reasoning_output_tokens: u64,
+    /// 5-minute cache writes (old-format entries count here)
+    pub cache_creation_5m_tokens: u64,
+    /// 1-hour cache writes
+    pub cache_creation_1h_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl TokenTotals {
+    /// Sum token counts from an iterator of UsageEntry references
+    pub fn from_entries<'a, I>(entries: I) -> Self
+    where
+        I: Iterator<Item = &'a UsageEntry>,
+    {
+        let mut totals = TokenTotals::default();
+        for entry in entries {
+            totals.add_entry(entry);
+        }
+        totals
+    }
+
+    /// Add a single entry's token counts
+    /// Entries without usage data contribute nothing
+    pub fn add_entry(&mut self, entry: &UsageEntry) {
+        let Some(usage) = entry.data.message.as_ref().and_then(|m| m.usage.as_ref()) else {
+            return;
+        };
+
+        self.input_tokens += usage.input_tokens.unwrap_or(0) as u64;
+        self.output_tokens += usage.output_tokens.unwrap_or(0) as u64;
+        self.reasoning_output_tokens += usage.reasoning_output_tokens.unwrap_or(0) as u64;
+        self.cache_read_tokens += usage.cache_read_input_tokens.unwrap_or(0) as u64;
+
+        // New format splits cache creation into 5m/1h, old format has a single
+        // field which was always the 5-minute cache
+        if let Some(cache_creation) = &usage.cache_creation {
+            self.cache_creation_5m_tokens +=
+                cache_creation.ephemeral_5m_input_tokens.unwrap_or(0) as u64;
+            self.cache_creation_1h_tokens +=
+                cache_creation.ephemeral_1h_input_tokens.unwrap_or(0) as u64;
+        } else {
+            self.cache_creation_5m_tokens += usage.cache_creation_input_tokens.unwrap_or(0) as u64;
+        }
+    }
+
+    /// Cache writes of both durations
+    pub fn cache_creation_tokens(&self) -> u64 {
+        self.cache_creation_5m_tokens + self.cache_creation_1h_tokens
+    }
+
+    /// Share of input tokens served from the cache, 0.0 to 1.0
+    /// Input counts fresh input, cache writes and cache reads; None when there
+    /// was no input at all
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let input = self.input_tokens + self.cache_creation_tokens() + self.cache_read_tokens;
+        (input > 0).then(|| self.cache_read_tokens as f64 / input as f64)
+    }
+
+    /// Total of all token types
+    pub fn total(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.reasoning_output_tokens
+            + self.cache_creation_tokens()
+            + self.cache_read_tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, SessionId, Usage, UsageEntryData, usage::CacheCreation};
+
+    fn create_entry(usage: Option<Usage>) -> UsageEntry {
+        UsageEntry::from_data(
+            UsageEntryData {
+                timestamp: Some("2024-01-15T10:00:00.000Z".to_string()),
+                model: None,
+                cost_usd: None,
+                message: Some(Message {
+                    id: None,
+                    model: None,
+                    usage,
+                }),
+                request_id: None,
+            },
+            SessionId::from("test-session"),
+        )
+    }
+
+    #[test]
+    fn test_token_totals_mixed_formats() {
+        let entries = [
+            // Old format with flat cache creation field
+            create_entry(Some(Usage {
+                input_tokens: Some(100),
+                output_tokens: Some(50),
+                cache_creation_input_tokens: Some(20),
+                cache_read_input_tokens: Some(300),
+                cache_creation: None,
+                service_tier: None,
+                reasoning_output_tokens: None,
+            })),
+            // New format with split cache creation
+            create_entry(Some(Usage {
+                input_tokens: Some(10),
+                output_tokens: Some(5),
+                cache_creation_input_tokens: Some(999), // ignored when split is present
+                cache_read_input_tokens: None,
+                cache_creation: Some(CacheCreation {
+                    ephemeral_5m_input_tokens: Some(7),
+                    ephemeral_1h_input_tokens: Some(3),
+                }),
+                service_tier: None,
+                reasoning_output_tokens: Some(40),
+            })),
+            // No usage at all
+            create_entry(None),
+        ];
+
+        let totals = TokenTotals::from_entries(entries.iter());
+        assert_eq!(totals.input_tokens, 110);
+        assert_eq!(totals.output_tokens, 55);
+        assert_eq!(totals.reasoning_output_tokens, 40);
+        assert_eq!(totals.cache_creation_5m_tokens, 27);
+        assert_eq!(totals.cache_creation_1h_tokens, 3);
+        assert_eq!(totals.cache_creation_tokens(), 30);
+        assert_eq!(totals.cache_read_tokens, 300);
+        assert_eq!(totals.total(), 535);
+        // 300 cache reads out of 110 + 30 + 300 input tokens
+        assert_eq!(totals.cache_hit_ratio(), Some(300.0 / 440.0));
+        assert_eq!(TokenTotals::default().cache_hit_ratio(), None);
+    }
+}
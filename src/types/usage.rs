@@ -1,40 +1,246 @@
 use super::ids::{MessageId, ModelId, RequestId, SessionId};
-use serde::Deserialize;
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
 
 // Pure data structure deserialized from JSON
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UsageEntryData {
     pub timestamp: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_model")]
     pub model: Option<ModelId>,
-    #[serde(rename = "costUSD")]
+    #[serde(rename = "costUSD", default, deserialize_with = "deserialize_cost_usd")]
     pub cost_usd: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_message")]
     pub message: Option<Message>,
     #[serde(rename = "requestId")]
     pub request_id: Option<RequestId>,
 }
 
+/// Accept costUSD as either a number or a numeric string
+/// Some log producers emit `"costUSD": "0.123"`; a string that is not a number is treated as absent
+fn deserialize_cost_usd<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f64),
+        String(String),
+    }
+
+    Ok(match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(value)) => Some(value),
+        Some(NumberOrString::String(value)) => value.trim().parse().ok(),
+        None => None,
+    })
+}
+
+/// Accept model as a bare id string or an object like the hook's
+/// `{"id": "...", "display_name": "..."}` (an `id` key wins over `model`)
+/// Any other shape is treated as absent instead of dropping the whole entry
+fn deserialize_model<'de, D>(deserializer: D) -> Result<Option<ModelId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ModelField {
+        Id(ModelId),
+        Object {
+            #[serde(default)]
+            id: Option<ModelId>,
+            #[serde(default)]
+            model: Option<ModelId>,
+        },
+        Other(IgnoredAny),
+    }
+
+    Ok(match Option::<ModelField>::deserialize(deserializer)? {
+        Some(ModelField::Id(model)) => Some(model),
+        Some(ModelField::Object { id, model }) => id.or(model),
+        Some(ModelField::Other(_)) | None => None,
+    })
+}
+
+/// Accept `message` as an object (the usual shape) or an array of blocks
+/// For an array, the first element carrying usage wins, then the first object.
+/// Any other shape is treated as absent instead of dropping the whole entry.
+fn deserialize_message<'de, D>(deserializer: D) -> Result<Option<Message>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(MessageVisitor)
+}
+
+/// Visits a message without buffering the line, keeping the hot parse path cheap
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = Option<Message>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a message object or an array of message blocks")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Message::deserialize(de::value::MapAccessDeserializer::new(map)).map(Some)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut found: Option<Message> = None;
+        while let Some(MessageElement(element)) = seq.next_element()? {
+            let Some(message) = element else {
+                continue;
+            };
+            let replaces = match &found {
+                None => true,
+                Some(current) => current.usage.is_none() && message.usage.is_some(),
+            };
+            if replaces {
+                found = Some(message);
+            }
+        }
+        Ok(found)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+}
+
+/// One element of an array-shaped message; non-object elements are skipped
+struct MessageElement(Option<Message>);
+
+impl<'de> Deserialize<'de> for MessageElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ElementVisitor;
+
+        impl<'de> Visitor<'de> for ElementVisitor {
+            type Value = MessageElement;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a message block")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                Message::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(|message| MessageElement(Some(message)))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(MessageElement(None))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+
+            fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+
+            fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+
+            fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+
+            fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+
+            fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> {
+                Ok(MessageElement(None))
+            }
+        }
+
+        deserializer.deserialize_any(ElementVisitor)
+    }
+}
+
 // Complete usage entry with session context
 #[derive(Debug, Clone)]
 pub struct UsageEntry {
     pub data: UsageEntryData,
     pub session_id: SessionId,
+    /// Project key (the `projects/<dir>` name) the entry was loaded from
+    pub project: Option<Arc<str>>,
+    /// Claude data root (e.g. `~/.claude`) whose projects directory held the entry
+    pub source: Option<Arc<Path>>,
 }
 
 impl UsageEntry {
     pub fn from_data(data: UsageEntryData, session_id: SessionId) -> Self {
-        Self { data, session_id }
+        Self {
+            data,
+            session_id,
+            project: None,
+            source: None,
+        }
+    }
+
+    /// Attach the project the entry was loaded from
+    pub fn with_project(mut self, project: Arc<str>) -> Self {
+        self.project = Some(project);
+        self
+    }
+
+    /// Attach the Claude data root the entry was loaded from
+    pub fn with_source(mut self, source: Arc<Path>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Model used for this entry, preferring the message-level model
+    pub fn model(&self) -> Option<&ModelId> {
+        self.data
+            .message
+            .as_ref()
+            .and_then(|message| message.model.as_ref())
+            .or(self.data.model.as_ref())
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Message {
     pub id: Option<MessageId>,
     pub model: Option<ModelId>,
     pub usage: Option<Usage>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Usage {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
@@ -42,10 +248,77 @@ pub struct Usage {
     pub cache_read_input_tokens: Option<u32>,
     pub cache_creation: Option<CacheCreation>,
     pub service_tier: Option<String>,
+    /// Extended-thinking tokens reported separately from output_tokens,
+    /// billed at the output rate
+    #[serde(default)]
+    pub reasoning_output_tokens: Option<u32>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CacheCreation {
     pub ephemeral_5m_input_tokens: Option<u32>,
     pub ephemeral_1h_input_tokens: Option<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> UsageEntryData {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn test_cost_usd_number_or_string() {
+        assert_eq!(parse(r#"{"costUSD":0.123}"#).cost_usd, Some(0.123));
+        assert_eq!(parse(r#"{"costUSD":"0.123"}"#).cost_usd, Some(0.123));
+        assert_eq!(parse(r#"{"costUSD":null}"#).cost_usd, None);
+        assert_eq!(parse(r#"{"costUSD":"n/a"}"#).cost_usd, None);
+        assert_eq!(parse(r#"{}"#).cost_usd, None);
+    }
+
+    #[test]
+    fn test_message_array_shape() {
+        let data = parse(
+            r#"{"costUSD":0.25,"message":[{"type":"text","text":"hi"},"stray",{"id":"m1","usage":{"input_tokens":10}}]}"#,
+        );
+        assert_eq!(data.cost_usd, Some(0.25));
+        let message = data.message.unwrap();
+        assert_eq!(message.id.unwrap().as_str(), "m1");
+        assert_eq!(message.usage.unwrap().input_tokens, Some(10));
+
+        // Unexpected shapes keep the entry and drop only the message
+        let data = parse(r#"{"costUSD":0.5,"message":"text"}"#);
+        assert_eq!(data.cost_usd, Some(0.5));
+        assert!(data.message.is_none());
+        assert!(parse(r#"{"message":[]}"#).message.is_none());
+        assert!(parse(r#"{"message":null}"#).message.is_none());
+
+        // The object form is unchanged
+        let data = parse(r#"{"message":{"id":"m2"}}"#);
+        assert_eq!(data.message.unwrap().id.unwrap().as_str(), "m2");
+    }
+
+    #[test]
+    fn test_model_string_or_object() {
+        assert_eq!(
+            parse(r#"{"model":"claude-opus-4-1-20250805"}"#).model,
+            Some(ModelId::ClaudeOpus4_1_20250805)
+        );
+        assert_eq!(
+            parse(r#"{"model":{"id":"claude-sonnet-4-20250514","display_name":"Sonnet 4"}}"#).model,
+            Some(ModelId::ClaudeSonnet4_20250514)
+        );
+        assert_eq!(
+            parse(r#"{"model":{"model":"claude-3-opus-20240229"}}"#).model,
+            Some(ModelId::Claude3Opus20240229)
+        );
+
+        // Unknown shapes keep the entry and drop only the model
+        let data = parse(r#"{"costUSD":0.5,"model":{"display_name":"Sonnet 4"}}"#);
+        assert_eq!(data.cost_usd, Some(0.5));
+        assert!(data.model.is_none());
+        assert!(parse(r#"{"model":42}"#).model.is_none());
+        assert!(parse(r#"{"model":null}"#).model.is_none());
+    }
+}
@@ -0,0 +1,443 @@
+use crate::config;
+use crate::error::{CcrError, Result};
+use crate::types::{SessionId, UsageEntry, UsageEntryData};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// First two bytes of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Cache files not written for this long are removed when the directory is
+/// swept; a session file that is still around is simply parsed again
+const FILE_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Minimum time between two sweeps of the cache directory
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Marker file whose modification time records the last sweep
+const PRUNE_MARKER: &str = ".pruned";
+
+/// On-disk encoding of a cached snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheFormat {
+    /// Plain JSON
+    #[default]
+    Json,
+    /// Gzip-compressed JSON, for users with huge histories
+    Gzip,
+}
+
+impl CacheFormat {
+    /// Gzip when CLAUDE_CCR_CACHE_COMPRESS is enabled, plain JSON otherwise
+    pub fn from_env() -> Self {
+        if config::env_flag("CLAUDE_CCR_CACHE_COMPRESS") {
+            CacheFormat::Gzip
+        } else {
+            CacheFormat::Json
+        }
+    }
+
+    /// File extension for this format, so both can live side by side
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CacheFormat::Json => "json",
+            CacheFormat::Gzip => "json.gz",
+        }
+    }
+
+    /// Cache file path for a base name in a directory, e.g. `dir/usage.json.gz`
+    pub fn path_in(&self, dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{}.{}", name, self.extension()))
+    }
+
+    /// Detect the format from the leading bytes rather than trusting the name
+    fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            CacheFormat::Gzip
+        } else {
+            CacheFormat::Json
+        }
+    }
+}
+
+/// One cached entry; UsageEntry shares its strings through Arc, which serde
+/// does not serialize directly
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedEntry {
+    data: UsageEntryData,
+    session_id: SessionId,
+    #[serde(default)]
+    project: Option<String>,
+}
+
+impl CachedEntry {
+    fn from_entry(entry: &UsageEntry) -> Self {
+        Self {
+            data: entry.data.clone(),
+            session_id: entry.session_id.clone(),
+            project: entry.project.as_deref().map(str::to_string),
+        }
+    }
+
+    fn into_entry(self) -> UsageEntry {
+        let entry = UsageEntry::from_data(self.data, self.session_id);
+        match self.project {
+            Some(project) => entry.with_project(Arc::from(project)),
+            None => entry,
+        }
+    }
+}
+
+/// Identity of a JSONL file's contents: logs are append-only, so an unchanged
+/// size and modification time mean unchanged entries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileKey {
+    pub path: PathBuf,
+    pub len: u64,
+    pub modified_secs: u64,
+    pub modified_nanos: u32,
+}
+
+impl FileKey {
+    /// Read the key from the file's metadata, None when it is unavailable
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            len: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+
+    /// Cache file for this JSONL file in a directory; the name only depends
+    /// on the path, so a changed file overwrites its stale entry
+    /// The name is a 64-bit FNV-1a hash, which unlike std's hashers is stable
+    /// across Rust releases, so an upgrade keeps the cache warm
+    pub fn cache_path_in(&self, dir: &Path, format: CacheFormat) -> PathBuf {
+        let hash = fnv1a(self.path.as_os_str().as_encoded_bytes());
+        format.path_in(dir, &format!("{:016x}", hash))
+    }
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Parsed entries of one JSONL file with the key they were parsed under
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFile {
+    key: FileKey,
+    entries: Vec<CachedEntry>,
+    /// Malformed lines skipped while parsing, replayed on a cache hit
+    #[serde(default)]
+    skipped_lines: usize,
+}
+
+/// Write the parsed entries of one JSONL file and its count of malformed
+/// lines to its cache file
+pub fn save_file_cache(
+    dir: &Path,
+    key: &FileKey,
+    entries: &[UsageEntry],
+    skipped_lines: usize,
+    format: CacheFormat,
+) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|source| CcrError::DirectoryAccess {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let cached = CachedFile {
+        key: key.clone(),
+        entries: entries.iter().map(CachedEntry::from_entry).collect(),
+        skipped_lines,
+    };
+    write_json(&key.cache_path_in(dir, format), &cached, format)
+}
+
+/// Read the cached entries of one JSONL file and its count of malformed lines
+/// None when there is no readable cache file or it was written for another
+/// size or modification time
+pub fn load_file_cache(
+    dir: &Path,
+    key: &FileKey,
+    format: CacheFormat,
+) -> Option<(Vec<UsageEntry>, usize)> {
+    let cached: CachedFile = read_json(&key.cache_path_in(dir, format)).ok()?;
+    (cached.key == *key).then(|| {
+        let entries = cached
+            .entries
+            .into_iter()
+            .map(CachedEntry::into_entry)
+            .collect();
+        (entries, cached.skipped_lines)
+    })
+}
+
+/// Remove cache files older than a month, sweeping the directory at most once
+/// a day; returns how many files were removed
+/// Without this, the caches of deleted session files would pile up forever.
+pub fn prune_file_cache(dir: &Path, now: SystemTime) -> usize {
+    let is_older_than = |path: &Path, max_age: Duration| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|age| age >= max_age))
+    };
+
+    let marker = dir.join(PRUNE_MARKER);
+    if marker.exists() && !is_older_than(&marker, PRUNE_INTERVAL) {
+        return 0;
+    }
+    // A missing directory has nothing to prune
+    let Ok(marker_file) = fs::File::create(&marker) else {
+        return 0;
+    };
+    let _ = marker_file.set_modified(now);
+
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return 0;
+    };
+    read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    [CacheFormat::Json, CacheFormat::Gzip]
+                        .iter()
+                        .any(|format| name.ends_with(&format!(".{}", format.extension())))
+                })
+        })
+        .filter(|path| is_older_than(path, FILE_CACHE_MAX_AGE))
+        .filter(|path| fs::remove_file(path).is_ok())
+        .count()
+}
+
+/// Serialize a value as JSON and write it in the given format
+fn write_json<T: Serialize>(path: &Path, value: &T, format: CacheFormat) -> Result<()> {
+    let json = serde_json::to_vec(value).map_err(|source| CcrError::JsonSerialize {
+        context: format!("cache {}", path.display()),
+        source,
+    })?;
+
+    let bytes = match format {
+        CacheFormat::Json => json,
+        CacheFormat::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(&json)
+                .and_then(|_| encoder.finish())
+                .map_err(|source| CcrError::FileWrite {
+                    path: path.to_path_buf(),
+                    source,
+                })?
+        }
+    };
+
+    fs::write(path, bytes).map_err(|source| CcrError::FileWrite {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Read a file written by write_json in either format
+/// The format is detected from the gzip magic bytes, so a stale file in the
+/// other format is never misread
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let read_error = |source| CcrError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    };
+    let bytes = fs::read(path).map_err(read_error)?;
+
+    let json = match CacheFormat::detect(&bytes) {
+        CacheFormat::Json => bytes,
+        CacheFormat::Gzip => {
+            let mut json = Vec::new();
+            GzDecoder::new(bytes.as_slice())
+                .read_to_end(&mut json)
+                .map_err(read_error)?;
+            json
+        }
+    };
+
+    serde_json::from_slice(&json).map_err(|source| CcrError::JsonParse {
+        context: format!("cache {}", path.display()),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MergedUsageSnapshot;
+
+    fn sample_entries() -> Vec<Arc<UsageEntry>> {
+        let lines = [
+            r#"{"timestamp":"2024-01-15T10:00:00.000Z","costUSD":0.5,"requestId":"req-1","message":{"id":"msg-1","model":"claude-opus-4-1-20250805","usage":{"input_tokens":10,"output_tokens":20}}}"#,
+            r#"{"timestamp":"2024-01-15T11:00:00.000Z","requestId":"req-2","message":{"id":"msg-2","model":"claude-sonnet-4-20250514","usage":{"input_tokens":100,"cache_creation":{"ephemeral_5m_input_tokens":5,"ephemeral_1h_input_tokens":7}}}}"#,
+        ];
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let data: UsageEntryData = serde_json::from_str(line).unwrap();
+                let entry = UsageEntry::from_data(data, format!("session-{}", i).into());
+                Arc::new(if i == 0 {
+                    entry.with_project(Arc::from("-work-ccr"))
+                } else {
+                    entry
+                })
+            })
+            .collect()
+    }
+
+    fn owned_entries() -> Vec<UsageEntry> {
+        sample_entries()
+            .iter()
+            .map(|entry| UsageEntry::clone(entry))
+            .collect()
+    }
+
+    fn snapshot_of(entries: Vec<UsageEntry>) -> MergedUsageSnapshot {
+        MergedUsageSnapshot::new(entries.into_iter().map(Arc::new).collect())
+    }
+
+    #[test]
+    fn test_cache_round_trip_both_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("session.jsonl");
+        fs::write(&jsonl, "{}").unwrap();
+        let key = FileKey::for_path(&jsonl).unwrap();
+        let entries = owned_entries();
+        let expected = format!("{:?}", snapshot_of(entries.clone()));
+
+        for format in [CacheFormat::Json, CacheFormat::Gzip] {
+            save_file_cache(dir.path(), &key, &entries, 3, format).unwrap();
+            let (loaded, skipped_lines) = load_file_cache(dir.path(), &key, format).unwrap();
+            assert_eq!(format!("{:?}", snapshot_of(loaded)), expected);
+            assert_eq!(skipped_lines, 3);
+        }
+
+        // The compressed file is distinguishable by its magic bytes
+        let gzip = fs::read(key.cache_path_in(dir.path(), CacheFormat::Gzip)).unwrap();
+        assert!(gzip.starts_with(&GZIP_MAGIC));
+        let json = fs::read(key.cache_path_in(dir.path(), CacheFormat::Json)).unwrap();
+        assert_eq!(CacheFormat::detect(&json), CacheFormat::Json);
+    }
+
+    #[test]
+    fn test_file_cache_requires_matching_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("session.jsonl");
+        fs::write(&jsonl, "{}").unwrap();
+        let key = FileKey::for_path(&jsonl).unwrap();
+        assert_eq!(key.len, 2);
+        let entries = owned_entries();
+
+        let cache_dir = dir.path().join("cache");
+        for format in [CacheFormat::Json, CacheFormat::Gzip] {
+            assert!(load_file_cache(&cache_dir, &key, format).is_none());
+            save_file_cache(&cache_dir, &key, &entries, 0, format).unwrap();
+            let (loaded, _) = load_file_cache(&cache_dir, &key, format).unwrap();
+            assert_eq!(format!("{:?}", loaded), format!("{:?}", entries));
+        }
+
+        // An appended file no longer matches its cached key
+        let grown = FileKey {
+            len: 3,
+            ..key.clone()
+        };
+        assert_eq!(
+            grown.cache_path_in(&cache_dir, CacheFormat::Json),
+            key.cache_path_in(&cache_dir, CacheFormat::Json)
+        );
+        assert!(load_file_cache(&cache_dir, &grown, CacheFormat::Json).is_none());
+    }
+
+    #[test]
+    fn test_cache_file_names_are_stable() {
+        // Reference values of 64-bit FNV-1a
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        let key = FileKey {
+            path: PathBuf::from("a"),
+            len: 0,
+            modified_secs: 0,
+            modified_nanos: 0,
+        };
+        assert_eq!(
+            key.cache_path_in(Path::new("cache"), CacheFormat::Gzip),
+            PathBuf::from("cache/af63dc4c8601ec8c.json.gz")
+        );
+    }
+
+    #[test]
+    fn test_prune_file_cache_removes_old_files_daily() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let write = |name: &str, age_days: u64| {
+            let path = dir.path().join(name);
+            fs::File::create(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age_days * 24 * 60 * 60))
+                .unwrap();
+            path
+        };
+        let stale = write("0000000000000001.json", 40);
+        let stale_gzip = write("0000000000000002.json.gz", 31);
+        let fresh = write("0000000000000003.json", 2);
+        let foreign = write("notes.txt", 400);
+
+        assert_eq!(prune_file_cache(dir.path(), now), 2);
+        assert!(!stale.exists() && !stale_gzip.exists());
+        assert!(fresh.exists() && foreign.exists());
+
+        // Swept at most once a day
+        let stale = write("0000000000000004.json", 40);
+        assert_eq!(prune_file_cache(dir.path(), now), 0);
+        assert!(stale.exists());
+        let tomorrow = now + PRUNE_INTERVAL;
+        assert_eq!(prune_file_cache(dir.path(), tomorrow), 1);
+
+        assert_eq!(prune_file_cache(&dir.path().join("missing"), now), 0);
+    }
+
+    #[test]
+    fn test_cache_format_detected_from_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = owned_entries();
+
+        // Gzip data under a plain name still loads
+        let path = dir.path().join("usage.json");
+        write_json(&path, &entries.len(), CacheFormat::Gzip).unwrap();
+        assert_eq!(read_json::<usize>(&path).unwrap(), 2);
+
+        // Garbage is an error, not an empty cache
+        fs::write(&path, "not a cache").unwrap();
+        assert!(matches!(
+            read_json::<usize>(&path),
+            Err(CcrError::JsonParse { .. })
+        ));
+        assert!(matches!(
+            read_json::<usize>(&dir.path().join("missing.json")),
+            Err(CcrError::FileRead { .. })
+        ));
+    }
+}
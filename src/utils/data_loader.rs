@@ -1,13 +1,27 @@
-use crate::constants::SESSION_BLOCK_DURATION;
-use crate::error::Result;
-use crate::types::{MergedUsageSnapshot, SessionId, UniqueHash, UsageEntry, UsageEntryData};
-use chrono::{Local, Utc};
+use crate::config;
+use crate::constants::session_block_duration;
+use crate::error::{CcrError, Result};
+use crate::types::{
+    Cost, DedupBuildHasher, LoadDiagnostics, MergedUsageSnapshot, SessionId, TokenTotals,
+    UniqueHash, UsageEntry, UsageEntryData,
+};
+use crate::utils::cache::{
+    CacheFormat, FileKey, load_file_cache, prune_file_cache, save_file_cache,
+};
+use crate::utils::paths::projects_dir;
+use chrono::{DateTime, Duration, Local, Utc};
+use flate2::read::MultiGzDecoder;
 use rayon::prelude::*;
 use serde_json;
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use tokio::task;
 
 // Capacity constants for performance optimization
@@ -15,17 +29,112 @@ const INITIAL_HASH_CAPACITY: usize = 1024;
 const ENTRIES_BATCH_CAPACITY: usize = 128;
 const ALL_ENTRIES_CAPACITY: usize = 1024;
 
+/// Suffix of gzip-compressed session logs
+const GZIP_JSONL_SUFFIX: &str = ".jsonl.gz";
+
+/// Loader settings, normally read from the environment
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Load at least this much history (CLAUDE_CCR_LOOKBACK_HOURS)
+    pub lookback: Option<Duration>,
+    /// Scan only this many most recently modified files per projects directory
+    /// (CLAUDE_CCR_MAX_FILES)
+    pub max_files: Option<usize>,
+    /// Load only the current session and the current block window, skipping
+    /// files not modified since (CLAUDE_CCR_MINIMAL); today's total is incomplete
+    pub minimal: bool,
+    /// Keep duplicate message/request records (CLAUDE_CCR_NO_DEDUP); for
+    /// forensics only, as totals then overstate the real cost
+    pub no_dedup: bool,
+    /// Load the whole history, without the recent-activity cutoff
+    /// (for reports and exports rather than the statusline); overrides
+    /// `lookback`, `max_files` and `minimal`
+    pub unfiltered: bool,
+    /// Subdirectories of each Claude root whose top-level `.jsonl` files are
+    /// also loaded, besides the root itself (CLAUDE_CCR_EXTRA_DIRS, comma-separated)
+    pub extra_dirs: Vec<String>,
+    /// Directory caching the parsed entries of each file by size and
+    /// modification time (CLAUDE_CCR_CACHE); None parses every file
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl LoadOptions {
+    /// Read the options from their environment variables
+    pub fn from_env() -> Self {
+        Self {
+            lookback: config::env_parse::<u32>("CLAUDE_CCR_LOOKBACK_HOURS")
+                .map(|hours| Duration::hours(i64::from(hours))),
+            max_files: config::env_parse("CLAUDE_CCR_MAX_FILES"),
+            minimal: config::env_flag("CLAUDE_CCR_MINIMAL"),
+            no_dedup: config::env_flag("CLAUDE_CCR_NO_DEDUP"),
+            unfiltered: false,
+            extra_dirs: config::env_var("CLAUDE_CCR_EXTRA_DIRS")
+                .map(|dirs| {
+                    dirs.split(',')
+                        .map(str::trim)
+                        .filter(|dir| !dir.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            cache_dir: config::env_flag("CLAUDE_CCR_CACHE")
+                .then(entry_cache_dir)
+                .flatten(),
+        }
+    }
+}
+
+/// Default directory of the per-file entry cache, `~/.cache/ccr/entries`
+pub fn entry_cache_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".cache/ccr/entries"))
+}
+
 /// Filter boundaries for data loading
 struct FilterBoundaries {
     cutoff_timestamp: String,
 }
 
 impl FilterBoundaries {
+    /// Calculate filter boundaries for the given options
+    fn new(now: DateTime<Utc>, options: &LoadOptions) -> Result<Self> {
+        if options.unfiltered {
+            // Every timestamp sorts at or after the empty string
+            Ok(Self {
+                cutoff_timestamp: String::new(),
+            })
+        } else if options.minimal {
+            Self::block_window(now)
+        } else {
+            Self::with_lookback(now, options.lookback)
+        }
+    }
+
+    /// Cutoff covering only the current and previous session blocks
+    fn block_window(now: DateTime<Utc>) -> Result<Self> {
+        let cutoff = Self::block_window_start(now)?;
+        Ok(Self {
+            cutoff_timestamp: cutoff.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        })
+    }
+
+    /// Start of the two-block window needed to find the active block
+    fn block_window_start(now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        now.checked_sub_signed(session_block_duration() * 2)
+            .ok_or_else(|| crate::error::CcrError::DataValidation {
+                message: "Failed to calculate minimum lookback".to_string(),
+            })
+    }
+
     /// Calculate filter boundaries based on today's start and session block duration
     /// Optimized for performance with minimal allocations
-    fn new() -> Result<Self> {
+    ///
+    /// An optional lookback extends the cutoff to `now - lookback`, but never
+    /// moves it later than the block-safety window
+    fn with_lookback(now: DateTime<Utc>, lookback: Option<Duration>) -> Result<Self> {
         // Today's start (in UTC for comparison with timestamps)
-        let today_start = Local::now()
+        let today_start = now
+            .with_timezone(&Local)
             .date_naive()
             .and_hms_opt(0, 0, 0)
             .ok_or_else(|| crate::error::CcrError::DataValidation {
@@ -42,29 +151,26 @@ impl FilterBoundaries {
         // before today's start. This ensures we capture complete session blocks
         // that might span across midnight.
         let safe_today_cutoff = today_start
-            .checked_sub_signed(SESSION_BLOCK_DURATION)
+            .checked_sub_signed(session_block_duration())
             .ok_or_else(|| crate::error::CcrError::DataValidation {
                 message: "Failed to calculate cutoff timestamp".to_string(),
-            })?
-            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            })?;
 
         // Also ensure we get at least 2 session blocks from current time
         // (current block + previous block for proper cost calculation)
-        let minimum_lookback = Utc::now()
-            .checked_sub_signed(SESSION_BLOCK_DURATION * 2)
-            .ok_or_else(|| crate::error::CcrError::DataValidation {
-                message: "Failed to calculate minimum lookback".to_string(),
-            })?
-            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let minimum_lookback = Self::block_window_start(now)?;
 
         // Use the earlier timestamp as the cutoff to ensure complete data
-        let cutoff_timestamp = if safe_today_cutoff < minimum_lookback {
-            safe_today_cutoff
-        } else {
-            minimum_lookback
-        };
+        let mut cutoff = safe_today_cutoff.min(minimum_lookback);
 
-        Ok(Self { cutoff_timestamp })
+        // A configured lookback may only load more history, never less
+        if let Some(requested) = lookback.and_then(|lookback| now.checked_sub_signed(lookback)) {
+            cutoff = cutoff.min(requested);
+        }
+
+        Ok(Self {
+            cutoff_timestamp: cutoff.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        })
     }
 }
 
@@ -88,27 +194,45 @@ fn should_keep_entry(
     }
 }
 
-/// Collect all JSONL files from a projects directory
-fn collect_jsonl_files(projects_path: &Path) -> Vec<(PathBuf, String)> {
-    if !projects_path.exists() {
-        return Vec::new();
-    }
+/// A JSONL file found under `projects/<project>/`, or loose under a Claude root
+#[derive(Debug)]
+struct JsonlFile {
+    path: PathBuf,
+    session_id: String,
+    /// None for files outside `projects/`
+    project: Option<Arc<str>>,
+    /// Claude data root the projects directory belongs to
+    source: Arc<Path>,
+}
+
+/// Collect all JSONL files from a projects directory under the `source` root
+///
+/// A missing directory yields no files, while one that exists but cannot be
+/// read (e.g. permission denied or a regular file) is reported as an error
+fn collect_jsonl_files(projects_path: &Path, source: &Arc<Path>) -> Result<Vec<JsonlFile>> {
+    let entries = match fs::read_dir(projects_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(CcrError::DirectoryAccess {
+                path: projects_path.to_path_buf(),
+                source,
+            });
+        }
+    };
 
     // Collect all project directories
-    let project_dirs: Vec<_> = fs::read_dir(projects_path)
-        .ok()
-        .map(|entries| {
-            entries
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
-                .collect()
-        })
-        .unwrap_or_default();
+    let project_dirs: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+        .collect();
 
     // Parallel scan of all project directories for JSONL files
-    project_dirs
+    let files = project_dirs
         .par_iter()
         .flat_map(|project_entry| {
+            let project: Option<Arc<str>> =
+                Some(Arc::from(project_entry.file_name().to_string_lossy()));
             fs::read_dir(project_entry.path())
                 .ok()
                 .map(|entries| {
@@ -117,10 +241,13 @@ fn collect_jsonl_files(projects_path: &Path) -> Vec<(PathBuf, String)> {
                         .filter_map(|file_entry| {
                             let file_name = file_entry.file_name();
                             let file_name_str = file_name.to_string_lossy();
-                            if file_name_str.ends_with(".jsonl") {
-                                let session_id =
-                                    file_name_str.trim_end_matches(".jsonl").to_string();
-                                Some((file_entry.path(), session_id))
+                            if is_jsonl_file_name(&file_name_str) {
+                                Some(JsonlFile {
+                                    path: file_entry.path(),
+                                    session_id: session_id_from_file_name(&file_name_str),
+                                    project: project.clone(),
+                                    source: Arc::clone(source),
+                                })
                             } else {
                                 None
                             }
@@ -129,150 +256,1236 @@ fn collect_jsonl_files(projects_path: &Path) -> Vec<(PathBuf, String)> {
                 })
                 .unwrap_or_default()
         })
+        .collect();
+
+    Ok(files)
+}
+
+/// Collect the session logs directly inside `dir`, without descending
+/// Newer Claude Code versions may write some sessions outside `projects/`.
+/// Only session-shaped names (`<uuid>.jsonl`) are taken, so other logs kept
+/// next to them, such as the prompt history in `history.jsonl`, are not read
+fn collect_loose_jsonl_files(dir: &Path, source: &Arc<Path>) -> Vec<JsonlFile> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let file_name_str = file_name.to_string_lossy();
+            if !is_jsonl_file_name(&file_name_str) {
+                return None;
+            }
+            let session_id = session_id_from_file_name(&file_name_str);
+            is_session_uuid(&session_id).then(|| JsonlFile {
+                path: entry.path(),
+                session_id,
+                project: None,
+                source: Arc::clone(source),
+            })
+        })
         .collect()
 }
 
-/// Process a single JSONL file and return filtered entries
-fn process_jsonl_file(
-    path: &Path,
-    session_file_id: &str,
+/// Collect the JSONL files of one Claude root: `projects/`, then loose files
+/// in the root and in `extra_dirs`
+/// A directory reachable twice (e.g. an extra directory linked to the root or
+/// into `projects/`) is scanned once, by canonical path; only the handful of
+/// directories is canonicalized, not every file
+fn collect_root_jsonl_files(source: &Arc<Path>, extra_dirs: &[String]) -> Result<Vec<JsonlFile>> {
+    let projects = projects_dir(source);
+    let mut files = collect_jsonl_files(&projects, source)?;
+
+    let canonical = |dir: &Path| fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let canonical_projects = canonical(&projects);
+    let loose_dirs =
+        std::iter::once(source.to_path_buf()).chain(extra_dirs.iter().map(|dir| source.join(dir)));
+    let mut seen = HashSet::new();
+    for dir in loose_dirs {
+        let canonical_dir = canonical(&dir);
+        if !canonical_dir.starts_with(&canonical_projects) && seen.insert(canonical_dir) {
+            files.extend(collect_loose_jsonl_files(&dir, source));
+        }
+    }
+    Ok(files)
+}
+
+/// Whether a file stem looks like a Claude Code session id, e.g.
+/// `3680e2cb-6c42-4c66-8545-973e66227c1d`
+fn is_session_uuid(stem: &str) -> bool {
+    stem.len() == 36
+        && stem.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Keep only the `max_files` most recently modified files
+/// Files whose modification time cannot be read sort last
+fn select_recent_files(files: Vec<JsonlFile>, max_files: usize) -> Vec<JsonlFile> {
+    if files.len() <= max_files {
+        return files;
+    }
+
+    let mut with_mtime: Vec<_> = files
+        .into_par_iter()
+        .map(|file| {
+            let modified = fs::metadata(&file.path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (modified, file)
+        })
+        .collect();
+
+    // Newest first; the path breaks ties so the selection is deterministic
+    with_mtime.sort_by(|(a_time, a_file), (b_time, b_file)| {
+        b_time
+            .cmp(a_time)
+            .then_with(|| a_file.path.cmp(&b_file.path))
+    });
+    with_mtime.truncate(max_files);
+    with_mtime.into_iter().map(|(_, file)| file).collect()
+}
+
+/// Keep files modified at or after `since`, plus the current session's files
+/// Logs are append-only, so an older file cannot hold entries after `since`;
+/// files whose modification time cannot be read are kept
+fn select_modified_since(
+    files: Vec<JsonlFile>,
+    since: SystemTime,
     current_session_id: &SessionId,
-    cutoff_timestamp: &str,
-) -> Vec<UsageEntry> {
-    match fs::read_to_string(path) {
-        Ok(contents) => {
-            // Pre-create session ID to avoid repeated allocations
-            // Arc<str> makes cloning very cheap
-            let file_session_id = SessionId::from(session_file_id);
-
-            // Parse lines in parallel with early filtering
-            contents
-                .par_lines()
-                .filter(|line| !line.trim().is_empty())
-                .filter_map(|line| {
-                    // Early exit for non-matching sessions (before parsing)
-                    // Only parse if it's the current session or check timestamp
-                    let data: UsageEntryData = serde_json::from_str(line).ok()?;
-
-                    // Use pre-created session ID (Arc clone is cheap)
-                    let entry = UsageEntry::from_data(data, file_session_id.clone());
-
-                    // Apply early filtering to reduce memory usage
-                    if should_keep_entry(&entry, current_session_id, cutoff_timestamp) {
-                        Some(entry)
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+) -> Vec<JsonlFile> {
+    files
+        .into_par_iter()
+        .filter(|file| {
+            file.session_id == current_session_id.as_str()
+                || fs::metadata(&file.path)
+                    .and_then(|metadata| metadata.modified())
+                    .map_or(true, |modified| modified >= since)
+        })
+        .collect()
+}
+
+/// Session logs, plain or gzip-compressed
+fn is_jsonl_file_name(file_name: &str) -> bool {
+    file_name.ends_with(".jsonl") || file_name.ends_with(GZIP_JSONL_SUFFIX)
+}
+
+/// Derive the session id from a JSONL file name (`<session>.jsonl` or `<session>.jsonl.gz`)
+fn session_id_from_file_name(file_name: &str) -> String {
+    file_name
+        .strip_suffix(".gz")
+        .unwrap_or(file_name)
+        .trim_end_matches(".jsonl")
+        .to_string()
+}
+
+/// Read a session log, decompressing `.jsonl.gz` archives while reading
+/// Concatenated gzip members are read through, as produced by appending to an archive
+fn read_jsonl_file(path: &Path) -> io::Result<String> {
+    let is_gzip = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(GZIP_JSONL_SUFFIX));
+    if !is_gzip {
+        return fs::read_to_string(path);
+    }
+    let mut contents = String::new();
+    MultiGzDecoder::new(io::BufReader::new(fs::File::open(path)?)).read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Shared state for one load: filter settings plus counters updated by the parsing tasks
+struct LoadContext {
+    current_session_id: SessionId,
+    cutoff_timestamp: String,
+    /// Scan only this many most recently modified files per projects directory
+    max_files: Option<usize>,
+    /// Skip files not modified since this time (minimal mode)
+    modified_since: Option<SystemTime>,
+    /// Subdirectories of each root scanned for loose JSONL files
+    extra_dirs: Vec<String>,
+    /// Per-file entry cache and its encoding
+    cache: Option<(PathBuf, CacheFormat)>,
+    /// Entries dropped by the early filter
+    filtered_entries: AtomicUsize,
+    /// JSONL files actually read
+    files_scanned: AtomicUsize,
+    /// Malformed lines per file, only files with any
+    skipped_lines: Mutex<Vec<(PathBuf, usize)>>,
+}
+
+impl LoadContext {
+    fn new(current_session_id: SessionId, cutoff_timestamp: String) -> Self {
+        Self {
+            current_session_id,
+            cutoff_timestamp,
+            max_files: None,
+            modified_since: None,
+            extra_dirs: Vec::new(),
+            cache: None,
+            filtered_entries: AtomicUsize::new(0),
+            files_scanned: AtomicUsize::new(0),
+            skipped_lines: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Remember a file's malformed lines
+    fn record_skipped(&self, path: &Path, count: usize) {
+        if count > 0
+            && let Ok(mut skipped) = self.skipped_lines.lock()
+        {
+            skipped.push((path.to_path_buf(), count));
+        }
+    }
+
+    /// Copy the counters into the snapshot diagnostics
+    fn record(&self, diagnostics: &mut LoadDiagnostics) {
+        diagnostics.filtered_entries = self.filtered_entries.load(AtomicOrdering::Relaxed);
+        diagnostics.files_scanned = self.files_scanned.load(AtomicOrdering::Relaxed);
+        diagnostics.cutoff_timestamp = Some(self.cutoff_timestamp.clone());
+        if let Ok(skipped) = self.skipped_lines.lock() {
+            diagnostics.skipped_lines = skipped.clone();
+            diagnostics.skipped_lines.sort();
         }
-        Err(_) => Vec::new(),
     }
 }
 
-/// Deduplicate entries using global hash set
-fn deduplicate_entries(
-    results: Vec<Vec<UsageEntry>>,
-    global_hashes: Arc<Mutex<HashSet<UniqueHash>>>,
-) -> Result<Vec<Arc<UsageEntry>>> {
-    let mut all_entries = Vec::with_capacity(ENTRIES_BATCH_CAPACITY);
+/// Process a single JSONL file and return filtered entries tagged with its
+/// project and source root
+fn process_jsonl_file(file: &JsonlFile, context: &LoadContext) -> Vec<UsageEntry> {
+    let skipped = AtomicUsize::new(0);
+    let mut entries = match context.cache {
+        // The current session's file changes on every render, so it is
+        // always parsed; other files come from the cache while unchanged
+        Some((ref dir, format)) if file.session_id != context.current_session_id.as_str() => {
+            let entries = cached_or_parsed_entries(file, dir, format, &skipped);
+            filter_entries(entries, context)
+        }
+        _ => match read_jsonl_file(&file.path) {
+            Ok(contents) => parse_jsonl_contents(&contents, &file.session_id, context, &skipped),
+            Err(_) => Vec::new(),
+        },
+    };
+    context.record_skipped(&file.path, skipped.into_inner());
+    for entry in &mut entries {
+        entry.project = file.project.clone();
+        entry.source = Some(Arc::clone(&file.source));
+    }
+    entries
+}
+
+/// All entries of a file, from the cache when its size and modification time
+/// are unchanged, otherwise parsed and written back to the cache
+/// The cache is best effort: failing to write it only costs the next parse.
+/// The count of malformed lines is cached along with the entries, so a
+/// cache hit reports the same skipped lines as a parse
+fn cached_or_parsed_entries(
+    file: &JsonlFile,
+    dir: &Path,
+    format: CacheFormat,
+    skipped: &AtomicUsize,
+) -> Vec<UsageEntry> {
+    // Read the key before the contents, so an append in between only
+    // invalidates the cache entry rather than hiding the new lines
+    let key = FileKey::for_path(&file.path);
+    if let Some((entries, skipped_lines)) = key
+        .as_ref()
+        .and_then(|key| load_file_cache(dir, key, format))
+    {
+        skipped.fetch_add(skipped_lines, AtomicOrdering::Relaxed);
+        return entries;
+    }
+
+    let Ok(contents) = read_jsonl_file(&file.path) else {
+        return Vec::new();
+    };
+    let entries = parse_jsonl_all(&contents, &file.session_id, skipped);
+    if let Some(key) = key {
+        let skipped_lines = skipped.load(AtomicOrdering::Relaxed);
+        let _ = save_file_cache(dir, &key, &entries, skipped_lines, format);
+    }
+    entries
+}
+
+/// Apply the early filter to already parsed entries
+fn filter_entries(entries: Vec<UsageEntry>, context: &LoadContext) -> Vec<UsageEntry> {
+    let total = entries.len();
+    let kept: Vec<UsageEntry> = entries
+        .into_iter()
+        .filter(|entry| {
+            should_keep_entry(
+                entry,
+                &context.current_session_id,
+                &context.cutoff_timestamp,
+            )
+        })
+        .collect();
+    context
+        .filtered_entries
+        .fetch_add(total - kept.len(), AtomicOrdering::Relaxed);
+    kept
+}
+
+/// Parse every entry of JSONL contents, without filtering
+fn parse_jsonl_all(
+    contents: &str,
+    session_file_id: &str,
+    skipped: &AtomicUsize,
+) -> Vec<UsageEntry> {
+    let file_session_id = SessionId::from(session_file_id);
+    contents
+        .par_lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let data = parse_line(line, skipped)?;
+            Some(UsageEntry::from_data(data, file_session_id.clone()))
+        })
+        .collect()
+}
+
+/// Parse one non-blank line, counting it in `skipped` when it is not a valid entry
+fn parse_line(line: &str, skipped: &AtomicUsize) -> Option<UsageEntryData> {
+    let data = serde_json::from_str(line).ok();
+    if data.is_none() {
+        skipped.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+    data
+}
+
+/// Parse JSONL contents and return filtered entries
+fn parse_jsonl_contents(
+    contents: &str,
+    session_file_id: &str,
+    context: &LoadContext,
+    skipped: &AtomicUsize,
+) -> Vec<UsageEntry> {
+    // Pre-create session ID to avoid repeated allocations
+    // Arc<str> makes cloning very cheap
+    let file_session_id = SessionId::from(session_file_id);
+
+    // Parse lines in parallel with early filtering
+    contents
+        .par_lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            // Early exit for non-matching sessions (before parsing)
+            // Only parse if it's the current session or check timestamp
+            let data = parse_line(line, skipped)?;
 
-    // Process all results with a single lock acquisition
-    let mut hashes = global_hashes
-        .lock()
-        .map_err(|_| crate::error::CcrError::LockPoisoned)?;
+            // Use pre-created session ID (Arc clone is cheap)
+            let entry = UsageEntry::from_data(data, file_session_id.clone());
+
+            // Apply early filtering to reduce memory usage
+            if should_keep_entry(
+                &entry,
+                &context.current_session_id,
+                &context.cutoff_timestamp,
+            ) {
+                Some(entry)
+            } else {
+                context
+                    .filtered_entries
+                    .fetch_add(1, AtomicOrdering::Relaxed);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Deduplicate entries by message_id:request_id
+///
+/// When the same pair appears more than once (e.g. in two files), the entry
+/// with more complete data wins. Ties keep the earliest timestamp and then the
+/// lowest session id, so the result does not depend on processing order.
+fn deduplicate_entries(results: Vec<Vec<UsageEntry>>) -> Vec<Arc<UsageEntry>> {
+    let mut all_entries: Vec<UsageEntry> = Vec::with_capacity(ENTRIES_BATCH_CAPACITY);
+    let mut seen: HashMap<UniqueHash, usize, DedupBuildHasher> =
+        HashMap::with_capacity_and_hasher(INITIAL_HASH_CAPACITY, DedupBuildHasher::default());
 
     for entries in results {
         for entry in entries {
             // Check for duplicate only when both IDs exist
             if let Some(hash) = UniqueHash::from_usage_entry_data(&entry.data) {
-                if hashes.contains(&hash) {
-                    continue;
+                match seen.entry(hash) {
+                    Entry::Occupied(occupied) => {
+                        let index = *occupied.get();
+                        if is_preferred_duplicate(&entry, &all_entries[index]) {
+                            all_entries[index] = entry;
+                        }
+                        continue;
+                    }
+                    Entry::Vacant(vacant) => {
+                        vacant.insert(all_entries.len());
+                    }
                 }
-                hashes.insert(hash);
             }
 
-            all_entries.push(Arc::new(entry));
+            all_entries.push(entry);
         }
     }
-    // Lock is automatically released here
 
-    Ok(all_entries)
+    all_entries.into_iter().map(Arc::new).collect()
 }
 
+/// Score how complete an entry's data is (pre-calculated cost > usage > timestamp)
+fn completeness(entry: &UsageEntry) -> u8 {
+    let has_cost = entry.data.cost_usd.is_some();
+    let has_usage = entry
+        .data
+        .message
+        .as_ref()
+        .is_some_and(|message| message.usage.is_some());
+    let has_timestamp = entry.data.timestamp.is_some();
+
+    (has_cost as u8) << 2 | (has_usage as u8) << 1 | has_timestamp as u8
+}
+
+/// Check whether a duplicate candidate should replace the currently kept entry
+fn is_preferred_duplicate(candidate: &UsageEntry, current: &UsageEntry) -> bool {
+    completeness(candidate)
+        .cmp(&completeness(current))
+        // Earlier timestamp wins
+        .then_with(|| current.data.timestamp.cmp(&candidate.data.timestamp))
+        // Lower session id wins
+        .then_with(|| {
+            current
+                .session_id
+                .as_str()
+                .cmp(candidate.session_id.as_str())
+        })
+        == Ordering::Greater
+}
+
+/// Entries parsed from one projects directory, or the error that made it unreadable
+type DirectoryResult = std::result::Result<Vec<Vec<UsageEntry>>, CcrError>;
+
 /// Process all files from a projects directory
 async fn process_projects_directory(
-    projects_path: PathBuf,
-    global_hashes: Arc<Mutex<HashSet<UniqueHash>>>,
-    current_session_id: SessionId,
-    cutoff_timestamp: String,
-) -> Result<Vec<Arc<UsageEntry>>> {
+    source: Arc<Path>,
+    context: Arc<LoadContext>,
+) -> Result<DirectoryResult> {
     task::spawn_blocking(move || {
         // Collect all JSONL files
-        let all_files = collect_jsonl_files(&projects_path);
+        let mut all_files = collect_root_jsonl_files(&source, &context.extra_dirs)?;
+        if let Some(since) = context.modified_since {
+            all_files = select_modified_since(all_files, since, &context.current_session_id);
+        }
+        if let Some(max_files) = context.max_files {
+            all_files = select_recent_files(all_files, max_files);
+        }
+        context
+            .files_scanned
+            .fetch_add(all_files.len(), AtomicOrdering::Relaxed);
 
         // Process files in parallel
-        let results: Vec<_> = all_files
+        let results = all_files
             .par_iter()
-            .map(|(path, session_file_id)| {
-                process_jsonl_file(
-                    path,
-                    session_file_id,
-                    &current_session_id,
-                    &cutoff_timestamp,
-                )
-            })
+            .map(|file| process_jsonl_file(file, &context))
             .collect();
 
-        // Deduplicate entries
-        let entries = deduplicate_entries(results, global_hashes)?;
-
-        Ok(entries)
+        Ok(results)
     })
-    .await?
+    .await
+    .map_err(Into::into)
 }
 
 /// Load all data with optimized parallelism and early filtering
+/// Options are read from the environment (see [`LoadOptions::from_env`])
 pub async fn load_all_data(
     claude_paths: &[PathBuf],
     session_id: &SessionId,
 ) -> Result<MergedUsageSnapshot> {
-    // Initialize shared state for deduplication
-    let global_hashes: Arc<Mutex<HashSet<UniqueHash>>> =
-        Arc::new(Mutex::new(HashSet::with_capacity(INITIAL_HASH_CAPACITY)));
+    load_all_data_with(claude_paths, session_id, &LoadOptions::from_env()).await
+}
 
+/// Load all data with explicit options
+pub async fn load_all_data_with(
+    claude_paths: &[PathBuf],
+    session_id: &SessionId,
+    options: &LoadOptions,
+) -> Result<MergedUsageSnapshot> {
     // Calculate filter boundaries
-    let boundaries = FilterBoundaries::new()?;
+    let now = Utc::now();
+    let boundaries = FilterBoundaries::new(now, options)?;
+    let mut context = LoadContext::new(session_id.clone(), boundaries.cutoff_timestamp);
+    context.extra_dirs = options.extra_dirs.clone();
+    context.cache = options
+        .cache_dir
+        .clone()
+        .map(|dir| (dir, CacheFormat::from_env()));
+    if let Some(ref dir) = options.cache_dir {
+        prune_file_cache(dir, SystemTime::now());
+    }
+    // The file selection settings only make sense for the statusline; the
+    // whole-history modes must see every file
+    if !options.unfiltered {
+        context.max_files = options.max_files;
+        if options.minimal {
+            context.modified_since = Some(FilterBoundaries::block_window_start(now)?.into());
+        }
+    }
+    let context = Arc::new(context);
 
     // Process each projects directory in parallel
     let tasks: Vec<_> = claude_paths
         .iter()
         .map(|base_path| {
-            let projects_path = base_path.join("projects");
-            process_projects_directory(
-                projects_path,
-                Arc::clone(&global_hashes),
-                session_id.clone(),
-                boundaries.cutoff_timestamp.clone(),
-            )
+            process_projects_directory(Arc::from(base_path.as_path()), Arc::clone(&context))
         })
         .collect();
 
     // Merge results from all base paths
-    let mut all_entries = Vec::with_capacity(ALL_ENTRIES_CAPACITY);
+    let mut results = Vec::with_capacity(ALL_ENTRIES_CAPACITY);
+    let mut diagnostics = LoadDiagnostics::default();
 
     for task in tasks {
-        let data = task.await?;
-        all_entries.extend(data);
+        // An unreadable directory should not hide data from the other paths
+        match task.await? {
+            Ok(data) => results.extend(data),
+            Err(e) => diagnostics.access_errors.push(e),
+        }
     }
 
-    // Sort all entries by timestamp (string sort is sufficient for ISO 8601)
-    all_entries.sort_by(|a, b| {
+    context.record(&mut diagnostics);
+
+    // Deduplicate once across all base paths so the outcome is deterministic
+    let mut all_entries = if options.no_dedup {
+        results.into_iter().flatten().map(Arc::new).collect()
+    } else {
+        deduplicate_entries(results)
+    };
+    sort_by_timestamp(&mut all_entries);
+
+    Ok(MergedUsageSnapshot {
+        all_entries,
+        diagnostics,
+        keeps_duplicates: options.no_dedup,
+    })
+}
+
+/// Load a single JSONL file, bypassing the directory scan
+/// The session id is derived from the file name and no entries are filtered out,
+/// which makes this useful for reproducing per-session cost reports
+pub fn load_jsonl_file(path: &Path) -> Result<MergedUsageSnapshot> {
+    let contents = read_jsonl_file(path).map_err(|source| CcrError::FileRead {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    Ok(snapshot_from_contents(
+        &contents,
+        &session_id_from_file_name(&file_name),
+    ))
+}
+
+/// Cost and token totals of in-memory JSONL content, without touching the filesystem
+/// Blank and malformed lines are skipped and duplicates are counted once,
+/// as when loading files; meant for embedding (e.g. an editor plugin)
+pub fn parse_and_cost(jsonl: &str) -> (Cost, TokenTotals) {
+    let snapshot = snapshot_from_contents(jsonl, "");
+    let entries = || snapshot.all_entries.iter().map(|e| e.as_ref());
+    (
+        Cost::from_entries(entries()),
+        TokenTotals::from_entries(entries()),
+    )
+}
+
+/// Parse and deduplicate the contents of one session file, keeping every entry
+fn snapshot_from_contents(contents: &str, session_file_id: &str) -> MergedUsageSnapshot {
+    // Treat the file's own session as current so the early filter keeps everything
+    let context = LoadContext::new(SessionId::from(session_file_id), String::new());
+    let entries = parse_jsonl_contents(contents, session_file_id, &context, &AtomicUsize::new(0));
+
+    let mut all_entries = deduplicate_entries(vec![entries]);
+    sort_by_timestamp(&mut all_entries);
+
+    MergedUsageSnapshot::new(all_entries)
+}
+
+/// Sort entries by timestamp (string sort is sufficient for ISO 8601)
+fn sort_by_timestamp(entries: &mut [Arc<UsageEntry>]) {
+    entries.sort_by(|a, b| {
         a.data
             .timestamp
             .as_deref()
             .cmp(&b.data.timestamp.as_deref())
     });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_load_jsonl_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session-abc.jsonl");
+        let mut file = fs::File::create(&path).unwrap();
+        // Old entries are kept because the early filter is disabled
+        writeln!(file, r#"{{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.5,"message":{{"id":"m1"}},"requestId":"r1"}}"#).unwrap();
+        writeln!(file, r#"{{"timestamp":"2020-01-01T00:01:00Z","costUSD":1.5,"message":{{"id":"m1"}},"requestId":"r1"}}"#).unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "not json").unwrap();
+        writeln!(
+            file,
+            r#"{{"timestamp":"2020-01-01T00:02:00Z","costUSD":0.5}}"#
+        )
+        .unwrap();
+
+        let snapshot = load_jsonl_file(&path).unwrap();
+        assert_eq!(snapshot.all_entries.len(), 2);
+        assert_eq!(
+            snapshot
+                .session_cost(&SessionId::from("session-abc"))
+                .value(),
+            2.0
+        );
+    }
+
+    fn create_entry(session_id: &str, cost_usd: Option<f64>) -> UsageEntry {
+        let line = match cost_usd {
+            Some(cost) => format!(
+                r#"{{"timestamp":"2024-01-15T10:00:00Z","costUSD":{},"message":{{"id":"m1"}},"requestId":"r1"}}"#,
+                cost
+            ),
+            None => {
+                r#"{"timestamp":"2024-01-15T10:00:00Z","message":{"id":"m1"},"requestId":"r1"}"#
+                    .to_string()
+            }
+        };
+        let data: UsageEntryData = serde_json::from_str(&line).unwrap();
+        UsageEntry::from_data(data, SessionId::from(session_id))
+    }
+
+    #[test]
+    fn test_deduplicate_prefers_entry_with_cost() {
+        // The costed duplicate wins regardless of which one is seen first
+        for costed_first in [true, false] {
+            let costed = vec![create_entry("session-b", Some(0.42))];
+            let bare = vec![create_entry("session-a", None)];
+            let results = if costed_first {
+                vec![costed, bare]
+            } else {
+                vec![bare, costed]
+            };
+
+            let entries = deduplicate_entries(results);
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].data.cost_usd, Some(0.42));
+            assert_eq!(entries[0].session_id.as_str(), "session-b");
+        }
+    }
 
-    Ok(MergedUsageSnapshot { all_entries })
+    #[test]
+    fn test_deduplicate_tiebreak_is_deterministic() {
+        // Equally complete duplicates resolve to the lowest session id
+        for reversed in [false, true] {
+            let mut results = vec![
+                vec![create_entry("session-b", Some(0.1))],
+                vec![create_entry("session-a", Some(0.1))],
+            ];
+            if reversed {
+                results.reverse();
+            }
+
+            let entries = deduplicate_entries(results);
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].session_id.as_str(), "session-a");
+        }
+    }
+
+    #[test]
+    fn test_parse_and_cost() {
+        let jsonl = [
+            r#"{"timestamp":"2024-01-15T10:00:00Z","costUSD":1.5,"message":{"id":"m1","usage":{"input_tokens":100,"output_tokens":20}},"requestId":"r1"}"#,
+            "",
+            "not json",
+            // Streaming duplicate of the first entry
+            r#"{"timestamp":"2024-01-15T10:00:01Z","costUSD":1.5,"message":{"id":"m1","usage":{"input_tokens":100,"output_tokens":20}},"requestId":"r1"}"#,
+            r#"{"timestamp":"2024-01-15T10:01:00Z","costUSD":0.5,"message":{"id":"m2","usage":{"input_tokens":50,"output_tokens":10}},"requestId":"r2"}"#,
+        ]
+        .join("\n");
+
+        let (cost, tokens) = parse_and_cost(&jsonl);
+        assert_eq!(cost.value(), 2.0);
+        assert_eq!(tokens.input_tokens, 150);
+        assert_eq!(tokens.output_tokens, 30);
+
+        let (cost, tokens) = parse_and_cost("");
+        assert_eq!(cost.value(), 0.0);
+        assert_eq!(tokens.total(), 0);
+    }
+
+    #[test]
+    fn test_load_jsonl_file_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.jsonl");
+
+        match load_jsonl_file(&path) {
+            Err(CcrError::FileRead { path: err_path, .. }) => assert_eq!(err_path, path),
+            other => panic!("Expected FileRead error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_collect_jsonl_files_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let files =
+            collect_jsonl_files(&dir.path().join("projects"), &Arc::from(dir.path())).unwrap();
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_collect_jsonl_files_projects_is_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects");
+        fs::write(&path, "not a directory").unwrap();
+
+        match collect_jsonl_files(&path, &Arc::from(dir.path())) {
+            Err(CcrError::DirectoryAccess { path: err_path, .. }) => assert_eq!(err_path, path),
+            other => panic!("Expected DirectoryAccess error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_tags_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(
+            project_dir.join("current.jsonl"),
+            r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#,
+        )
+        .unwrap();
+
+        let snapshot = load_all_data(&[dir.path().to_path_buf()], &SessionId::from("current"))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.all_entries.len(), 1);
+        assert_eq!(
+            snapshot.all_entries[0].project.as_deref(),
+            Some("-work-ccr")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_source_of_session_with_two_roots() {
+        let write_session = |root: &Path, session: &str| {
+            let project_dir = root.join("projects").join("-work-ccr");
+            fs::create_dir_all(&project_dir).unwrap();
+            fs::write(
+                project_dir.join(format!("{}.jsonl", session)),
+                format!(
+                    r#"{{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0,"message":{{"id":"{0}"}},"requestId":"{0}"}}"#,
+                    session
+                ),
+            )
+            .unwrap();
+        };
+        let primary = tempfile::tempdir().unwrap();
+        let alternate = tempfile::tempdir().unwrap();
+        write_session(primary.path(), "main-session");
+        write_session(alternate.path(), "other-session");
+
+        let options = LoadOptions {
+            lookback: Some(Duration::days(365 * 100)),
+            ..Default::default()
+        };
+        let snapshot = load_all_data_with(
+            &[primary.path().to_path_buf(), alternate.path().to_path_buf()],
+            &SessionId::from("main-session"),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            snapshot.source_of_session(&SessionId::from("main-session")),
+            Some(primary.path().to_path_buf())
+        );
+        assert_eq!(
+            snapshot.source_of_session(&SessionId::from("other-session")),
+            Some(alternate.path().to_path_buf())
+        );
+        assert_eq!(
+            snapshot.source_of_session(&SessionId::from("missing")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_reads_gzip_archives() {
+        use flate2::Compression;
+        use flate2::write::GzEncoder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let recent = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let lines = [
+            format!(
+                r#"{{"timestamp":"{}","costUSD":1.0,"message":{{"id":"m1"}},"requestId":"r1"}}"#,
+                recent
+            ),
+            format!(
+                r#"{{"timestamp":"{}","costUSD":2.0,"message":{{"id":"m2"}},"requestId":"r2"}}"#,
+                recent
+            ),
+        ];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(lines.join("\n").as_bytes()).unwrap();
+        fs::write(
+            project_dir.join("archived.jsonl.gz"),
+            encoder.finish().unwrap(),
+        )
+        .unwrap();
+        // The plain copy of the first line is deduplicated against the archive
+        fs::write(project_dir.join("current.jsonl"), &lines[0]).unwrap();
+
+        let snapshot = load_all_data(&[dir.path().to_path_buf()], &SessionId::from("current"))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.all_entries.len(), 2);
+        let cost = Cost::from_entries(snapshot.all_entries.iter().map(|entry| entry.as_ref()));
+        assert_eq!(cost.value(), 3.0);
+        assert_eq!(session_id_from_file_name("archived.jsonl.gz"), "archived");
+        assert_eq!(session_id_from_file_name("plain.jsonl"), "plain");
+    }
+
+    #[test]
+    fn test_collect_root_jsonl_files_finds_loose_sessions() {
+        const LOCAL: &str = "3680e2cb-6c42-4c66-8545-973e66227c1d";
+        const EXTRA: &str = "0f9d5a31-2b7e-4c1a-9e33-8d2b6c4f7a10";
+
+        let root = tempfile::tempdir().unwrap();
+        let project_dir = root.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::create_dir_all(root.path().join("sessions")).unwrap();
+        fs::write(project_dir.join("in-project.jsonl"), "").unwrap();
+        fs::write(root.path().join(format!("{}.jsonl", LOCAL)), "").unwrap();
+        fs::write(root.path().join("settings.json"), "").unwrap();
+        // Claude Code's prompt history is not a session log
+        fs::write(root.path().join("history.jsonl"), r#"{"timestamp":1}"#).unwrap();
+        fs::write(
+            root.path()
+                .join("sessions")
+                .join(format!("{}.jsonl", EXTRA)),
+            "",
+        )
+        .unwrap();
+        // Directories reached again through links are scanned once
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.path(), root.path().join("again")).unwrap();
+            std::os::unix::fs::symlink(&project_dir, root.path().join("linked")).unwrap();
+        }
+
+        let source: Arc<Path> = Arc::from(root.path());
+        let sessions = |extra_dirs: &[&str]| {
+            let extra_dirs: Vec<String> = extra_dirs.iter().map(|dir| dir.to_string()).collect();
+            let files = collect_root_jsonl_files(&source, &extra_dirs).unwrap();
+            let mut found: Vec<(String, bool)> = files
+                .iter()
+                .map(|file| (file.session_id.clone(), file.project.is_some()))
+                .collect();
+            found.sort();
+            found
+        };
+
+        assert_eq!(
+            sessions(&[]),
+            vec![(LOCAL.to_string(), false), ("in-project".to_string(), true)]
+        );
+        assert_eq!(
+            sessions(&["sessions", "again", "linked"]),
+            vec![
+                (EXTRA.to_string(), false),
+                (LOCAL.to_string(), false),
+                ("in-project".to_string(), true)
+            ]
+        );
+        assert!(is_session_uuid(LOCAL));
+        assert!(!is_session_uuid("history"));
+        assert!(!is_session_uuid("3680e2cb_6c42_4c66_8545_973e66227c1d"));
+    }
+
+    #[test]
+    fn test_select_recent_files() {
+        use std::time::{Duration as StdDuration, SystemTime};
+
+        let dir = tempfile::tempdir().unwrap();
+        let base = SystemTime::now() - StdDuration::from_secs(3600);
+        let files: Vec<JsonlFile> = ["a", "b", "c", "d"]
+            .iter()
+            .zip([30, 10, 40, 20])
+            .map(|(name, age_minutes)| {
+                let path = dir.path().join(format!("{}.jsonl", name));
+                let file = fs::File::create(&path).unwrap();
+                file.set_modified(base - StdDuration::from_secs(age_minutes * 60))
+                    .unwrap();
+                JsonlFile {
+                    path,
+                    session_id: name.to_string(),
+                    project: Some(Arc::from("project")),
+                    source: Arc::from(dir.path()),
+                }
+            })
+            .collect();
+
+        let selected = select_recent_files(files, 2);
+        let names: Vec<&str> = selected.iter().map(|f| f.session_id.as_str()).collect();
+        assert_eq!(names, vec!["b", "d"]);
+
+        // Below the cap nothing is dropped
+        let one = vec![JsonlFile {
+            path: dir.path().join("a.jsonl"),
+            session_id: "a".to_string(),
+            project: Some(Arc::from("project")),
+            source: Arc::from(dir.path()),
+        }];
+        assert_eq!(select_recent_files(one, 2).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_minimal_scans_fewer_files() {
+        use std::time::Duration as StdDuration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let now = Utc::now();
+        let recent = now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let this_morning =
+            (now - Duration::hours(11)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let day_old = SystemTime::now() - StdDuration::from_secs(24 * 3600);
+        for (name, timestamp, modified) in [
+            ("current", this_morning.as_str(), Some(day_old)),
+            ("earlier", this_morning.as_str(), Some(day_old)),
+            ("other", recent.as_str(), None),
+        ] {
+            let path = project_dir.join(format!("{}.jsonl", name));
+            fs::write(
+                &path,
+                format!(r#"{{"timestamp":"{}","costUSD":1.0}}"#, timestamp),
+            )
+            .unwrap();
+            if let Some(modified) = modified {
+                fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(modified)
+                    .unwrap();
+            }
+        }
+
+        let paths = [dir.path().to_path_buf()];
+        let session = SessionId::from("current");
+        let full = load_all_data_with(&paths, &session, &LoadOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(full.diagnostics.files_scanned, 3);
+
+        // Minimal mode skips the stale file of another session but keeps
+        // the current session's file regardless of its age
+        let options = LoadOptions {
+            minimal: true,
+            ..Default::default()
+        };
+        let minimal = load_all_data_with(&paths, &session, &options)
+            .await
+            .unwrap();
+        assert_eq!(minimal.diagnostics.files_scanned, 2);
+        assert_eq!(minimal.session_cost(&session).value(), 1.0);
+        assert!(
+            minimal
+                .all_entries
+                .iter()
+                .all(|entry| entry.session_id.as_str() != "earlier")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unfiltered_ignores_minimal_and_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let year_old = SystemTime::now() - std::time::Duration::from_secs(365 * 24 * 3600);
+        for name in ["old-a", "old-b", "old-c"] {
+            let path = project_dir.join(format!("{}.jsonl", name));
+            fs::write(
+                &path,
+                r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#,
+            )
+            .unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(year_old)
+                .unwrap();
+        }
+
+        // As if CLAUDE_CCR_MINIMAL and CLAUDE_CCR_MAX_FILES were set
+        let options = LoadOptions {
+            unfiltered: true,
+            minimal: true,
+            max_files: Some(1),
+            ..Default::default()
+        };
+        let snapshot =
+            load_all_data_with(&[dir.path().to_path_buf()], &SessionId::default(), &options)
+                .await
+                .unwrap();
+        assert_eq!(snapshot.diagnostics.files_scanned, 3);
+        assert_eq!(snapshot.all_entries.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_counts_filtered_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let recent = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let lines = [
+            r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#.to_string(),
+            r#"{"timestamp":"2020-01-02T00:00:00Z","costUSD":1.0}"#.to_string(),
+            r#"{"timestamp":"2020-01-03T00:00:00Z","costUSD":1.0}"#.to_string(),
+            format!(r#"{{"timestamp":"{}","costUSD":1.0}}"#, recent),
+        ];
+        fs::write(project_dir.join("old.jsonl"), lines.join("\n")).unwrap();
+        // Old entries of the current session are never filtered
+        fs::write(
+            project_dir.join("current.jsonl"),
+            r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#,
+        )
+        .unwrap();
+
+        let snapshot = load_all_data(&[dir.path().to_path_buf()], &SessionId::from("current"))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.all_entries.len(), 2);
+        assert_eq!(snapshot.diagnostics.filtered_entries, 3);
+        assert!(snapshot.diagnostics.cutoff_timestamp.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_counts_skipped_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        let lines = [
+            r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#,
+            // Truncated write and a field of the wrong type
+            r#"{"timestamp":"2020-01-01T00:01:00Z","cost"#,
+            r#"{"timestamp":1704067320,"costUSD":1.0}"#,
+            "",
+        ];
+        let current = project_dir.join("current.jsonl");
+        fs::write(&current, lines.join("\n")).unwrap();
+        fs::write(project_dir.join("clean.jsonl"), lines[0]).unwrap();
+
+        let snapshot = load_all_data(&[dir.path().to_path_buf()], &SessionId::from("current"))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.all_entries.len(), 1);
+        // Blank lines are not malformed, and clean files are not listed
+        assert_eq!(
+            snapshot.diagnostics.skipped_lines,
+            vec![(current.clone(), 2)]
+        );
+        assert_eq!(snapshot.diagnostics.skipped_line_count(), 2);
+
+        // Cache hits replay the count of the parse that filled the cache
+        let other = project_dir.join("other.jsonl");
+        fs::rename(&current, &other).unwrap();
+        let options = LoadOptions {
+            lookback: Some(Duration::days(365 * 100)),
+            cache_dir: Some(dir.path().join("cache")),
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            let snapshot = load_all_data_with(
+                &[dir.path().to_path_buf()],
+                &SessionId::from("current"),
+                &options,
+            )
+            .await
+            .unwrap();
+            assert_eq!(snapshot.diagnostics.skipped_lines, vec![(other.clone(), 2)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_reuses_cached_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("claude");
+        let project_dir = root.join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+        let recent = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let other = project_dir.join("other.jsonl");
+        fs::write(
+            &other,
+            format!(
+                "{}\n{}\n",
+                r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0}"#,
+                format_args!(r#"{{"timestamp":"{}","costUSD":2.0}}"#, recent)
+            ),
+        )
+        .unwrap();
+        fs::write(
+            project_dir.join("current.jsonl"),
+            r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":4.0}"#,
+        )
+        .unwrap();
+
+        let current = SessionId::from("current");
+        let cached_options = LoadOptions {
+            cache_dir: Some(dir.path().join("cache")),
+            ..LoadOptions::default()
+        };
+        let load = |options: LoadOptions| {
+            let roots = [root.clone()];
+            let current = current.clone();
+            async move {
+                load_all_data_with(&roots, &current, &options)
+                    .await
+                    .unwrap()
+            }
+        };
+        let total = |snapshot: &MergedUsageSnapshot| {
+            Cost::from_entries(snapshot.all_entries.iter().map(|e| e.as_ref())).value()
+        };
+
+        // Cold and warm cache give the same snapshot as no cache
+        let uncached = load(LoadOptions::default()).await;
+        let cold = load(cached_options.clone()).await;
+        let warm = load(cached_options.clone()).await;
+        assert_eq!(total(&uncached), 6.0);
+        assert_eq!(
+            format!("{:?}", cold.all_entries),
+            format!("{:?}", uncached.all_entries)
+        );
+        assert_eq!(
+            format!("{:?}", warm.all_entries),
+            format!("{:?}", uncached.all_entries)
+        );
+        assert_eq!(warm.diagnostics.filtered_entries, 1);
+
+        // Same size and modification time: the cached entries are reused
+        let modified = fs::metadata(&other).unwrap().modified().unwrap();
+        let contents = fs::read_to_string(&other).unwrap();
+        fs::write(
+            &other,
+            contents.replace(r#""costUSD":2.0"#, r#""costUSD":3.0"#),
+        )
+        .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&other)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(total(&load(cached_options.clone()).await), 6.0);
+        assert_eq!(total(&load(LoadOptions::default()).await), 7.0);
+
+        // An appended line changes the size and invalidates the cache
+        let mut file = fs::File::options().append(true).open(&other).unwrap();
+        writeln!(file, r#"{{"timestamp":"{}","costUSD":0.5}}"#, recent).unwrap();
+        drop(file);
+        assert_eq!(total(&load(cached_options).await), 7.5);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_no_dedup_keeps_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("projects").join("-work-ccr");
+        fs::create_dir_all(&project_dir).unwrap();
+        let line = r#"{"timestamp":"2020-01-01T00:00:00Z","costUSD":1.0,"message":{"id":"m1"},"requestId":"r1"}"#;
+        // Streamed twice in one file and copied into a resumed session
+        fs::write(project_dir.join("current.jsonl"), [line, line].join("\n")).unwrap();
+        fs::write(project_dir.join("resumed.jsonl"), line).unwrap();
+
+        let load = |no_dedup| {
+            let options = LoadOptions {
+                lookback: Some(Duration::days(365 * 100)),
+                no_dedup,
+                ..Default::default()
+            };
+            let paths = [dir.path().to_path_buf()];
+            async move {
+                load_all_data_with(&paths, &SessionId::from("current"), &options)
+                    .await
+                    .unwrap()
+            }
+        };
+        let deduplicated = load(false).await;
+        let raw = load(true).await;
+
+        let total = |snapshot: &MergedUsageSnapshot| {
+            Cost::from_entries(snapshot.all_entries.iter().map(|e| e.as_ref()))
+        };
+        assert_eq!(total(&deduplicated).value(), 1.0);
+        assert_eq!(total(&raw).value(), 3.0);
+
+        let block_total = |snapshot: &MergedUsageSnapshot| -> f64 {
+            snapshot
+                .blocks_with_costs(false)
+                .iter()
+                .map(|(_, cost)| cost.value())
+                .sum()
+        };
+        assert_eq!(block_total(&deduplicated), 1.0);
+        assert_eq!(block_total(&raw), 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_load_all_data_reports_unreadable_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("projects"), "not a directory").unwrap();
+
+        let snapshot = load_all_data(&[dir.path().to_path_buf()], &SessionId::from("current"))
+            .await
+            .unwrap();
+        assert!(snapshot.all_entries.is_empty());
+        assert_eq!(snapshot.diagnostics.access_errors.len(), 1);
+    }
+
+    #[test]
+    fn test_lookback_hours_keeps_older_entries() {
+        let now = Utc::now();
+        let old = (now - Duration::hours(40)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let contents = format!(r#"{{"timestamp":"{}","costUSD":1.0}}"#, old);
+        let parse = |boundaries: &FilterBoundaries| {
+            let context = LoadContext::new(
+                SessionId::from("current"),
+                boundaries.cutoff_timestamp.clone(),
+            );
+            parse_jsonl_contents(&contents, "other", &context, &AtomicUsize::new(0))
+        };
+
+        // The default window reaches back at most 29 hours (midnight minus one block)
+        let default = FilterBoundaries::with_lookback(now, None).unwrap();
+        assert!(parse(&default).is_empty());
+
+        let extended = FilterBoundaries::with_lookback(now, Some(Duration::hours(48))).unwrap();
+        assert_eq!(parse(&extended).len(), 1);
+
+        // A short lookback never narrows the block-safety window
+        let short = FilterBoundaries::with_lookback(now, Some(Duration::hours(1))).unwrap();
+        assert_eq!(short.cutoff_timestamp, default.cutoff_timestamp);
+    }
 }
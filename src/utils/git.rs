@@ -1,23 +1,503 @@
-use std::path::Path;
+use crate::config;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use tokio::fs as async_fs;
+use tokio::task;
 
-// Get git branch
+/// Repositories with more tracked files than this skip the dirty check,
+/// which has to stat every tracked file
+const MAX_DIRTY_CHECK_ENTRIES: usize = 20_000;
+
+/// Mode of a gitlink (submodule) index entry, which has no file to compare
+const GITLINK_MODE: u32 = 0o160000;
+
+/// Mode of a sparse directory entry, which stands for a whole directory
+/// outside the sparse checkout
+const SPARSE_DIR_MODE: u32 = 0o040000;
+
+/// Flag marking an extended entry, which carries a second flags word
+const CE_EXTENDED: u16 = 0x4000;
+
+/// Extended flag for entries outside the sparse checkout, whose files are
+/// intentionally absent from the working tree
+const CE_SKIP_WORKTREE: u16 = 0x4000;
+
+/// Branch and working tree state of a repository
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    /// Tracked files differ from the index, or a merge is in progress
+    pub dirty: bool,
+}
+
+// Get branch and dirty state, unless disabled with CLAUDE_CCR_NO_GIT=1
+pub async fn get_git_status(cwd: &Path) -> GitStatus {
+    get_git_status_with(cwd, !config::env_flag("CLAUDE_CCR_NO_GIT")).await
+}
+
+// Get branch and dirty state when enabled
+// The dirty check compares the index's cached size and mtime of each tracked
+// file with the working tree, like `git diff` does before reading contents;
+// it does not look at untracked files
+pub async fn get_git_status_with(cwd: &Path, enabled: bool) -> GitStatus {
+    if !enabled {
+        return GitStatus::default();
+    }
+    let Some(git_dir) = resolve_git_dir(cwd).await else {
+        return GitStatus::default();
+    };
+    let Some(branch) = read_head_branch(&git_dir).await else {
+        return GitStatus::default();
+    };
+
+    let work_tree = cwd.to_path_buf();
+    let dirty = task::spawn_blocking(move || is_dirty(&work_tree, &git_dir))
+        .await
+        .unwrap_or(false);
+    GitStatus {
+        branch: Some(branch),
+        dirty,
+    }
+}
+
+// Get git branch, unless disabled with CLAUDE_CCR_NO_GIT=1
 pub async fn get_git_branch(cwd: &Path) -> Option<String> {
-    let head_path = cwd.join(".git").join("HEAD");
+    get_git_branch_with(cwd, !config::env_flag("CLAUDE_CCR_NO_GIT")).await
+}
+
+// Get git branch when enabled; returns early without any I/O otherwise
+pub async fn get_git_branch_with(cwd: &Path, enabled: bool) -> Option<String> {
+    if !enabled {
+        return None;
+    }
+    read_head_branch(&resolve_git_dir(cwd).await?).await
+}
+
+// Find the git directory of a work tree
+// `.git` is usually a directory, but in linked worktrees and submodules it is
+// a file pointing elsewhere (`gitdir: <path>`, relative to the work tree);
+// that directory has its own HEAD and index
+async fn resolve_git_dir(work_tree: &Path) -> Option<PathBuf> {
+    let dot_git = work_tree.join(".git");
+    let metadata = async_fs::metadata(&dot_git).await.ok()?;
+    if metadata.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = async_fs::read_to_string(&dot_git).await.ok()?;
+    let pointer = content.lines().next()?.strip_prefix("gitdir:")?.trim();
+    if pointer.is_empty() {
+        return None;
+    }
+    Some(work_tree.join(pointer))
+}
+
+// Branch name from a git directory's HEAD, or the short hash when detached
+async fn read_head_branch(git_dir: &Path) -> Option<String> {
+    let content = async_fs::read_to_string(git_dir.join("HEAD")).await.ok()?;
+    let trimmed = content.trim();
 
-    if let Ok(content) = async_fs::read_to_string(&head_path).await {
-        let trimmed = content.trim();
+    // Parse ref format
+    if let Some(branch) = trimmed.strip_prefix("ref: refs/heads/") {
+        return Some(branch.to_string());
+    }
 
-        // Parse ref format
-        if let Some(branch) = trimmed.strip_prefix("ref: refs/heads/") {
-            return Some(branch.to_string());
+    // Detached HEAD - return short hash
+    if trimmed.len() >= 7 && !trimmed.starts_with("ref:") {
+        return Some(trimmed[..7].to_string());
+    }
+
+    None
+}
+
+/// Check a work tree for uncommitted changes against its git directory
+fn is_dirty(work_tree: &Path, git_dir: &Path) -> bool {
+    if git_dir.join("MERGE_HEAD").exists() {
+        return true;
+    }
+    let Ok(index) = fs::read(git_dir.join("index")) else {
+        return false;
+    };
+    let Some(entries) = parse_index(&index) else {
+        return false;
+    };
+    if entries.len() > MAX_DIRTY_CHECK_ENTRIES {
+        return false;
+    }
+
+    entries.iter().any(|entry| entry.is_modified_in(work_tree))
+}
+
+/// The parts of a git index entry needed to spot a changed file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexEntry {
+    path: PathBuf,
+    mtime_secs: u32,
+    mtime_nanos: u32,
+    /// File size truncated to 32 bits, as git stores it
+    size: u32,
+    mode: u32,
+    /// Merge stage; nonzero while a conflict is unresolved
+    stage: u16,
+    /// Not checked out, so the working tree has nothing to compare
+    skip_worktree: bool,
+}
+
+impl IndexEntry {
+    fn is_modified_in(&self, work_tree: &Path) -> bool {
+        if self.stage != 0 {
+            return true;
         }
+        if self.skip_worktree || self.mode == GITLINK_MODE || self.mode == SPARSE_DIR_MODE {
+            return false;
+        }
+        let Ok(metadata) = fs::symlink_metadata(work_tree.join(&self.path)) else {
+            // Deleted from the working tree
+            return true;
+        };
+        let Some(modified) = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        else {
+            return false;
+        };
+
+        // Nanoseconds are only compared when git recorded them
+        metadata.len() as u32 != self.size
+            || modified.as_secs() as u32 != self.mtime_secs
+            || (self.mtime_nanos != 0 && modified.subsec_nanos() != self.mtime_nanos)
+    }
+}
+
+/// Parse the entries of a version 2, 3 or 4 index file
+/// None for anything unexpected, so a corrupt index never reports dirty
+fn parse_index(data: &[u8]) -> Option<Vec<IndexEntry>> {
+    if data.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = read_u32(data, 4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = read_u32(data, 8)? as usize;
 
-        // Detached HEAD - return short hash
-        if trimmed.len() >= 7 && !trimmed.starts_with("ref:") {
-            return Some(trimmed[..7].to_string());
+    let mut entries = Vec::with_capacity(count.min(MAX_DIRTY_CHECK_ENTRIES + 1));
+    let mut offset = 12;
+    let mut previous_path: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        let start = offset;
+        let mtime_secs = read_u32(data, start + 8)?;
+        let mtime_nanos = read_u32(data, start + 12)?;
+        let mode = read_u32(data, start + 24)?;
+        let size = read_u32(data, start + 36)?;
+        let flags = u16::from_be_bytes(data.get(start + 60..start + 62)?.try_into().ok()?);
+        offset = start + 62;
+        // Version 3 and later add a second flags word to extended entries
+        let mut extended_flags = 0;
+        if version >= 3 && flags & CE_EXTENDED != 0 {
+            extended_flags = u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+            offset += 2;
         }
+
+        let path = if version == 4 {
+            // Prefix-compressed: drop N bytes of the previous path, then
+            // append a NUL-terminated suffix; no padding follows
+            let (strip, varint_len) = read_varint(data.get(offset..)?)?;
+            offset += varint_len;
+            let suffix_len = data.get(offset..)?.iter().position(|b| *b == 0)?;
+            let keep = previous_path.len().checked_sub(strip)?;
+            let mut path = previous_path[..keep].to_vec();
+            path.extend_from_slice(&data[offset..offset + suffix_len]);
+            offset += suffix_len + 1;
+            path
+        } else {
+            let path_len = data.get(offset..)?.iter().position(|b| *b == 0)?;
+            let path = data[offset..offset + path_len].to_vec();
+            // Entries are NUL-padded to a multiple of eight bytes
+            let entry_len = offset + path_len - start;
+            offset = start + (entry_len + 8) / 8 * 8;
+            path
+        };
+
+        entries.push(IndexEntry {
+            path: PathBuf::from(String::from_utf8_lossy(&path).into_owned()),
+            mtime_secs,
+            mtime_nanos,
+            size,
+            mode,
+            stage: (flags >> 12) & 0x3,
+            skip_worktree: extended_flags & CE_SKIP_WORKTREE != 0,
+        });
+        previous_path = path;
     }
 
-    None
+    Some(entries)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Git's offset varint used by index version 4; returns the value and its length
+fn read_varint(data: &[u8]) -> Option<(usize, usize)> {
+    let mut byte = *data.first()?;
+    let mut value = (byte & 0x7f) as usize;
+    let mut len = 1;
+    while byte & 0x80 != 0 {
+        byte = *data.get(len)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+        len += 1;
+    }
+    Some((value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_repo(head: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git").join("HEAD"), head).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_get_git_branch() {
+        let repo = create_repo("ref: refs/heads/main\n");
+        assert_eq!(
+            get_git_branch_with(repo.path(), true).await,
+            Some("main".to_string())
+        );
+
+        let detached = create_repo("0123456789abcdef\n");
+        assert_eq!(
+            get_git_branch_with(detached.path(), true).await,
+            Some("0123456".to_string())
+        );
+
+        let not_repo = tempfile::tempdir().unwrap();
+        assert_eq!(get_git_branch_with(not_repo.path(), true).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_git_branch_from_gitdir_file() {
+        // A linked worktree: `.git` is a file pointing at the worktree's gitdir
+        let dir = tempfile::tempdir().unwrap();
+        let git_dir = dir
+            .path()
+            .join("main")
+            .join(".git")
+            .join("worktrees")
+            .join("feature");
+        fs::create_dir_all(&git_dir).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/feature\n").unwrap();
+
+        let worktree = dir.path().join("feature");
+        fs::create_dir(&worktree).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", git_dir.display()),
+        )
+        .unwrap();
+        assert_eq!(
+            get_git_branch_with(&worktree, true).await,
+            Some("feature".to_string())
+        );
+
+        // Submodules point at their gitdir with a relative path
+        let submodule = dir.path().join("main").join("vendor");
+        fs::create_dir_all(&submodule).unwrap();
+        fs::write(
+            submodule.join(".git"),
+            "gitdir: ../.git/worktrees/feature\n",
+        )
+        .unwrap();
+        assert_eq!(
+            get_git_status_with(&submodule, true).await.branch,
+            Some("feature".to_string())
+        );
+
+        // A broken pointer is not a repository
+        fs::write(worktree.join(".git"), "not a pointer\n").unwrap();
+        assert_eq!(get_git_branch_with(&worktree, true).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_git_branch_disabled() {
+        // Disabled lookup returns None even inside a repository
+        let repo = create_repo("ref: refs/heads/main\n");
+        assert_eq!(get_git_branch_with(repo.path(), false).await, None);
+    }
+
+    /// Index entry bytes for a path with the given stat data
+    fn index_entry(path: &str, mtime: (u32, u32), size: u32, version: u32) -> Vec<u8> {
+        index_entry_with(path, mtime, size, version, 0o100644, None)
+    }
+
+    /// Index entry bytes with an explicit mode and, for version 3 and later,
+    /// an optional extended flags word
+    fn index_entry_with(
+        path: &str,
+        mtime: (u32, u32),
+        size: u32,
+        version: u32,
+        mode: u32,
+        extended_flags: Option<u16>,
+    ) -> Vec<u8> {
+        let mut entry = Vec::new();
+        for field in [0, 0, mtime.0, mtime.1, 0, 0, mode, 0, 0, size] {
+            entry.extend_from_slice(&u32::to_be_bytes(field));
+        }
+        entry.extend_from_slice(&[0; 20]);
+        let flags = path.len() as u16 | extended_flags.map_or(0, |_| CE_EXTENDED);
+        entry.extend_from_slice(&flags.to_be_bytes());
+        if let Some(extended_flags) = extended_flags {
+            entry.extend_from_slice(&extended_flags.to_be_bytes());
+        }
+        if version == 4 {
+            // No prefix shared with the previous path
+            entry.push(0);
+        }
+        entry.extend_from_slice(path.as_bytes());
+        entry.push(0);
+        if version != 4 {
+            while entry.len() % 8 != 0 {
+                entry.push(0);
+            }
+        }
+        entry
+    }
+
+    fn write_index(repo: &Path, version: u32, entries: &[Vec<u8>]) {
+        let mut index = b"DIRC".to_vec();
+        index.extend_from_slice(&version.to_be_bytes());
+        index.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for entry in entries {
+            index.extend_from_slice(entry);
+        }
+        fs::write(repo.join(".git").join("index"), index).unwrap();
+    }
+
+    fn stat(path: &Path) -> ((u32, u32), u32) {
+        let metadata = fs::metadata(path).unwrap();
+        let modified = metadata
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap();
+        (
+            (modified.as_secs() as u32, modified.subsec_nanos()),
+            metadata.len() as u32,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_git_status_dirty() {
+        let repo = create_repo("ref: refs/heads/main\n");
+        let file = repo.path().join("notes.txt");
+        fs::write(&file, "hello").unwrap();
+        let (mtime, size) = stat(&file);
+
+        for version in [2, 4] {
+            write_index(
+                repo.path(),
+                version,
+                &[index_entry("notes.txt", mtime, size, version)],
+            );
+            assert_eq!(
+                get_git_status_with(repo.path(), true).await,
+                GitStatus {
+                    branch: Some("main".to_string()),
+                    dirty: false,
+                }
+            );
+        }
+
+        // Changed size
+        fs::write(&file, "hello, world").unwrap();
+        assert!(get_git_status_with(repo.path(), true).await.dirty);
+
+        // Tracked file deleted from the working tree
+        write_index(repo.path(), 2, &[index_entry("gone.txt", mtime, size, 2)]);
+        assert!(get_git_status_with(repo.path(), true).await.dirty);
+
+        // Clean index, but a merge in progress
+        let (mtime, size) = stat(&file);
+        write_index(repo.path(), 2, &[index_entry("notes.txt", mtime, size, 2)]);
+        assert!(!get_git_status_with(repo.path(), true).await.dirty);
+        fs::write(repo.path().join(".git").join("MERGE_HEAD"), "0123456\n").unwrap();
+        assert!(get_git_status_with(repo.path(), true).await.dirty);
+
+        assert_eq!(
+            get_git_status_with(repo.path(), false).await,
+            GitStatus::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_index_prefix_compressed_paths() {
+        let mut index = b"DIRC".to_vec();
+        index.extend_from_slice(&4u32.to_be_bytes());
+        index.extend_from_slice(&2u32.to_be_bytes());
+        index.extend_from_slice(&index_entry("src/lib.rs", (1, 0), 10, 4));
+        // "src/main.rs" shares "src/" with the previous path: strip 6 bytes
+        let mut second = index_entry("main.rs", (2, 0), 20, 4);
+        second[62] = 6;
+        index.extend_from_slice(&second);
+
+        let entries = parse_index(&index).unwrap();
+        let paths: Vec<_> = entries.iter().map(|entry| entry.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+        );
+        assert_eq!(entries[1].size, 20);
+
+        // Truncated or foreign data is not an index
+        assert!(parse_index(&index[..index.len() - 4]).is_none());
+        assert!(parse_index(b"PACK").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_git_status_ignores_skip_worktree_entries() {
+        let repo = create_repo("ref: refs/heads/main\n");
+        let file = repo.path().join("notes.txt");
+        fs::write(&file, "hello").unwrap();
+        let (mtime, size) = stat(&file);
+
+        // Outside the sparse checkout: a missing file and a sparse directory
+        let skip = Some(CE_SKIP_WORKTREE);
+        let entries = [
+            index_entry("notes.txt", mtime, size, 3),
+            index_entry_with("docs/guide.md", (1, 0), 10, 3, 0o100644, skip),
+            index_entry_with("vendor/", (1, 0), 0, 3, SPARSE_DIR_MODE, skip),
+        ];
+        write_index(repo.path(), 3, &entries);
+
+        let parsed =
+            parse_index(&fs::read(repo.path().join(".git").join("index")).unwrap()).unwrap();
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|entry| entry.skip_worktree)
+                .collect::<Vec<_>>(),
+            vec![false, true, true]
+        );
+        assert_eq!(parsed[2].path, PathBuf::from("vendor/"));
+        assert!(!get_git_status_with(repo.path(), true).await.dirty);
+
+        // The same missing file without the flag is a deletion
+        write_index(
+            repo.path(),
+            3,
+            &[index_entry("docs/guide.md", (1, 0), 10, 3)],
+        );
+        assert!(get_git_status_with(repo.path(), true).await.dirty);
+    }
 }
@@ -1,9 +1,25 @@
+pub mod cache;
 pub mod data_loader;
 pub mod git;
 pub mod paths;
+pub mod pricing_loader;
+pub mod thread_pool;
 pub mod transcript_loader;
 
-pub use data_loader::load_all_data;
-pub use git::get_git_branch;
-pub use paths::get_claude_paths;
-pub use transcript_loader::load_transcript_usage;
+pub use cache::{CacheFormat, FileKey, load_file_cache, prune_file_cache, save_file_cache};
+pub use data_loader::{
+    LoadOptions, entry_cache_dir, load_all_data, load_all_data_with, load_jsonl_file,
+    parse_and_cost,
+};
+pub use git::{
+    GitStatus, get_git_branch, get_git_branch_with, get_git_status, get_git_status_with,
+};
+pub use paths::{
+    get_claude_paths, get_claude_paths_with, has_claude_data, has_claude_data_in, projects_dir,
+    projects_dir_with,
+};
+pub use pricing_loader::{
+    LITELLM_PRICING_URL, load_remote_pricing, pricing_cache_path, remote_pricing_from_env,
+};
+pub use thread_pool::init_global_thread_pool;
+pub use transcript_loader::{hook_transcript_usage, load_transcript_usage};
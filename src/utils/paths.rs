@@ -1,13 +1,47 @@
+use crate::config;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default name of the directory holding per-project session logs
+const DEFAULT_PROJECTS_SUBDIR: &str = "projects";
+
+/// Locations some Claude Code versions use instead of `projects`
+const ALTERNATE_PROJECTS_SUBDIRS: &[&str] = &["usage/projects", "statsig/projects"];
 
 // Get Claude paths
+// Directories listed in CLAUDE_CONFIG_DIR win; otherwise the known locations
+// under HOME, XDG_CONFIG_HOME and APPDATA are probed
 pub fn get_claude_paths() -> Vec<PathBuf> {
-    let mut paths = Vec::new();
+    get_claude_paths_with(
+        config::env_var("CLAUDE_CONFIG_DIR").as_deref(),
+        env::var_os("HOME").map(PathBuf::from).as_deref(),
+        config::env_var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .as_deref(),
+        env::var_os("APPDATA").map(PathBuf::from).as_deref(),
+    )
+}
 
-    if let Ok(home) = env::var("HOME") {
-        let home_path = PathBuf::from(home);
+// Get Claude paths from explicit environment values
+// `config_dirs` is a path list like PATH (colon-separated on Unix); when none
+// of its entries exist the usual locations are used instead
+pub fn get_claude_paths_with(
+    config_dirs: Option<&str>,
+    home: Option<&Path>,
+    xdg_config_home: Option<&Path>,
+    appdata: Option<&Path>,
+) -> Vec<PathBuf> {
+    let configured: Vec<PathBuf> = config_dirs
+        .map(|dirs| env::split_paths(dirs).filter(|p| p.exists()).collect())
+        .unwrap_or_default();
+    if !configured.is_empty() {
+        return configured;
+    }
 
+    let mut paths = Vec::new();
+
+    if let Some(home_path) = home {
         // Primary path
         paths.push(home_path.join(".claude"));
 
@@ -19,10 +53,164 @@ pub fn get_claude_paths() -> Vec<PathBuf> {
         paths.push(home_path.join(".local/share/Claude"));
     }
 
+    // XDG config path used by newer Claude Code versions
+    match xdg_config_home {
+        Some(xdg) => paths.push(xdg.join("claude")),
+        None => paths.extend(home.map(|home_path| home_path.join(".config/claude"))),
+    }
+
     // Windows paths
-    if let Ok(appdata) = env::var("APPDATA") {
-        paths.push(PathBuf::from(appdata).join("Claude"));
+    if let Some(appdata) = appdata {
+        paths.push(appdata.join("Claude"));
+    }
+
+    let mut existing: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        // ~/.config/claude and ~/.config/Claude are one directory on
+        // case-insensitive file systems
+        let duplicate = existing.iter().any(|seen| same_file(seen, &path));
+        if path.exists() && !duplicate {
+            existing.push(path);
+        }
+    }
+    existing
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Find the projects directory under a Claude path
+// Uses CLAUDE_CCR_PROJECTS_SUBDIR (default `projects`) when it exists there,
+// otherwise the first known alternate layout that does
+pub fn projects_dir(base_path: &Path) -> PathBuf {
+    projects_dir_with(
+        base_path,
+        config::env_var("CLAUDE_CCR_PROJECTS_SUBDIR").as_deref(),
+    )
+}
+
+// Find the projects directory with an explicit subdirectory name
+// Falls back to the preferred path when nothing exists, so callers report
+// problems against the configured location
+pub fn projects_dir_with(base_path: &Path, subdir: Option<&str>) -> PathBuf {
+    let preferred = base_path.join(subdir.unwrap_or(DEFAULT_PROJECTS_SUBDIR));
+    if preferred.is_dir() {
+        return preferred;
+    }
+
+    std::iter::once(DEFAULT_PROJECTS_SUBDIR)
+        .chain(ALTERNATE_PROJECTS_SUBDIRS.iter().copied())
+        .map(|alternate| base_path.join(alternate))
+        .find(|path| path.is_dir())
+        .unwrap_or(preferred)
+}
+
+// Check whether any discovered Claude path has usage data to show
+// Much cheaper than load_all_data: stops at the first JSONL file found
+pub fn has_claude_data() -> bool {
+    has_claude_data_in(&get_claude_paths())
+}
+
+// Check whether any of the given Claude paths has a projects/<dir>/*.jsonl file
+pub fn has_claude_data_in(claude_paths: &[PathBuf]) -> bool {
+    claude_paths
+        .iter()
+        .any(|base_path| projects_has_jsonl(&projects_dir(base_path)))
+}
+
+fn projects_has_jsonl(projects_path: &Path) -> bool {
+    let Ok(project_dirs) = fs::read_dir(projects_path) else {
+        return false;
+    };
+
+    project_dirs.filter_map(|entry| entry.ok()).any(|project| {
+        fs::read_dir(project.path()).is_ok_and(|files| {
+            files
+                .filter_map(|entry| entry.ok())
+                .any(|file| file.file_name().to_string_lossy().ends_with(".jsonl"))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_claude_data_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        assert!(!has_claude_data_in(std::slice::from_ref(&root)));
+
+        // Projects without JSONL files have nothing to show
+        let project = root.join("projects").join("-work-ccr");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("notes.txt"), "").unwrap();
+        assert!(!has_claude_data_in(std::slice::from_ref(&root)));
+
+        fs::write(project.join("session.jsonl"), "").unwrap();
+        assert!(has_claude_data_in(std::slice::from_ref(&root)));
+        assert!(!has_claude_data_in(&[]));
+    }
+
+    #[test]
+    fn test_get_claude_paths_with_config_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        let volume = dir.path().join("volume");
+        let other = dir.path().join("other");
+        fs::create_dir_all(home.join(".claude")).unwrap();
+        fs::create_dir_all(&volume).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        // Existing CLAUDE_CONFIG_DIR entries replace the usual locations
+        let missing = dir.path().join("missing");
+        let config_dirs = env::join_paths([&volume, &missing, &other]).unwrap();
+        assert_eq!(
+            get_claude_paths_with(config_dirs.to_str(), Some(&home), None, None),
+            vec![volume.clone(), other]
+        );
+
+        // Nothing configured exists: fall back to HOME
+        assert_eq!(
+            get_claude_paths_with(missing.to_str(), Some(&home), None, None),
+            vec![home.join(".claude")]
+        );
+
+        // XDG config directory
+        let xdg = dir.path().join("xdg");
+        fs::create_dir_all(xdg.join("claude")).unwrap();
+        assert_eq!(
+            get_claude_paths_with(None, Some(&home), Some(&xdg), None),
+            vec![home.join(".claude"), xdg.join("claude")]
+        );
+        assert!(get_claude_paths_with(None, None, None, None).is_empty());
     }
 
-    paths.into_iter().filter(|p| p.exists()).collect()
+    #[test]
+    fn test_projects_dir_alternates() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        // Nothing exists: the preferred location is returned
+        assert_eq!(projects_dir_with(root, None), root.join("projects"));
+        assert_eq!(projects_dir_with(root, Some("logs")), root.join("logs"));
+
+        // Projects nested under an alternate subdirectory are found
+        let nested = root.join("usage").join("projects");
+        fs::create_dir_all(nested.join("-work-ccr")).unwrap();
+        fs::write(nested.join("-work-ccr").join("session.jsonl"), "").unwrap();
+        assert_eq!(projects_dir_with(root, None), nested);
+        assert!(projects_has_jsonl(&projects_dir_with(root, None)));
+
+        // The default and configured names win over alternates
+        fs::create_dir_all(root.join("projects")).unwrap();
+        assert_eq!(projects_dir_with(root, None), root.join("projects"));
+        fs::create_dir_all(root.join("logs")).unwrap();
+        assert_eq!(projects_dir_with(root, Some("logs")), root.join("logs"));
+    }
 }
@@ -0,0 +1,212 @@
+use crate::config;
+use crate::error::{CcrError, Result};
+use crate::types::ModelPricing;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// LiteLLM's community-maintained price list
+pub const LITELLM_PRICING_URL: &str =
+    "https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json";
+
+/// How long a cached price list is used before it is fetched again
+const PRICING_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Give up on the fetch quickly, as the statusline waits for it
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// After a failed fetch, wait this long before trying again, so an offline
+/// machine does not pay the fetch timeout on every render
+const FETCH_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+impl ModelPricing {
+    /// Fetch and parse a LiteLLM-format pricing JSON
+    pub fn load_from_litellm(url: &str) -> Result<HashMap<String, ModelPricing>> {
+        let fetch_error = |source| CcrError::PricingFetch {
+            url: url.to_string(),
+            source: Box::new(source),
+        };
+        let response = ureq::get(url)
+            .timeout(FETCH_TIMEOUT)
+            .call()
+            .map_err(fetch_error)?;
+
+        // The list is larger than ureq's default string limit
+        let mut json = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut json)
+            .map_err(|source| fetch_error(ureq::Error::from(source)))?;
+
+        ModelPricing::parse_litellm(&json).map_err(|source| CcrError::JsonParse {
+            context: url.to_string(),
+            source,
+        })
+    }
+}
+
+/// Default location of the cached price list, `~/.cache/ccr/pricing.json`
+pub fn pricing_cache_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".cache/ccr/pricing.json"))
+}
+
+/// Remote pricing table when enabled with CCR_REMOTE_PRICING
+/// The URL defaults to LiteLLM's list and can be changed with CCR_PRICING_URL
+pub fn remote_pricing_from_env() -> Option<HashMap<String, ModelPricing>> {
+    if !config::env_flag("CCR_REMOTE_PRICING") {
+        return None;
+    }
+    let url = config::env_var("CCR_PRICING_URL").unwrap_or_else(|| LITELLM_PRICING_URL.to_string());
+    load_remote_pricing(
+        &url,
+        &pricing_cache_path()?,
+        PRICING_CACHE_TTL,
+        SystemTime::now(),
+    )
+}
+
+/// Pricing table from the cache if younger than `ttl`, otherwise fetched
+/// and cached again
+///
+/// When the fetch fails (e.g. offline) a stale cache is still used; None
+/// means the built-in prices apply. A failed fetch is recorded next to the
+/// cache and not retried for an hour.
+pub fn load_remote_pricing(
+    url: &str,
+    cache_path: &Path,
+    ttl: Duration,
+    now: SystemTime,
+) -> Option<HashMap<String, ModelPricing>> {
+    let cached = read_cached_pricing(cache_path);
+    if let Some((modified, table)) = &cached
+        && now.duration_since(*modified).is_ok_and(|age| age < ttl)
+    {
+        return Some(table.clone());
+    }
+
+    let failure_marker = failure_marker_path(cache_path);
+    let recently_failed = fs::metadata(&failure_marker)
+        .and_then(|m| m.modified())
+        .is_ok_and(|failed| {
+            now.duration_since(failed)
+                .is_ok_and(|age| age < FETCH_RETRY_BACKOFF)
+        });
+    if recently_failed {
+        return cached.map(|(_, table)| table);
+    }
+
+    match ModelPricing::load_from_litellm(url) {
+        Ok(table) => {
+            // A cache that cannot be written only costs a fetch next time
+            let _ = write_cached_pricing(cache_path, &table);
+            let _ = fs::remove_file(&failure_marker);
+            Some(table)
+        }
+        Err(_) => {
+            let _ = write_failure_marker(&failure_marker, now);
+            cached.map(|(_, table)| table)
+        }
+    }
+}
+
+/// File recording the time of the last failed fetch, e.g. `pricing.json.failed`
+fn failure_marker_path(cache_path: &Path) -> PathBuf {
+    let mut path = cache_path.as_os_str().to_owned();
+    path.push(".failed");
+    PathBuf::from(path)
+}
+
+fn write_failure_marker(path: &Path, now: SystemTime) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::File::create(path)?.set_modified(now)
+}
+
+fn read_cached_pricing(path: &Path) -> Option<(SystemTime, HashMap<String, ModelPricing>)> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let json = fs::read_to_string(path).ok()?;
+    let table = serde_json::from_str(&json).ok()?;
+    Some((modified, table))
+}
+
+fn write_cached_pricing(path: &Path, table: &HashMap<String, ModelPricing>) -> Result<()> {
+    let write_error = |source| CcrError::FileWrite {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(write_error)?;
+    }
+    let json = serde_json::to_string(table).map_err(|source| CcrError::JsonSerialize {
+        context: path.display().to_string(),
+        source,
+    })?;
+    fs::write(path, json).map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(input: f64) -> HashMap<String, ModelPricing> {
+        HashMap::from([(
+            "claude-test".to_string(),
+            ModelPricing {
+                input_cost_per_token: input,
+                output_cost_per_token: 0.0,
+                cache_creation_input_token_cost: 0.0,
+                cache_read_input_token_cost: 0.0,
+                cache_creation_1h_token_cost: 0.0,
+            },
+        )])
+    }
+
+    #[test]
+    fn test_remote_pricing_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("ccr").join("pricing.json");
+        // Nothing listens here, so every fetch fails at once
+        let unreachable = "http://127.0.0.1:9/pricing.json";
+        let ttl = Duration::from_secs(60);
+
+        // No cache and no network: built-in prices
+        assert!(load_remote_pricing(unreachable, &cache_path, ttl, SystemTime::now()).is_none());
+
+        write_cached_pricing(&cache_path, &table(1e-6)).unwrap();
+        let fresh = load_remote_pricing(unreachable, &cache_path, ttl, SystemTime::now()).unwrap();
+        assert_eq!(fresh["claude-test"].input_cost_per_token, 1e-6);
+
+        // Past the TTL the fetch is retried, and on failure the stale copy is kept
+        let later = SystemTime::now() + Duration::from_secs(3600);
+        let stale = load_remote_pricing(unreachable, &cache_path, ttl, later).unwrap();
+        assert_eq!(stale, fresh);
+    }
+
+    #[test]
+    fn test_failed_fetch_backs_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("pricing.json");
+        let marker = failure_marker_path(&cache_path);
+        let unreachable = "http://127.0.0.1:9/pricing.json";
+        let ttl = Duration::from_secs(60);
+        let now = SystemTime::now();
+
+        assert!(load_remote_pricing(unreachable, &cache_path, ttl, now).is_none());
+        let failed = fs::metadata(&marker).unwrap().modified().unwrap();
+        assert_eq!(failed, now);
+
+        // Within the backoff the fetch is skipped, so the marker is not rewritten
+        let soon = now + Duration::from_secs(60);
+        assert!(load_remote_pricing(unreachable, &cache_path, ttl, soon).is_none());
+        assert_eq!(fs::metadata(&marker).unwrap().modified().unwrap(), now);
+
+        // Afterwards the fetch is tried and fails again
+        let later = now + FETCH_RETRY_BACKOFF + Duration::from_secs(1);
+        assert!(load_remote_pricing(unreachable, &cache_path, ttl, later).is_none());
+        assert_eq!(fs::metadata(&marker).unwrap().modified().unwrap(), later);
+    }
+}
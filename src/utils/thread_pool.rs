@@ -0,0 +1,45 @@
+use crate::error::CcrError;
+use std::error::Error;
+
+/// Initialize the global rayon thread pool
+///
+/// If the global pool is already initialized (e.g. by a host application that
+/// uses ccr as a library), a warning is printed and the existing pool is used
+/// instead of aborting. Returns true if this call initialized the pool.
+pub fn init_global_thread_pool(num_threads: usize) -> bool {
+    let result = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("ccr-worker-{}", i))
+        .build_global();
+
+    match result {
+        Ok(()) => true,
+        Err(source) => {
+            let err = CcrError::ThreadPoolInit(source);
+            let reason = err.source().map(|s| s.to_string()).unwrap_or_default();
+            eprintln!(
+                "ccr: warning: {} ({}), continuing with the existing pool",
+                err, reason
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_init_does_not_abort() {
+        // The first call may already fail if another test touched rayon,
+        // but neither call is allowed to panic or return an error
+        let _ = init_global_thread_pool(2);
+        assert!(!init_global_thread_pool(2));
+
+        // The existing pool remains usable
+        use rayon::prelude::*;
+        let sum: u32 = (1..=10u32).into_par_iter().sum();
+        assert_eq!(sum, 55);
+    }
+}
@@ -1,19 +1,75 @@
-use crate::types::{TranscriptMessage, TranscriptUsage};
+use crate::types::{StatuslineHookJson, TranscriptMessage, TranscriptUsage};
+use std::future::Future;
+use std::io;
 use std::path::Path;
+use std::time::Duration;
 use tokio::fs as async_fs;
 
+/// Total read attempts before giving up on a transient error
+const READ_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BACKOFF: Duration = Duration::from_millis(20);
+
 /// Load the latest transcript usage from a transcript file
 /// This function handles the I/O and parsing, returning just the usage data
 pub async fn load_transcript_usage(transcript_path: &Path) -> Option<TranscriptUsage> {
-    // Try to read the file
-    let Ok(content) = async_fs::read_to_string(transcript_path).await else {
+    // Try to read the file, retrying briefly on network filesystem hiccups
+    let Ok(content) = read_with_retry(|| async_fs::read_to_string(transcript_path)).await else {
         return None;
     };
 
-    // Parse JSONL lines from last to first (most recent usage info)
-    let lines: Vec<&str> = content.lines().rev().collect();
+    latest_usage(&content)
+}
+
+/// Latest usage for a hook payload
+/// Inline `transcript_usage` wins when the host provides it; otherwise the
+/// transcript file is read as for current Claude Code versions
+pub async fn hook_transcript_usage(hook_data: &StatuslineHookJson) -> Option<TranscriptUsage> {
+    match hook_data.transcript_usage {
+        Some(ref usage) => Some(usage.clone()),
+        None => load_transcript_usage(Path::new(&hook_data.transcript_path)).await,
+    }
+}
+
+/// Errors worth retrying: the file exists but could not be read right now
+/// (EAGAIN, ETIMEDOUT, EINTR); anything else such as NotFound fails fast
+fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+    )
+}
 
-    for line in lines {
+/// Run a read up to READ_ATTEMPTS times, backing off between transient failures
+async fn read_with_retry<F, Fut>(mut read: F) -> io::Result<String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<String>>,
+{
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match read().await {
+            Err(error) if attempt < READ_ATTEMPTS && is_transient(&error) => {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Find the most recent assistant usage in transcript contents
+/// Main-thread messages win over sidechain (subagent) messages, whose usage
+/// does not reflect the conversation's context; a sidechain usage is only
+/// returned when there is no main-thread one
+fn latest_usage(content: &str) -> Option<TranscriptUsage> {
+    let mut sidechain_usage = None;
+
+    // Parse JSONL lines from last to first (most recent usage info)
+    for line in content.lines().rev() {
         let trimmed = line.trim();
         if trimmed.is_empty() {
             continue;
@@ -27,11 +83,132 @@ pub async fn load_transcript_usage(transcript_path: &Path) -> Option<TranscriptU
                 && let Some(usage) = message.usage
                 && usage.input_tokens.is_some()
             {
-                return Some(usage);
+                if msg.is_sidechain != Some(true) {
+                    return Some(usage);
+                }
+                sidechain_usage.get_or_insert(usage);
             }
         }
     }
 
-    // No valid usage information found
-    None
+    // No main-thread usage information found
+    sidechain_usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Read source failing with the given errors before returning contents
+    async fn run_reads(errors: &[io::ErrorKind]) -> (io::Result<String>, usize) {
+        let calls = Cell::new(0);
+        let result = read_with_retry(|| {
+            let call = calls.get();
+            calls.set(call + 1);
+            let result = match errors.get(call) {
+                Some(&kind) => Err(io::Error::from(kind)),
+                None => Ok("contents".to_string()),
+            };
+            async move { result }
+        })
+        .await;
+        (result, calls.get())
+    }
+
+    #[tokio::test]
+    async fn test_hook_transcript_usage_prefers_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":100}}}"#,
+        )
+        .unwrap();
+        let hook = |extra: &str| -> StatuslineHookJson {
+            serde_json::from_str(&format!(
+                r#"{{"session_id":"s","cwd":"/tmp","model":{{"display_name":"Opus"}}{}}}"#,
+                extra
+            ))
+            .unwrap()
+        };
+        let path_field = format!(r#","transcript_path":{:?}"#, path.to_str().unwrap());
+
+        // Inline usage is used even though the path is missing or points elsewhere
+        let inline = hook(r#","transcript_usage":{"input_tokens":42,"cache_read_input_tokens":8}"#);
+        let usage = hook_transcript_usage(&inline).await.unwrap();
+        assert_eq!(usage.input_tokens, Some(42));
+        assert_eq!(usage.cache_read_input_tokens, Some(8));
+
+        let both = hook(&format!(
+            r#"{},"transcript_usage":{{"input_tokens":42}}"#,
+            path_field
+        ));
+        assert_eq!(
+            hook_transcript_usage(&both).await.unwrap().input_tokens,
+            Some(42)
+        );
+
+        // Without inline usage the transcript file is read
+        let path_only = hook(&path_field);
+        assert_eq!(
+            hook_transcript_usage(&path_only)
+                .await
+                .unwrap()
+                .input_tokens,
+            Some(100)
+        );
+        assert!(hook_transcript_usage(&hook("")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_with_retry_recovers_from_transient_errors() {
+        let (result, calls) =
+            run_reads(&[io::ErrorKind::WouldBlock, io::ErrorKind::TimedOut]).await;
+        assert_eq!(result.unwrap(), "contents");
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_read_with_retry_gives_up() {
+        // Bounded: the third transient failure is returned
+        let (result, calls) = run_reads(&[io::ErrorKind::TimedOut; 5]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(calls, READ_ATTEMPTS as usize);
+
+        // Permanent errors are not retried
+        let (result, calls) = run_reads(&[io::ErrorKind::NotFound]).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_latest_usage_prefers_main_thread() {
+        let content = [
+            r#"{"type":"assistant","isSidechain":false,"message":{"usage":{"input_tokens":100}}}"#,
+            r#"{"type":"user","message":{}}"#,
+            r#"{"type":"assistant","isSidechain":true,"message":{"usage":{"input_tokens":5}}}"#,
+        ]
+        .join("\n");
+
+        let usage = latest_usage(&content).unwrap();
+        assert_eq!(usage.input_tokens, Some(100));
+    }
+
+    #[test]
+    fn test_latest_usage_sidechain_fallback() {
+        // Older transcripts without the flag are treated as main thread
+        let content = [
+            r#"{"type":"assistant","isSidechain":true,"message":{"usage":{"input_tokens":5}}}"#,
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":7}}}"#,
+        ]
+        .join("\n");
+        assert_eq!(latest_usage(&content).unwrap().input_tokens, Some(7));
+
+        // Only sidechain usage available
+        let content =
+            r#"{"type":"assistant","isSidechain":true,"message":{"usage":{"input_tokens":5}}}"#;
+        assert_eq!(latest_usage(content).unwrap().input_tokens, Some(5));
+        assert!(latest_usage("").is_none());
+    }
 }